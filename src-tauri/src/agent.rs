@@ -0,0 +1,473 @@
+// Agent runner - lets a chat model use the workspace file and exec commands
+// as tools, looping until it answers without calling one.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+use std::process::Stdio;
+use std::sync::Mutex;
+use tauri::Manager;
+use tokio::sync::oneshot;
+
+use crate::providers::{
+    self, ChatRequest, Message, ProviderError, ToolCall, ToolDefinition, Transport, Usage,
+};
+use crate::usage::UsageLedger;
+
+const DEFAULT_MAX_STEPS: u32 = 8;
+const MAX_STEPS_LIMIT: u32 = 25;
+
+/// Tool output beyond this is cut off so one large file cannot exhaust the
+/// model's context window.
+const MAX_TOOL_OUTPUT_CHARS: usize = 20_000;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct AgentRequest {
+    #[serde(flatten)]
+    pub chat: ChatRequest,
+    /// Every path the tools touch must resolve inside this directory.
+    pub workspace_root: String,
+    #[serde(default)]
+    pub max_steps: Option<u32>,
+}
+
+/// One entry of the run transcript, emitted as an `agent-step` event.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AgentStep {
+    Assistant {
+        step: u32,
+        content: String,
+        tool_calls: Vec<ToolCall>,
+    },
+    ToolResult {
+        step: u32,
+        call_id: String,
+        name: String,
+        output: String,
+        is_error: bool,
+    },
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct AgentStepEvent {
+    run_id: String,
+    #[serde(flatten)]
+    step: AgentStep,
+}
+
+/// Sent as `agent-approval`; answer with `respond_agent_approval`.
+#[derive(Debug, Clone, Serialize)]
+struct ApprovalRequest {
+    run_id: String,
+    call_id: String,
+    name: String,
+    arguments: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AgentResult {
+    pub content: String,
+    /// `false` when the step limit was reached before a final answer.
+    pub finished: bool,
+    pub steps: Vec<AgentStep>,
+    pub usage: Usage,
+    /// Estimated USD cost over all steps; `None` if any step was unpriced.
+    pub cost: Option<f64>,
+}
+
+/// Tool calls waiting for the user, keyed by `run_id/call_id`.
+#[derive(Default)]
+pub struct AgentApprovals {
+    pending: Mutex<HashMap<String, oneshot::Sender<bool>>>,
+}
+
+impl AgentApprovals {
+    /// Returns `false` when no call with that id is waiting.
+    pub fn respond(&self, run_id: &str, call_id: &str, approved: bool) -> bool {
+        match self.pending.lock().unwrap().remove(&approval_key(run_id, call_id)) {
+            Some(sender) => sender.send(approved).is_ok(),
+            None => false,
+        }
+    }
+
+    async fn wait(&self, key: String) -> bool {
+        let (sender, receiver) = oneshot::channel();
+        self.pending.lock().unwrap().insert(key.clone(), sender);
+
+        // Removes the entry if the run is cancelled while waiting
+        let _guard = PendingGuard {
+            approvals: self,
+            key,
+        };
+        receiver.await.unwrap_or(false)
+    }
+}
+
+struct PendingGuard<'a> {
+    approvals: &'a AgentApprovals,
+    key: String,
+}
+
+impl Drop for PendingGuard<'_> {
+    fn drop(&mut self) {
+        self.approvals.pending.lock().unwrap().remove(&self.key);
+    }
+}
+
+fn approval_key(run_id: &str, call_id: &str) -> String {
+    format!("{}/{}", run_id, call_id)
+}
+
+pub async fn run(
+    window: tauri::Window,
+    transport: Transport,
+    run_id: String,
+    request: AgentRequest,
+) -> Result<AgentResult, ProviderError> {
+    let app_handle = window.app_handle();
+    let root = PathBuf::from(&request.workspace_root);
+    if !root.is_dir() {
+        return Err(ProviderError::bad_request(format!(
+            "Workspace root is not a directory: {}",
+            request.workspace_root
+        )));
+    }
+
+    let max_steps = request
+        .max_steps
+        .unwrap_or(DEFAULT_MAX_STEPS)
+        .clamp(1, MAX_STEPS_LIMIT);
+    let mut chat = request.chat;
+    chat.tools = tool_definitions();
+
+    let mut result = AgentResult {
+        content: String::new(),
+        finished: false,
+        steps: Vec::new(),
+        usage: Usage::default(),
+        cost: Some(0.0),
+    };
+    let record = |result: &mut AgentResult, step: AgentStep| {
+        let _ = window.emit(
+            "agent-step",
+            AgentStepEvent {
+                run_id: run_id.clone(),
+                step: step.clone(),
+            },
+        );
+        result.steps.push(step);
+    };
+
+    for step in 1..=max_steps {
+        let response = providers::chat(&transport, &chat).await?;
+
+        let cost = app_handle
+            .state::<UsageLedger>()
+            .record(&app_handle, &chat.provider, &chat.model, response.usage.as_ref())
+            .ok()
+            .flatten();
+        result.cost = result.cost.zip(cost).map(|(total, cost)| total + cost);
+        if let Some(usage) = &response.usage {
+            result.usage.prompt_tokens += usage.prompt_tokens;
+            result.usage.completion_tokens += usage.completion_tokens;
+        }
+
+        record(
+            &mut result,
+            AgentStep::Assistant {
+                step,
+                content: response.content.clone(),
+                tool_calls: response.tool_calls.clone(),
+            },
+        );
+        result.content = response.content.clone();
+        chat.messages
+            .push(Message::assistant(response.content, response.tool_calls.clone()));
+
+        if response.tool_calls.is_empty() {
+            result.finished = true;
+            return Ok(result);
+        }
+
+        for call in &response.tool_calls {
+            let (output, is_error) = if requires_approval(&call.name) {
+                let _ = window.emit(
+                    "agent-approval",
+                    ApprovalRequest {
+                        run_id: run_id.clone(),
+                        call_id: call.id.clone(),
+                        name: call.name.clone(),
+                        arguments: call.arguments.clone(),
+                    },
+                );
+                let approved = app_handle
+                    .state::<AgentApprovals>()
+                    .wait(approval_key(&run_id, &call.id))
+                    .await;
+                if approved {
+                    run_tool(&root, call).await
+                } else {
+                    ("The user declined this action.".to_string(), true)
+                }
+            } else {
+                run_tool(&root, call).await
+            };
+
+            let output = truncate(output);
+            record(
+                &mut result,
+                AgentStep::ToolResult {
+                    step,
+                    call_id: call.id.clone(),
+                    name: call.name.clone(),
+                    output: output.clone(),
+                    is_error,
+                },
+            );
+            chat.messages.push(Message::tool_result(call, output));
+        }
+    }
+
+    Ok(result)
+}
+
+fn tool_definitions() -> Vec<ToolDefinition> {
+    let path_only = serde_json::json!({
+        "type": "object",
+        "properties": {
+            "path": { "type": "string", "description": "Path relative to the workspace root" }
+        },
+        "required": ["path"]
+    });
+
+    vec![
+        ToolDefinition {
+            name: "read_file".to_string(),
+            description: "Read a text file from the workspace.".to_string(),
+            parameters: path_only.clone(),
+        },
+        ToolDefinition {
+            name: "list_dir".to_string(),
+            description: "List the entries of a workspace directory. Use \".\" for the root."
+                .to_string(),
+            parameters: path_only,
+        },
+        ToolDefinition {
+            name: "write_file".to_string(),
+            description: "Create or overwrite a text file in the workspace. Requires user approval."
+                .to_string(),
+            parameters: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "path": { "type": "string", "description": "Path relative to the workspace root" },
+                    "content": { "type": "string", "description": "Full new file content" }
+                },
+                "required": ["path", "content"]
+            }),
+        },
+        ToolDefinition {
+            name: "execute_command".to_string(),
+            description: "Run a command (program and arguments, no shell) in the workspace root \
+                          and return its output. Requires user approval."
+                .to_string(),
+            parameters: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "command": { "type": "string", "description": "e.g. \"cargo test\"" }
+                },
+                "required": ["command"]
+            }),
+        },
+    ]
+}
+
+fn requires_approval(tool: &str) -> bool {
+    matches!(tool, "write_file" | "execute_command")
+}
+
+/// Returns the tool output and whether it is an error; errors go back to the
+/// model as results so it can correct itself.
+async fn run_tool(root: &Path, call: &ToolCall) -> (String, bool) {
+    let result = match call.name.as_str() {
+        "read_file" => match workspace_path(root, &call.arguments) {
+            Ok(path) => crate::read_file_content(path).await,
+            Err(e) => Err(e),
+        },
+        "list_dir" => match workspace_path(root, &call.arguments) {
+            Ok(path) => crate::read_dir(path).await.map(|entries| {
+                entries
+                    .iter()
+                    .map(|entry| format!("{}{}", entry.name, if entry.is_dir { "/" } else { "" }))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            }),
+            Err(e) => Err(e),
+        },
+        "write_file" => match workspace_path(root, &call.arguments) {
+            Ok(path) => {
+                let content = call.arguments["content"].as_str().unwrap_or_default().to_string();
+                let bytes = content.len();
+                crate::write_file_content(path.clone(), content)
+                    .await
+                    .map(|_| format!("Wrote {} bytes to {}", bytes, path))
+            }
+            Err(e) => Err(e),
+        },
+        "execute_command" => match call.arguments["command"].as_str() {
+            Some(command) => execute_command(root, command).await,
+            None => Err("Missing \"command\" argument".to_string()),
+        },
+        other => Err(format!("Unknown tool: {}", other)),
+    };
+
+    match result {
+        Ok(output) => (output, false),
+        Err(e) => (e, true),
+    }
+}
+
+// Like `execute_code`, but the child is killed when the run is cancelled
+// instead of holding a runtime worker until it exits
+async fn execute_command(root: &Path, command: &str) -> Result<String, String> {
+    let mut parts = command.split_whitespace();
+    let program = parts.next().ok_or("Empty command")?;
+
+    let output = tokio::process::Command::new(program)
+        .args(parts)
+        .current_dir(root)
+        .stdin(Stdio::null())
+        .kill_on_drop(true)
+        .output()
+        .await
+        .map_err(|e| format!("Failed to execute command: {}", e))?;
+
+    Ok(format!(
+        "exit code: {}\nstdout:\n{}\nstderr:\n{}",
+        output.status.code().unwrap_or(-1),
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    ))
+}
+
+/// Resolves the `path` argument against the workspace root, rejecting
+/// anything that would escape it, through symlinks as well.
+fn workspace_path(root: &Path, arguments: &serde_json::Value) -> Result<String, String> {
+    let relative = arguments["path"]
+        .as_str()
+        .ok_or("Missing \"path\" argument")?;
+    let outside = || format!("Path is outside the workspace: {}", relative);
+
+    let root = root
+        .canonicalize()
+        .map_err(|e| format!("Workspace root is not accessible: {}", e))?;
+    let mut resolved = root.clone();
+    for component in Path::new(relative).components() {
+        match component {
+            Component::Normal(part) => resolved.push(part),
+            Component::CurDir => {}
+            Component::ParentDir if resolved != root => {
+                resolved.pop();
+            }
+            _ => return Err(outside()),
+        }
+    }
+
+    // The target may not exist yet (write_file), so resolve the deepest
+    // ancestor that does and check where the links in it really lead
+    let mut existing = resolved.as_path();
+    let mut missing = Vec::new();
+    let real = loop {
+        match existing.canonicalize() {
+            Ok(real) => break real,
+            // A dangling link could point anywhere once written through
+            Err(_) if existing.symlink_metadata().is_ok_and(|meta| meta.is_symlink()) => return Err(outside()),
+            Err(_) => {
+                missing.push(existing.file_name().ok_or_else(outside)?);
+                existing = existing.parent().ok_or_else(outside)?;
+            }
+        }
+    };
+    if !real.starts_with(&root) {
+        return Err(outside());
+    }
+
+    let real = missing.into_iter().rev().fold(real, |path, part| path.join(part));
+    Ok(real.to_string_lossy().to_string())
+}
+
+fn truncate(mut output: String) -> String {
+    if output.len() > MAX_TOOL_OUTPUT_CHARS {
+        let mut end = MAX_TOOL_OUTPUT_CHARS;
+        while !output.is_char_boundary(end) {
+            end -= 1;
+        }
+        output.truncate(end);
+        output.push_str("\n[output truncated]");
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A workspace holding a `src` directory, deleted with the returned guard
+    fn workspace() -> (tempfile::TempDir, PathBuf) {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("src")).unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        (dir, root)
+    }
+
+    fn resolve(root: &Path, path: &str) -> Result<String, String> {
+        workspace_path(root, &serde_json::json!({ "path": path }))
+    }
+
+    #[test]
+    fn paths_inside_the_workspace_resolve() {
+        let (_dir, root) = workspace();
+        let base = root.to_string_lossy().to_string();
+
+        assert_eq!(resolve(&root, ".").unwrap(), base);
+        assert_eq!(resolve(&root, "src").unwrap(), format!("{}/src", base));
+        assert_eq!(resolve(&root, "./src/../src/main.rs").unwrap(), format!("{}/src/main.rs", base));
+        // Files that do not exist yet, for write_file
+        assert_eq!(resolve(&root, "new/dir/file.txt").unwrap(), format!("{}/new/dir/file.txt", base));
+    }
+
+    #[test]
+    fn parent_dirs_and_absolute_paths_are_rejected() {
+        let (_dir, root) = workspace();
+
+        for path in ["..", "../secret", "src/../../secret", "/etc/passwd"] {
+            let error = resolve(&root, path).unwrap_err();
+            assert!(error.contains("outside the workspace"), "{}: {}", path, error);
+        }
+        assert!(workspace_path(&root, &serde_json::json!({})).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_out_of_the_workspace_are_rejected() {
+        use std::os::unix::fs::symlink;
+
+        let (_dir, root) = workspace();
+        let outside = tempfile::tempdir().unwrap();
+        std::fs::write(outside.path().join("id_rsa"), "secret").unwrap();
+
+        symlink(outside.path(), root.join("escape")).unwrap();
+        symlink(outside.path().join("id_rsa"), root.join("key")).unwrap();
+        symlink(outside.path().join("missing"), root.join("dangling")).unwrap();
+        symlink(root.join("src"), root.join("code")).unwrap();
+
+        for path in ["escape", "escape/id_rsa", "escape/new.txt", "key", "dangling"] {
+            let error = resolve(&root, path).unwrap_err();
+            assert!(error.contains("outside the workspace"), "{}: {}", path, error);
+        }
+
+        // Links that stay inside resolve to their target
+        let base = root.to_string_lossy().to_string();
+        assert_eq!(resolve(&root, "code/lib.rs").unwrap(), format!("{}/src/lib.rs", base));
+    }
+}
@@ -0,0 +1,457 @@
+// On-disk cache of chat replies, so identical prompts (MOA layers, skills
+// re-run on the same file) are answered without another provider call.
+// Each reply is one JSON file named after a hash of the request.
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Mutex, RwLock};
+
+use crate::providers::{ChatRequest, ChatResponse, GenerationOptions, Message, ResponseMeta};
+use crate::providers::{StreamSummary, ToolCall, ToolDefinition, Usage};
+use crate::storage;
+
+const CACHE_SETTINGS_FILE: &str = "response_cache.json";
+const CACHE_DIR: &str = "response_cache";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CacheSettings {
+    pub enabled: bool,
+    /// Replies older than this are ignored and eventually deleted.
+    pub ttl_secs: u64,
+    /// The oldest replies are evicted once the cache grows past this.
+    pub max_bytes: u64,
+}
+
+impl Default for CacheSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            ttl_secs: 24 * 60 * 60,
+            max_bytes: 50 * 1024 * 1024,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CacheStats {
+    pub entries: usize,
+    pub bytes: u64,
+}
+
+/// What is kept of a reply; enough to rebuild both a `ChatResponse` and a
+/// `StreamSummary`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedReply {
+    pub content: String,
+    #[serde(default)]
+    pub tool_calls: Vec<ToolCall>,
+    #[serde(default)]
+    pub finish_reason: Option<String>,
+    #[serde(default)]
+    pub usage: Option<Usage>,
+}
+
+impl CachedReply {
+    pub fn into_response(self) -> ChatResponse {
+        ChatResponse {
+            content: self.content,
+            tool_calls: self.tool_calls,
+            usage: self.usage,
+            cost: None,
+            meta: ResponseMeta::cached(),
+        }
+    }
+
+    pub fn into_summary(self) -> StreamSummary {
+        StreamSummary {
+            content: self.content,
+            finish_reason: self.finish_reason,
+            usage: self.usage,
+            cost: None,
+            meta: ResponseMeta::cached(),
+        }
+    }
+}
+
+impl From<&ChatResponse> for CachedReply {
+    fn from(response: &ChatResponse) -> Self {
+        Self {
+            content: response.content.clone(),
+            tool_calls: response.tool_calls.clone(),
+            finish_reason: None,
+            usage: response.usage.clone(),
+        }
+    }
+}
+
+impl From<&StreamSummary> for CachedReply {
+    fn from(summary: &StreamSummary) -> Self {
+        Self {
+            content: summary.content.clone(),
+            tool_calls: Vec::new(),
+            finish_reason: summary.finish_reason.clone(),
+            usage: summary.usage.clone(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct CacheEntry {
+    created_at: i64,
+    reply: CachedReply,
+}
+
+struct IndexEntry {
+    created_at: i64,
+    bytes: u64,
+}
+
+// Everything that can change the reply; keys and headers are left out so a
+// rotated key still hits.
+#[derive(Serialize)]
+struct CacheKey<'a> {
+    provider: &'a str,
+    model: &'a str,
+    base_url: Option<&'a str>,
+    messages: &'a [Message],
+    tools: &'a [ToolDefinition],
+    options: &'a GenerationOptions,
+}
+
+fn cache_key(request: &ChatRequest) -> String {
+    let key = CacheKey {
+        provider: &request.provider,
+        model: &request.model,
+        base_url: request.base_url.as_deref(),
+        messages: &request.messages,
+        tools: &request.tools,
+        options: &request.options,
+    };
+    let bytes = serde_json::to_vec(&key).unwrap_or_default();
+    Sha256::digest(bytes).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+pub struct ResponseCache {
+    settings: RwLock<CacheSettings>,
+    /// `None` when the app data directory is unavailable; the cache then
+    /// never stores anything.
+    dir: Option<PathBuf>,
+    index: Mutex<BTreeMap<String, IndexEntry>>,
+}
+
+impl ResponseCache {
+    pub fn load(app_handle: &tauri::AppHandle) -> Self {
+        let dir = storage::app_data_file(app_handle, CACHE_DIR)
+            .ok()
+            .filter(|dir| fs::create_dir_all(dir).is_ok());
+
+        // Rebuilt from the files themselves, so a crash cannot leave a
+        // separate index out of sync
+        let mut index = BTreeMap::new();
+        for entry in dir.iter().flat_map(|dir| fs::read_dir(dir).into_iter().flatten()).flatten() {
+            let path = entry.path();
+            let Some(key) = path.file_stem().and_then(|s| s.to_str()) else {
+                continue;
+            };
+            let Some(cached) = fs::read_to_string(&path)
+                .ok()
+                .and_then(|text| serde_json::from_str::<CacheEntry>(&text).ok())
+            else {
+                let _ = fs::remove_file(&path);
+                continue;
+            };
+            let bytes = entry.metadata().map(|m| m.len()).unwrap_or(0);
+            index.insert(
+                key.to_string(),
+                IndexEntry {
+                    created_at: cached.created_at,
+                    bytes,
+                },
+            );
+        }
+
+        Self {
+            settings: RwLock::new(storage::load_json(app_handle, CACHE_SETTINGS_FILE)),
+            dir,
+            index: Mutex::new(index),
+        }
+    }
+
+    pub fn settings(&self) -> CacheSettings {
+        self.settings.read().unwrap().clone()
+    }
+
+    pub fn set_settings(&self, app_handle: &tauri::AppHandle, settings: CacheSettings) -> Result<(), String> {
+        storage::save_json(app_handle, CACHE_SETTINGS_FILE, &settings)?;
+        *self.settings.write().unwrap() = settings;
+
+        let mut index = self.index.lock().unwrap();
+        self.evict(&mut index);
+        Ok(())
+    }
+
+    /// The cached reply to `request`, if caching is on and it has not expired.
+    pub fn get(&self, request: &ChatRequest) -> Option<CachedReply> {
+        let settings = self.settings();
+        if !settings.enabled {
+            return None;
+        }
+
+        let key = cache_key(request);
+        let mut index = self.index.lock().unwrap();
+        let created_at = index.get(&key)?.created_at;
+        if Utc::now().timestamp() - created_at >= settings.ttl_secs as i64 {
+            self.remove(&mut index, &key);
+            return None;
+        }
+
+        let path = self.dir.as_ref()?.join(format!("{}.json", key));
+        match fs::read_to_string(path)
+            .ok()
+            .and_then(|text| serde_json::from_str::<CacheEntry>(&text).ok())
+        {
+            Some(entry) => Some(entry.reply),
+            None => {
+                self.remove(&mut index, &key);
+                None
+            }
+        }
+    }
+
+    /// Stores a reply when caching is on; failures only cost a future hit.
+    pub fn put(&self, request: &ChatRequest, reply: CachedReply) {
+        if !self.settings().enabled {
+            return;
+        }
+        let Some(dir) = &self.dir else {
+            return;
+        };
+
+        let entry = CacheEntry {
+            created_at: Utc::now().timestamp(),
+            reply,
+        };
+        let Ok(text) = serde_json::to_string(&entry) else {
+            return;
+        };
+
+        let key = cache_key(request);
+        if fs::write(dir.join(format!("{}.json", key)), &text).is_err() {
+            return;
+        }
+
+        let mut index = self.index.lock().unwrap();
+        index.insert(
+            key,
+            IndexEntry {
+                created_at: entry.created_at,
+                bytes: text.len() as u64,
+            },
+        );
+        self.evict(&mut index);
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        let index = self.index.lock().unwrap();
+        CacheStats {
+            entries: index.len(),
+            bytes: index.values().map(|entry| entry.bytes).sum(),
+        }
+    }
+
+    /// Deletes every cached reply and reports what was freed.
+    pub fn clear(&self) -> CacheStats {
+        let freed = self.stats();
+        let mut index = self.index.lock().unwrap();
+        let keys: Vec<String> = index.keys().cloned().collect();
+        for key in keys {
+            self.remove(&mut index, &key);
+        }
+        freed
+    }
+
+    // Drops expired replies, then the oldest ones until under the size limit
+    fn evict(&self, index: &mut BTreeMap<String, IndexEntry>) {
+        let settings = self.settings();
+        let now = Utc::now().timestamp();
+
+        let mut by_age: Vec<(i64, String)> = index
+            .iter()
+            .map(|(key, entry)| (entry.created_at, key.clone()))
+            .collect();
+        by_age.sort();
+
+        let mut total: u64 = index.values().map(|entry| entry.bytes).sum();
+        for (created_at, key) in by_age {
+            let expired = now - created_at >= settings.ttl_secs as i64;
+            if !expired && total <= settings.max_bytes {
+                break;
+            }
+            total -= index.get(&key).map(|entry| entry.bytes).unwrap_or(0);
+            self.remove(index, &key);
+        }
+    }
+
+    fn remove(&self, index: &mut BTreeMap<String, IndexEntry>, key: &str) {
+        index.remove(key);
+        if let Some(dir) = &self.dir {
+            let _ = fs::remove_file(dir.join(format!("{}.json", key)));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_cache(settings: CacheSettings) -> (tempfile::TempDir, ResponseCache) {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = ResponseCache {
+            settings: RwLock::new(settings),
+            dir: Some(dir.path().to_path_buf()),
+            index: Mutex::new(BTreeMap::new()),
+        };
+        (dir, cache)
+    }
+
+    fn enabled() -> CacheSettings {
+        CacheSettings {
+            enabled: true,
+            ..CacheSettings::default()
+        }
+    }
+
+    fn request(prompt: &str) -> ChatRequest {
+        ChatRequest {
+            provider: "openai".to_string(),
+            model: "gpt-4o".to_string(),
+            messages: vec![Message::user(prompt.to_string())],
+            tools: Vec::new(),
+            options: GenerationOptions::default(),
+            api_key: None,
+            base_url: None,
+            headers: BTreeMap::new(),
+        }
+    }
+
+    fn reply(content: &str) -> CachedReply {
+        CachedReply {
+            content: content.to_string(),
+            tool_calls: Vec::new(),
+            finish_reason: None,
+            usage: None,
+        }
+    }
+
+    // Writes an entry created `age` seconds ago, as `put` would have
+    fn insert(cache: &ResponseCache, key: &str, age: i64, bytes: u64) {
+        let entry = CacheEntry {
+            created_at: Utc::now().timestamp() - age,
+            reply: reply(key),
+        };
+        let path = cache.dir.as_ref().unwrap().join(format!("{}.json", key));
+        fs::write(path, serde_json::to_string(&entry).unwrap()).unwrap();
+        cache.index.lock().unwrap().insert(
+            key.to_string(),
+            IndexEntry {
+                created_at: entry.created_at,
+                bytes,
+            },
+        );
+    }
+
+    fn keys(cache: &ResponseCache) -> Vec<String> {
+        cache.index.lock().unwrap().keys().cloned().collect()
+    }
+
+    #[test]
+    fn keys_cover_what_changes_the_reply_but_not_credentials() {
+        let base = cache_key(&request("hello"));
+        assert_eq!(base.len(), 64);
+        assert!(base.chars().all(|c| c.is_ascii_hexdigit()));
+
+        let mut rotated = request("hello");
+        rotated.api_key = Some("sk-new".to_string());
+        rotated.headers.insert("x-team".to_string(), "a".to_string());
+        assert_eq!(cache_key(&rotated), base);
+
+        let mut model = request("hello");
+        model.model = "gpt-4o-mini".to_string();
+        let mut options = request("hello");
+        options.options.temperature = Some(0.2);
+        let mut endpoint = request("hello");
+        endpoint.base_url = Some("http://localhost:8080".to_string());
+        for changed in [request("hello!"), model, options, endpoint] {
+            assert_ne!(cache_key(&changed), base);
+        }
+    }
+
+    #[test]
+    fn replies_are_only_stored_and_served_while_enabled() {
+        let (_dir, cache) = temp_cache(CacheSettings::default());
+        cache.put(&request("hello"), reply("hi"));
+        assert_eq!(cache.stats().entries, 0);
+
+        *cache.settings.write().unwrap() = enabled();
+        cache.put(&request("hello"), reply("hi"));
+        assert_eq!(cache.get(&request("hello")).unwrap().content, "hi");
+        assert!(cache.get(&request("bye")).is_none());
+
+        cache.settings.write().unwrap().enabled = false;
+        assert!(cache.get(&request("hello")).is_none());
+    }
+
+    #[test]
+    fn expired_replies_are_not_served_and_get_deleted() {
+        let (dir, cache) = temp_cache(CacheSettings {
+            ttl_secs: 60,
+            ..enabled()
+        });
+        let key = cache_key(&request("hello"));
+        insert(&cache, &key, 61, 10);
+
+        assert!(cache.get(&request("hello")).is_none());
+        assert!(keys(&cache).is_empty());
+        assert!(!dir.path().join(format!("{}.json", key)).exists());
+    }
+
+    #[test]
+    fn eviction_drops_expired_then_oldest_replies_until_under_the_limit() {
+        let (dir, cache) = temp_cache(CacheSettings {
+            ttl_secs: 1000,
+            max_bytes: 25,
+            ..enabled()
+        });
+        insert(&cache, "expired", 2000, 1);
+        insert(&cache, "oldest", 300, 10);
+        insert(&cache, "older", 200, 10);
+        insert(&cache, "newest", 100, 10);
+
+        let mut index = cache.index.lock().unwrap();
+        cache.evict(&mut index);
+        drop(index);
+
+        assert_eq!(keys(&cache), ["newest", "older"]);
+        assert!(!dir.path().join("oldest.json").exists());
+        assert!(!dir.path().join("expired.json").exists());
+        assert_eq!(cache.stats().bytes, 20);
+    }
+
+    #[test]
+    fn clearing_reports_and_deletes_everything() {
+        let (dir, cache) = temp_cache(enabled());
+        insert(&cache, "a", 0, 10);
+        insert(&cache, "b", 0, 5);
+
+        let freed = cache.clear();
+
+        assert_eq!((freed.entries, freed.bytes), (2, 15));
+        assert_eq!(cache.stats().entries, 0);
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+}
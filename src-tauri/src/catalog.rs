@@ -0,0 +1,88 @@
+// Model catalogs fetched from each provider's models endpoint, cached on
+// disk so model pickers do not query every provider each time they open.
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+use crate::providers::ModelInfo;
+use crate::storage;
+
+const CATALOG_FILE: &str = "models_cache.json";
+
+/// How long a fetched catalog is served before it is refreshed.
+const CATALOG_TTL_SECS: i64 = 24 * 60 * 60;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelCatalog {
+    pub provider: String,
+    pub models: Vec<ModelInfo>,
+    /// Unix timestamp (seconds) of the fetch.
+    pub fetched_at: i64,
+    /// Set when the provider could not be reached and an expired copy is
+    /// returned instead.
+    #[serde(default)]
+    pub stale: bool,
+}
+
+impl ModelCatalog {
+    pub fn new(provider: &str, models: Vec<ModelInfo>) -> Self {
+        Self {
+            provider: provider.to_string(),
+            models,
+            fetched_at: Utc::now().timestamp(),
+            stale: false,
+        }
+    }
+
+    fn is_fresh(&self) -> bool {
+        Utc::now().timestamp() - self.fetched_at < CATALOG_TTL_SECS
+    }
+}
+
+pub struct CatalogCache {
+    catalogs: Mutex<BTreeMap<String, ModelCatalog>>,
+}
+
+impl CatalogCache {
+    pub fn load(app_handle: &tauri::AppHandle) -> Self {
+        let catalogs: BTreeMap<String, ModelCatalog> = storage::load_json(app_handle, CATALOG_FILE);
+        Self {
+            catalogs: Mutex::new(catalogs),
+        }
+    }
+
+    /// The cached catalog if it is still within its TTL.
+    pub fn fresh(&self, provider: &str) -> Option<ModelCatalog> {
+        self.catalogs
+            .lock()
+            .unwrap()
+            .get(provider)
+            .filter(|catalog| catalog.is_fresh())
+            .cloned()
+    }
+
+    /// The cached catalog regardless of age, marked stale once expired.
+    pub fn cached(&self, provider: &str) -> Option<ModelCatalog> {
+        self.catalogs.lock().unwrap().get(provider).map(|catalog| ModelCatalog {
+            stale: !catalog.is_fresh(),
+            ..catalog.clone()
+        })
+    }
+
+    pub fn store(&self, app_handle: &tauri::AppHandle, catalog: ModelCatalog) -> Result<(), String> {
+        let mut catalogs = self.catalogs.lock().unwrap();
+        catalogs.insert(catalog.provider.clone(), catalog);
+        storage::save_json(app_handle, CATALOG_FILE, &*catalogs)
+    }
+
+    /// Drops a provider's catalog, e.g. after its key changed.
+    pub fn invalidate(&self, app_handle: &tauri::AppHandle, provider: &str) -> Result<(), String> {
+        let mut catalogs = self.catalogs.lock().unwrap();
+        if catalogs.remove(provider).is_some() {
+            storage::save_json(app_handle, CATALOG_FILE, &*catalogs)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,444 @@
+// Chat history kept in the app data directory, one JSON file per
+// conversation, so it survives restarts and can be searched and exported.
+
+use chrono::{TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use crate::providers::{Message, Usage};
+use crate::storage;
+
+const CONVERSATIONS_DIR: &str = "conversations";
+
+/// Used until the first user message supplies a title.
+const UNTITLED: &str = "New conversation";
+const TITLE_CHARS: usize = 60;
+const SNIPPET_CHARS: usize = 120;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredMessage {
+    #[serde(flatten)]
+    pub message: Message,
+    /// Unix timestamp (seconds).
+    pub created_at: i64,
+    /// Tokens spent on this reply, for assistant messages.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<Usage>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Conversation {
+    pub id: String,
+    pub title: String,
+    /// Provider and model of the latest reply.
+    pub provider: String,
+    pub model: String,
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub messages: Vec<StoredMessage>,
+    /// Sum over all replies.
+    #[serde(default)]
+    pub usage: Usage,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConversationSummary {
+    pub id: String,
+    pub title: String,
+    pub provider: String,
+    pub model: String,
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub message_count: usize,
+    pub usage: Usage,
+}
+
+impl From<&Conversation> for ConversationSummary {
+    fn from(conversation: &Conversation) -> Self {
+        Self {
+            id: conversation.id.clone(),
+            title: conversation.title.clone(),
+            provider: conversation.provider.clone(),
+            model: conversation.model.clone(),
+            created_at: conversation.created_at,
+            updated_at: conversation.updated_at,
+            message_count: conversation.messages.len(),
+            usage: conversation.usage.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchHit {
+    pub conversation: ConversationSummary,
+    /// Index of the first matching message; `None` when only the title matched.
+    pub message_index: Option<usize>,
+    pub snippet: String,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    Markdown,
+    Json,
+}
+
+pub struct ConversationStore {
+    /// `None` when the app data directory is unavailable.
+    dir: Option<PathBuf>,
+    summaries: Mutex<BTreeMap<String, ConversationSummary>>,
+    /// Held across read-modify-write so concurrent appends are not lost.
+    updates: Mutex<()>,
+}
+
+impl ConversationStore {
+    pub fn load(app_handle: &tauri::AppHandle) -> Self {
+        let dir = storage::app_data_file(app_handle, CONVERSATIONS_DIR)
+            .ok()
+            .filter(|dir| fs::create_dir_all(dir).is_ok());
+
+        let summaries = dir
+            .iter()
+            .flat_map(|dir| fs::read_dir(dir).into_iter().flatten())
+            .flatten()
+            .filter_map(|entry| fs::read_to_string(entry.path()).ok())
+            .filter_map(|text| serde_json::from_str::<Conversation>(&text).ok())
+            .map(|conversation| (conversation.id.clone(), ConversationSummary::from(&conversation)))
+            .collect();
+
+        Self {
+            dir,
+            summaries: Mutex::new(summaries),
+            updates: Mutex::new(()),
+        }
+    }
+
+    /// Most recently updated first.
+    pub fn list(&self) -> Vec<ConversationSummary> {
+        let mut summaries: Vec<ConversationSummary> = self.summaries.lock().unwrap().values().cloned().collect();
+        summaries.sort_by_key(|summary| std::cmp::Reverse(summary.updated_at));
+        summaries
+    }
+
+    pub fn create(&self, provider: &str, model: &str, title: Option<String>) -> Result<Conversation, String> {
+        let now = Utc::now().timestamp();
+        let conversation = Conversation {
+            id: format!("{}-{:08x}", Utc::now().timestamp_millis(), rand::random::<u32>()),
+            title: title
+                .map(|t| t.trim().to_string())
+                .filter(|t| !t.is_empty())
+                .unwrap_or_else(|| UNTITLED.to_string()),
+            provider: provider.to_string(),
+            model: model.to_string(),
+            created_at: now,
+            updated_at: now,
+            messages: Vec::new(),
+            usage: Usage::default(),
+        };
+        self.write(&conversation)?;
+        Ok(conversation)
+    }
+
+    pub fn get(&self, id: &str) -> Result<Conversation, String> {
+        let text = fs::read_to_string(self.path(id)?).map_err(|_| format!("Conversation not found: {}", id))?;
+        serde_json::from_str(&text).map_err(|e| format!("Failed to parse conversation {}: {}", id, e))
+    }
+
+    /// Adds messages to the end; `usage` belongs to the last one (the reply).
+    pub fn append(
+        &self,
+        id: &str,
+        messages: Vec<Message>,
+        provider: Option<String>,
+        model: Option<String>,
+        usage: Option<Usage>,
+    ) -> Result<ConversationSummary, String> {
+        let _update = self.updates.lock().unwrap();
+        let mut conversation = self.get(id)?;
+        let now = Utc::now().timestamp();
+
+        if conversation.title == UNTITLED {
+            if let Some(first) = messages.iter().find(|m| m.role == "user") {
+                conversation.title = title_from(&first.content.text());
+            }
+        }
+        if let Some(usage) = &usage {
+            conversation.usage.prompt_tokens += usage.prompt_tokens;
+            conversation.usage.completion_tokens += usage.completion_tokens;
+        }
+        conversation.provider = provider.unwrap_or(conversation.provider);
+        conversation.model = model.unwrap_or(conversation.model);
+        conversation.updated_at = now;
+
+        let last = messages.len().saturating_sub(1);
+        conversation
+            .messages
+            .extend(messages.into_iter().enumerate().map(|(i, message)| StoredMessage {
+                message,
+                created_at: now,
+                usage: if i == last { usage.clone() } else { None },
+            }));
+
+        self.write(&conversation)?;
+        Ok(ConversationSummary::from(&conversation))
+    }
+
+    pub fn rename(&self, id: &str, title: &str) -> Result<ConversationSummary, String> {
+        let title = title.trim();
+        if title.is_empty() {
+            return Err("Conversation title is required".to_string());
+        }
+
+        let _update = self.updates.lock().unwrap();
+        let mut conversation = self.get(id)?;
+        conversation.title = title.to_string();
+        self.write(&conversation)?;
+        Ok(ConversationSummary::from(&conversation))
+    }
+
+    pub fn delete(&self, id: &str) -> Result<(), String> {
+        let path = self.path(id)?;
+        let _update = self.updates.lock().unwrap();
+        if path.exists() {
+            fs::remove_file(path).map_err(|e| format!("Failed to delete conversation: {}", e))?;
+        }
+        self.summaries.lock().unwrap().remove(id);
+        Ok(())
+    }
+
+    /// Case-insensitive match on titles and message text, newest first.
+    pub fn search(&self, query: &str) -> Vec<SearchHit> {
+        let query: String = lowercase(query.trim()).collect();
+        if query.is_empty() {
+            return Vec::new();
+        }
+
+        self.list()
+            .into_iter()
+            .filter_map(|summary| {
+                let conversation = self.get(&summary.id).ok()?;
+                let matched = conversation.messages.iter().enumerate().find_map(|(i, stored)| {
+                    let text = stored.message.content.text();
+                    snippet(&text, &query).map(|snippet| (i, snippet))
+                });
+
+                match matched {
+                    Some((i, snippet)) => Some(SearchHit {
+                        conversation: summary,
+                        message_index: Some(i),
+                        snippet,
+                    }),
+                    None if lowercase(&summary.title).collect::<String>().contains(&query) => Some(SearchHit {
+                        snippet: summary.title.clone(),
+                        conversation: summary,
+                        message_index: None,
+                    }),
+                    None => None,
+                }
+            })
+            .collect()
+    }
+
+    pub fn export(&self, id: &str, format: ExportFormat) -> Result<String, String> {
+        let conversation = self.get(id)?;
+        match format {
+            ExportFormat::Json => serde_json::to_string_pretty(&conversation)
+                .map_err(|e| format!("Failed to serialize conversation: {}", e)),
+            ExportFormat::Markdown => Ok(to_markdown(&conversation)),
+        }
+    }
+
+    fn write(&self, conversation: &Conversation) -> Result<(), String> {
+        let text = serde_json::to_string_pretty(conversation)
+            .map_err(|e| format!("Failed to serialize conversation: {}", e))?;
+        fs::write(self.path(&conversation.id)?, text).map_err(|e| format!("Failed to save conversation: {}", e))?;
+
+        self.summaries
+            .lock()
+            .unwrap()
+            .insert(conversation.id.clone(), ConversationSummary::from(conversation));
+        Ok(())
+    }
+
+    // Ids come from the webview, so they must not be able to leave the directory
+    fn path(&self, id: &str) -> Result<PathBuf, String> {
+        if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+            return Err(format!("Invalid conversation id: {}", id));
+        }
+        let dir = self.dir.as_ref().ok_or("Conversation storage is unavailable")?;
+        Ok(dir.join(format!("{}.json", id)))
+    }
+}
+
+fn title_from(text: &str) -> String {
+    let line = text.lines().find(|l| !l.trim().is_empty()).unwrap_or(UNTITLED).trim();
+    if line.chars().count() > TITLE_CHARS {
+        format!("{}…", line.chars().take(TITLE_CHARS).collect::<String>())
+    } else {
+        line.to_string()
+    }
+}
+
+// Lowercased char by char, so a match found in it starts at a char of the
+// original text even where lowercasing changes the number of chars
+fn lowercase(text: &str) -> impl Iterator<Item = char> + '_ {
+    text.chars().flat_map(char::to_lowercase)
+}
+
+// The text around the first match of `query` (already lowercased)
+fn snippet(text: &str, query: &str) -> Option<String> {
+    let at = text.char_indices().position(|(i, _)| {
+        let mut rest = lowercase(&text[i..]);
+        query.chars().all(|c| rest.next() == Some(c))
+    })?;
+    let start = at.saturating_sub(SNIPPET_CHARS / 2);
+    let chars: String = text.chars().skip(start).take(SNIPPET_CHARS).collect();
+    let ellipsis = |cut: bool| if cut { "…" } else { "" };
+
+    Some(format!(
+        "{}{}{}",
+        ellipsis(start > 0),
+        chars.trim(),
+        ellipsis(start + SNIPPET_CHARS < text.chars().count())
+    ))
+}
+
+fn to_markdown(conversation: &Conversation) -> String {
+    let timestamp = |secs: i64| {
+        Utc.timestamp_opt(secs, 0)
+            .single()
+            .map(|t| t.format("%Y-%m-%d %H:%M UTC").to_string())
+            .unwrap_or_default()
+    };
+
+    let mut out = format!(
+        "# {}\n\n_{} · {} · {}_\n",
+        conversation.title,
+        conversation.provider,
+        conversation.model,
+        timestamp(conversation.created_at)
+    );
+    for stored in &conversation.messages {
+        let message = &stored.message;
+        out.push_str(&format!("\n## {}\n\n", message.role));
+
+        let text = message.content.text();
+        if !text.is_empty() {
+            out.push_str(&text);
+            out.push('\n');
+        }
+        let images = message.content.images().len();
+        if images > 0 {
+            out.push_str(&format!("\n_[{} image(s)]_\n", images));
+        }
+        for call in &message.tool_calls {
+            out.push_str(&format!("\n`{}({})`\n", call.name, call.arguments));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A store over a fresh directory, deleted with the returned guard
+    fn temp_store() -> (tempfile::TempDir, ConversationStore) {
+        let dir = tempfile::tempdir().unwrap();
+        let store = ConversationStore {
+            dir: Some(dir.path().to_path_buf()),
+            summaries: Mutex::new(BTreeMap::new()),
+            updates: Mutex::new(()),
+        };
+        (dir, store)
+    }
+
+    fn add_conversation(store: &ConversationStore, title: Option<&str>, messages: &[&str]) -> String {
+        let id = store.create("openai", "gpt-4o", title.map(String::from)).unwrap().id;
+        let messages = messages.iter().map(|text| Message::user(text.to_string())).collect();
+        store.append(&id, messages, None, None, None).unwrap();
+        id
+    }
+
+    #[test]
+    fn titles_come_from_the_first_non_empty_line() {
+        assert_eq!(title_from("\n  \n  Fix the build  \nsecond line"), "Fix the build");
+        assert_eq!(title_from("   "), UNTITLED);
+
+        let long = "é".repeat(TITLE_CHARS + 5);
+        assert_eq!(title_from(&long), format!("{}…", "é".repeat(TITLE_CHARS)));
+        assert_eq!(title_from(&long[..TITLE_CHARS * 2]), "é".repeat(TITLE_CHARS));
+    }
+
+    #[test]
+    fn snippets_center_on_the_match() {
+        assert_eq!(snippet("Hello World", "world").as_deref(), Some("Hello World"));
+        assert_eq!(snippet("Hello World", "mars"), None);
+
+        let text = format!("{}needle{}", "a".repeat(200), "b".repeat(200));
+        let found = snippet(&text, "needle").unwrap();
+        assert!(found.starts_with('…') && found.ends_with('…'), "{}", found);
+        assert_eq!(found.chars().count(), SNIPPET_CHARS + 2);
+        let at = found.find("needle").unwrap();
+        assert_eq!(found[..at].chars().count(), SNIPPET_CHARS / 2 + 1);
+    }
+
+    #[test]
+    fn snippets_stay_aligned_when_lowercasing_adds_chars() {
+        // 'İ' lowercases to two chars, which used to shift the window
+        let text = format!("{}needle{}", "İ".repeat(100), "b".repeat(200));
+        let found = snippet(&text, "needle").unwrap();
+        let at = found.find("needle").unwrap();
+        assert_eq!(found[..at].chars().count(), SNIPPET_CHARS / 2 + 1, "{}", found);
+
+        let query: String = lowercase("İSTAN").collect();
+        assert_eq!(snippet("in İstanbul", &query).as_deref(), Some("in İstanbul"));
+    }
+
+    #[test]
+    fn search_matches_messages_then_titles() {
+        let (_dir, store) = temp_store();
+        let in_message = add_conversation(&store, Some("Rust questions"), &["How do I use TOKIO channels?", "Tokio again"]);
+        let in_title = add_conversation(&store, Some("Tokio notes"), &["unrelated"]);
+        add_conversation(&store, None, &["nothing to see"]);
+
+        let hits = store.search("  tokio ");
+        assert_eq!(hits.len(), 2);
+
+        let hit = hits.iter().find(|hit| hit.conversation.id == in_message).unwrap();
+        assert_eq!(hit.message_index, Some(0));
+        assert_eq!(hit.snippet, "How do I use TOKIO channels?");
+
+        let hit = hits.iter().find(|hit| hit.conversation.id == in_title).unwrap();
+        assert_eq!(hit.message_index, None);
+        assert_eq!(hit.snippet, "Tokio notes");
+
+        assert!(store.search("   ").is_empty());
+    }
+
+    #[test]
+    fn untitled_conversations_are_named_after_the_first_user_message() {
+        let (_dir, store) = temp_store();
+        let id = add_conversation(&store, None, &["\nExplain lifetimes\nin detail"]);
+        assert_eq!(store.get(&id).unwrap().title, "Explain lifetimes");
+    }
+
+    #[test]
+    fn ids_cannot_leave_the_storage_directory() {
+        let (dir, store) = temp_store();
+
+        for id in ["", "../secrets", "a/b", "a.json", "..", "C:\\x", "id with space"] {
+            let error = store.path(id).unwrap_err();
+            assert!(error.contains("Invalid conversation id"), "{:?}: {}", id, error);
+            assert!(store.get(id).is_err());
+            assert!(store.delete(id).is_err());
+        }
+        assert_eq!(
+            store.path("1700000000000-0a1b2c3d").unwrap(),
+            dir.path().join("1700000000000-0a1b2c3d.json")
+        );
+    }
+}
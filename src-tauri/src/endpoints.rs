@@ -0,0 +1,106 @@
+// Named profiles for self-hosted OpenAI-compatible servers (vLLM, LM Studio,
+// llama.cpp server, LocalAI, ...). A profile is addressed as provider
+// `custom:<id>`; its optional key lives in the vault under that same name.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+use crate::storage;
+
+const ENDPOINTS_FILE: &str = "endpoints.json";
+
+pub const CUSTOM_PREFIX: &str = "custom:";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EndpointProfile {
+    /// Derived from the name when saving a new profile.
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    /// Up to and including the version segment, e.g. `http://localhost:8000/v1`.
+    pub base_url: String,
+    /// Sent with every request to this endpoint.
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    #[serde(default)]
+    pub default_model: Option<String>,
+}
+
+impl EndpointProfile {
+    pub fn provider_id(&self) -> String {
+        format!("{}{}", CUSTOM_PREFIX, self.id)
+    }
+}
+
+#[derive(Default)]
+pub struct EndpointStore {
+    profiles: Mutex<BTreeMap<String, EndpointProfile>>,
+}
+
+impl EndpointStore {
+    pub fn load(app_handle: &tauri::AppHandle) -> Self {
+        Self {
+            profiles: Mutex::new(storage::load_json(app_handle, ENDPOINTS_FILE)),
+        }
+    }
+
+    pub fn list(&self) -> Vec<EndpointProfile> {
+        self.profiles.lock().unwrap().values().cloned().collect()
+    }
+
+    /// The profile behind a `custom:<id>` provider id.
+    pub fn for_provider(&self, provider: &str) -> Option<EndpointProfile> {
+        let id = provider.strip_prefix(CUSTOM_PREFIX)?;
+        self.profiles.lock().unwrap().get(id).cloned()
+    }
+
+    pub fn save(&self, app_handle: &tauri::AppHandle, mut profile: EndpointProfile) -> Result<EndpointProfile, String> {
+        profile.name = profile.name.trim().to_string();
+        profile.base_url = profile.base_url.trim().trim_end_matches('/').to_string();
+        if profile.name.is_empty() {
+            return Err("Endpoint name is required".to_string());
+        }
+        if !profile.base_url.starts_with("http://") && !profile.base_url.starts_with("https://") {
+            return Err("Endpoint base URL must start with http:// or https://".to_string());
+        }
+
+        let mut profiles = self.profiles.lock().unwrap();
+        if profile.id.is_empty() {
+            profile.id = unique_id(&profiles, &profile.name);
+        }
+        profiles.insert(profile.id.clone(), profile.clone());
+        storage::save_json(app_handle, ENDPOINTS_FILE, &*profiles)?;
+        Ok(profile)
+    }
+
+    pub fn delete(&self, app_handle: &tauri::AppHandle, id: &str) -> Result<(), String> {
+        let mut profiles = self.profiles.lock().unwrap();
+        if profiles.remove(id).is_some() {
+            storage::save_json(app_handle, ENDPOINTS_FILE, &*profiles)?;
+        }
+        Ok(())
+    }
+}
+
+// `LM Studio` -> `lm-studio`, then `lm-studio-2` if that is taken
+fn unique_id(profiles: &BTreeMap<String, EndpointProfile>, name: &str) -> String {
+    let slug: String = name
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect::<String>()
+        .split('-')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+    let slug = if slug.is_empty() { "endpoint".to_string() } else { slug };
+
+    let mut id = slug.clone();
+    let mut n = 2;
+    while profiles.contains_key(&id) {
+        id = format!("{}-{}", slug, n);
+        n += 1;
+    }
+    id
+}
@@ -1,11 +1,261 @@
 // Prevents additional console window on Windows in release
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod agent;
+mod cache;
+mod catalog;
+mod conversations;
+mod endpoints;
+mod http;
+mod moa;
+mod providers;
+mod registry;
+mod semantic;
+mod storage;
+mod usage;
+mod vault;
+
+use agent::{AgentApprovals, AgentRequest, AgentResult};
+use cache::{CacheSettings, CacheStats, CachedReply, ResponseCache};
+use catalog::{CatalogCache, ModelCatalog};
+use conversations::{Conversation, ConversationStore, ConversationSummary, ExportFormat, SearchHit};
+use endpoints::{EndpointProfile, EndpointStore, CUSTOM_PREFIX};
+use http::{HttpState, NetworkConfig};
+use moa::{MoaRequest, MoaResult, MoaRun, MoaRuns};
+use providers::{
+    ChatRequest, ChatResponse, ContextPolicy, ContextStrategy, ContextTrim, Credentials, EmbedPurpose,
+    KeyValidation, Message, OllamaModelDetails, OllamaStatus, ProviderError, PullProgress, QueueStatus,
+    RateLimiter, RateLimits, RetryPolicy, RunningModel, StreamSummary, Transport, Usage,
+};
+use registry::RequestRegistry;
+use semantic::{IndexProgress, IndexStatus, SearchResult, SemanticIndex};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::Path;
+use std::sync::RwLock;
 use sysinfo::System;
-use tauri::api::path;
+use tauri::Manager;
+use usage::{PriceTable, UsageLedger, UsageReport};
+use vault::{ApiKeyStatus, Vault, VaultStatus};
+
+const RETRY_POLICY_FILE: &str = "retry_policy.json";
+const CONTEXT_POLICY_FILE: &str = "context_policy.json";
+const RATE_LIMITS_FILE: &str = "rate_limits.json";
+const OLLAMA_SETTINGS_FILE: &str = "ollama.json";
+
+struct RetrySettings(RwLock<RetryPolicy>);
+struct ContextSettings(RwLock<ContextPolicy>);
+
+/// The Ollama base URL is not a secret, so it lives outside the vault and
+/// local models keep working while the vault is locked.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct OllamaSettings {
+    base_url: Option<String>,
+}
+
+struct OllamaConfig(RwLock<OllamaSettings>);
+
+// Snapshot of the shared client and retry settings for one provider call
+fn transport(app_handle: &tauri::AppHandle) -> Transport {
+    let http = app_handle.state::<HttpState>();
+    let retry = app_handle.state::<RetrySettings>().0.read().unwrap().clone();
+
+    Transport {
+        client: http.client(),
+        read_timeout: http.config().read_timeout(),
+        retry,
+        limiter: app_handle.state::<RateLimiter>().inner().clone(),
+    }
+}
+
+// Fills in the stored key (or Ollama base URL) so secrets never have to
+// pass through the webview; an explicit key from the caller still wins.
+// Custom endpoints always use the base URL and headers of their profile,
+// and a stored key never follows a base URL the caller chose.
+fn fill_credentials(
+    app_handle: &tauri::AppHandle,
+    provider: &str,
+    api_key: &mut Option<String>,
+    base_url: &mut Option<String>,
+    headers: &mut BTreeMap<String, String>,
+) -> Result<(), ProviderError> {
+    if provider == "ollama" {
+        if base_url.is_none() {
+            *base_url = app_handle.state::<OllamaConfig>().0.read().unwrap().base_url.clone();
+        }
+        return Ok(());
+    }
+
+    let vault = app_handle.state::<Vault>();
+    let stored = |provider: &str| vault.get(provider).map_err(|e| ProviderError::Auth { message: e });
+
+    let custom = provider.starts_with(CUSTOM_PREFIX);
+    if custom {
+        let profile = app_handle
+            .state::<EndpointStore>()
+            .for_provider(provider)
+            .ok_or_else(|| ProviderError::bad_request(format!("Unknown custom endpoint: {}", provider)))?;
+        *base_url = Some(profile.base_url);
+        for (name, value) in profile.headers {
+            headers.entry(name).or_insert(value);
+        }
+
+        // Local servers (LM Studio, vLLM) often take no key; a profile that
+        // never stored one must not fail just because the vault is locked
+        if !vault.providers().iter().any(|stored| stored == provider) {
+            return Ok(());
+        }
+    }
+
+    // A profile's URL was saved together with its key, so it may receive it
+    let caller_base_url = if custom { None } else { base_url.as_deref() };
+    providers::attach_stored_key(provider, api_key, caller_base_url, || stored(provider))
+}
+
+// Older versions kept the Ollama base URL in the vault; moves it to its
+// settings file once the vault is open
+fn migrate_ollama_url(app_handle: &tauri::AppHandle) {
+    let vault = app_handle.state::<Vault>();
+    let Ok(Some(base_url)) = vault.get("ollama") else {
+        return;
+    };
+
+    let config = app_handle.state::<OllamaConfig>();
+    let mut settings = config.0.write().unwrap();
+    if settings.base_url.is_none() {
+        let migrated = OllamaSettings {
+            base_url: Some(base_url),
+        };
+        if storage::save_json(app_handle, OLLAMA_SETTINGS_FILE, &migrated).is_err() {
+            return;
+        }
+        *settings = migrated;
+    }
+    let _ = vault.delete(app_handle, "ollama");
+}
+
+// Credentials for calls outside a chat, filled from the vault and profiles
+fn stored_credentials(app_handle: &tauri::AppHandle, provider: &str) -> Result<Credentials, ProviderError> {
+    let mut credentials = Credentials {
+        provider: provider.to_string(),
+        api_key: None,
+        base_url: None,
+        headers: BTreeMap::new(),
+    };
+    fill_credentials(
+        app_handle,
+        provider,
+        &mut credentials.api_key,
+        &mut credentials.base_url,
+        &mut credentials.headers,
+    )?;
+    Ok(credentials)
+}
+
+// The stored Ollama base URL unless the caller passes one (e.g. from the
+// settings form before it is saved)
+fn ollama_credentials(app_handle: &tauri::AppHandle, base_url: Option<String>) -> Result<Credentials, ProviderError> {
+    let mut credentials = Credentials {
+        provider: "ollama".to_string(),
+        api_key: None,
+        base_url: base_url.filter(|u| !u.trim().is_empty()),
+        headers: BTreeMap::new(),
+    };
+    fill_credentials(
+        app_handle,
+        "ollama",
+        &mut credentials.api_key,
+        &mut credentials.base_url,
+        &mut credentials.headers,
+    )?;
+    Ok(credentials)
+}
+
+fn embedding_model(provider: &str, model: Option<String>) -> Result<String, ProviderError> {
+    model
+        .filter(|m| !m.trim().is_empty())
+        .or_else(|| providers::default_embedding_model(provider).map(String::from))
+        .ok_or_else(|| ProviderError::bad_request(format!("Choose an embedding model for {}", provider)))
+}
+
+// Trims the conversation to the model's context window per the saved policy.
+// The window comes from the model catalog when the provider reported one;
+// a model of unknown size is sent as it is rather than cut to a guess.
+async fn fit_to_context(
+    app_handle: &tauri::AppHandle,
+    transport: &Transport,
+    request: &mut ChatRequest,
+) -> Result<Option<ContextTrim>, ProviderError> {
+    let policy = app_handle.state::<ContextSettings>().0.read().unwrap().clone();
+    let window = app_handle
+        .state::<CatalogCache>()
+        .cached(&request.provider)
+        .and_then(|catalog| catalog.models.into_iter().find(|m| m.id == request.model))
+        .and_then(|model| model.context_window)
+        .or_else(|| providers::context_window(&request.model));
+    let Some(window) = window else {
+        return Ok(None);
+    };
+
+    let summarizer = match (&policy.strategy, &policy.summary_provider) {
+        (ContextStrategy::Summarize, Some(provider)) if *provider != request.provider => {
+            let summarizer = ChatRequest {
+                provider: provider.clone(),
+                model: policy.summary_model.clone().unwrap_or_default(),
+                messages: Vec::new(),
+                tools: Vec::new(),
+                options: Default::default(),
+                api_key: None,
+                base_url: None,
+                headers: BTreeMap::new(),
+            };
+            Some(prepare_request(app_handle, summarizer))
+        }
+        (ContextStrategy::Summarize, _) => Some(Ok(ChatRequest {
+            model: policy.summary_model.clone().unwrap_or_else(|| request.model.clone()),
+            messages: Vec::new(),
+            tools: Vec::new(),
+            options: Default::default(),
+            ..request.clone()
+        })),
+        _ => None,
+    };
+    let (summarizer, unavailable) = match summarizer {
+        Some(Ok(summarizer)) => (Some(summarizer), None),
+        Some(Err(error)) => (None, Some(error)),
+        None => (None, None),
+    };
+    let summary_model = summarizer.as_ref().map(|s| (s.provider.clone(), s.model.clone()));
+
+    let mut trim = providers::fit_context(transport, request, &policy, window, summarizer).await?;
+    if let Some(trim) = trim.as_mut() {
+        // The summary is a paid call like any other
+        if let (Some((provider, model)), Some(usage)) = (&summary_model, &trim.summary_usage) {
+            let _ = app_handle
+                .state::<UsageLedger>()
+                .record(app_handle, provider, model, Some(usage));
+        }
+        if trim.summarized_messages == 0 && trim.summary_error.is_none() {
+            trim.summary_error = unavailable;
+        }
+    }
+    Ok(trim)
+}
+
+// Adds stored credentials and inlines image attachments read from disk
+fn prepare_request(app_handle: &tauri::AppHandle, mut request: ChatRequest) -> Result<ChatRequest, ProviderError> {
+    fill_credentials(
+        app_handle,
+        &request.provider,
+        &mut request.api_key,
+        &mut request.base_url,
+        &mut request.headers,
+    )?;
+    providers::inline_images(&mut request)?;
+    Ok(request)
+}
 
 #[derive(Debug, Serialize, Deserialize)]
 struct FileEntry {
@@ -27,10 +277,31 @@ struct SystemStats {
     disk_percent: f32,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct Message {
-    role: String,
-    content: String,
+#[derive(Debug, Clone, Serialize)]
+struct ChatDeltaEvent {
+    request_id: String,
+    delta: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ChatDoneEvent {
+    request_id: String,
+    #[serde(flatten)]
+    summary: StreamSummary,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct IndexProgressEvent {
+    request_id: String,
+    #[serde(flatten)]
+    progress: IndexProgress,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct OllamaPullEvent {
+    request_id: String,
+    #[serde(flatten)]
+    progress: PullProgress,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -67,10 +338,13 @@ async fn get_system_stats() -> Result<SystemStats, String> {
 }
 
 #[tauri::command]
-async fn clean_cache() -> Result<String, String> {
-    // Simple cache cleaning - just return success message
-    // In production, this would clean temp files
-    Ok("Cache cleaned successfully".to_string())
+async fn clean_cache(cache: tauri::State<'_, ResponseCache>) -> Result<String, String> {
+    let freed = cache.clear();
+    Ok(format!(
+        "Cache cleaned successfully ({} cached responses, {:.1} MB freed)",
+        freed.entries,
+        freed.bytes as f64 / (1024.0 * 1024.0)
+    ))
 }
 
 #[tauri::command]
@@ -80,351 +354,776 @@ async fn optimize_memory() -> Result<String, String> {
 }
 
 #[tauri::command]
-async fn call_openai_api(
-    api_key: String,
-    model: String,
-    messages: Vec<Message>,
-) -> Result<String, String> {
-    let client = reqwest::Client::new();
-    
-    let payload = serde_json::json!({
-        "model": model,
-        "messages": messages,
-        "temperature": 0.7,
-    });
-    
-    let response = client
-        .post("https://api.openai.com/v1/chat/completions")
-        .header("Authorization", format!("Bearer {}", api_key))
-        .header("Content-Type", "application/json")
-        .json(&payload)
-        .send()
+async fn chat(
+    app_handle: tauri::AppHandle,
+    registry: tauri::State<'_, RequestRegistry>,
+    request_id: Option<String>,
+    request: ChatRequest,
+    bypass_cache: Option<bool>,
+) -> Result<ChatResponse, ProviderError> {
+    let request_id = request_id.unwrap_or_else(|| registry.next_id());
+    let transport = transport(&app_handle);
+    let request = prepare_request(&app_handle, request)?;
+
+    registry
+        .run(request_id, async move {
+            complete_chat(&app_handle, &transport, request, bypass_cache.unwrap_or(false)).await
+        })
         .await
-        .map_err(|e| format!("Request failed: {}", e))?;
-    
-    if !response.status().is_success() {
-        let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
-        return Err(format!("API error: {}", error_text));
+}
+
+// One non-streaming chat the way the `chat` command makes it: trimmed to the
+// context window, answered from the response cache when possible and
+// recorded in the usage ledger otherwise. MOA runs go through here too.
+async fn complete_chat(
+    app_handle: &tauri::AppHandle,
+    transport: &Transport,
+    mut request: ChatRequest,
+    bypass_cache: bool,
+) -> Result<ChatResponse, ProviderError> {
+    let context = fit_to_context(app_handle, transport, &mut request).await?;
+
+    // Cache hits cost nothing, so they skip the usage ledger
+    let cache = app_handle.state::<ResponseCache>();
+    let cached = if bypass_cache { None } else { cache.get(&request) };
+    if let Some(reply) = cached {
+        let mut response = reply.into_response();
+        response.meta.context = context;
+        return Ok(response);
     }
-    
-    let json: serde_json::Value = response.json().await.map_err(|e| format!("Failed to parse response: {}", e))?;
-    
-    let content = json["choices"][0]["message"]["content"]
-        .as_str()
-        .ok_or("Invalid response format")?
-        .to_string();
-    
-    Ok(content)
+
+    let mut response = providers::chat(transport, &request).await?;
+    cache.put(&request, CachedReply::from(&response));
+    response.meta.context = context;
+
+    response.cost = app_handle
+        .state::<UsageLedger>()
+        .record(app_handle, &request.provider, &request.model, response.usage.as_ref())
+        .ok()
+        .flatten();
+
+    Ok(response)
 }
 
+// Streams the reply as `chat-delta` events, followed by one `chat-done` event;
+// a cached reply arrives as a single delta
 #[tauri::command]
-async fn call_claude_api(
-    api_key: String,
-    model: String,
-    messages: Vec<Message>,
-) -> Result<String, String> {
-    let client = reqwest::Client::new();
-    
-    let payload = serde_json::json!({
-        "model": model,
-        "messages": messages,
-        "max_tokens": 4096,
-    });
-    
-    let response = client
-        .post("https://api.anthropic.com/v1/messages")
-        .header("x-api-key", api_key)
-        .header("anthropic-version", "2023-06-01")
-        .header("Content-Type", "application/json")
-        .json(&payload)
-        .send()
+async fn chat_stream(
+    window: tauri::Window,
+    registry: tauri::State<'_, RequestRegistry>,
+    request_id: String,
+    request: ChatRequest,
+    bypass_cache: Option<bool>,
+) -> Result<StreamSummary, ProviderError> {
+    let id = request_id.clone();
+    let app_handle = window.app_handle();
+    let transport = transport(&app_handle);
+    let mut request = prepare_request(&app_handle, request)?;
+
+    registry
+        .run(id, async move {
+            let context = fit_to_context(&app_handle, &transport, &mut request).await?;
+            let emit_delta = |delta: &str| {
+                let _ = window.emit(
+                    "chat-delta",
+                    ChatDeltaEvent {
+                        request_id: request_id.clone(),
+                        delta: delta.to_string(),
+                    },
+                );
+            };
+
+            let cache = app_handle.state::<ResponseCache>();
+            let cached = if bypass_cache.unwrap_or(false) { None } else { cache.get(&request) };
+            let mut summary = match cached {
+                Some(reply) => {
+                    let summary = reply.into_summary();
+                    emit_delta(&summary.content);
+                    summary
+                }
+                None => {
+                    let mut summary = providers::chat_stream(&transport, &request, emit_delta).await?;
+                    cache.put(&request, CachedReply::from(&summary));
+                    summary.cost = app_handle
+                        .state::<UsageLedger>()
+                        .record(&app_handle, &request.provider, &request.model, summary.usage.as_ref())
+                        .ok()
+                        .flatten();
+                    summary
+                }
+            };
+            summary.meta.context = context;
+
+            let _ = window.emit(
+                "chat-done",
+                ChatDoneEvent {
+                    request_id,
+                    summary: summary.clone(),
+                },
+            );
+
+            Ok(summary)
+        })
         .await
-        .map_err(|e| format!("Request failed: {}", e))?;
-    
-    if !response.status().is_success() {
-        let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
-        return Err(format!("API error: {}", error_text));
-    }
-    
-    let json: serde_json::Value = response.json().await.map_err(|e| format!("Failed to parse response: {}", e))?;
-    
-    let content = json["content"][0]["text"]
-        .as_str()
-        .ok_or("Invalid response format")?
-        .to_string();
-    
-    Ok(content)
 }
 
+// Runs the workspace agent, emitting `agent-step` events as it goes and an
+// `agent-approval` event before any write or command; cancel it with
+// `cancel_request` using the same run id
 #[tauri::command]
-async fn call_openrouter_api(
-    api_key: String,
-    model: String,
-    messages: Vec<Message>,
-) -> Result<String, String> {
-    let client = reqwest::Client::new();
-    
-    let payload = serde_json::json!({
-        "model": model,
-        "messages": messages,
-    });
-    
-    let response = client
-        .post("https://openrouter.ai/api/v1/chat/completions")
-        .header("Authorization", format!("Bearer {}", api_key))
-        .header("Content-Type", "application/json")
-        .header("HTTP-Referer", "https://bonzo-devassist.app")
-        .header("X-Title", "BONZO DevAssist AI")
-        .json(&payload)
-        .send()
+async fn run_agent(
+    window: tauri::Window,
+    registry: tauri::State<'_, RequestRegistry>,
+    run_id: String,
+    request: AgentRequest,
+) -> Result<AgentResult, ProviderError> {
+    let app_handle = window.app_handle();
+    let transport = transport(&app_handle);
+    let mut request = request;
+    request.chat = prepare_request(&app_handle, request.chat)?;
+
+    registry
+        .run(run_id.clone(), agent::run(window, transport, run_id, request))
         .await
-        .map_err(|e| format!("Request failed: {}", e))?;
-    
-    if !response.status().is_success() {
-        let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
-        return Err(format!("API error: {}", error_text));
+}
+
+// Answers an `agent-approval` event; returns false if the call is no longer waiting
+#[tauri::command]
+async fn respond_agent_approval(
+    approvals: tauri::State<'_, AgentApprovals>,
+    run_id: String,
+    call_id: String,
+    approved: bool,
+) -> Result<bool, String> {
+    Ok(approvals.respond(&run_id, &call_id, approved))
+}
+
+// Runs a mixture-of-agents request, emitting `moa-member` as each model
+// answers and `moa-done` with the final state. The run does not depend on
+// the panel that started it; `get_moa_run` reports it again after a reload.
+// Cancel it with `cancel_request` using the same run id.
+#[tauri::command]
+async fn run_moa(
+    window: tauri::Window,
+    registry: tauri::State<'_, RequestRegistry>,
+    runs: tauri::State<'_, MoaRuns>,
+    run_id: String,
+    request: MoaRequest,
+) -> Result<MoaResult, ProviderError> {
+    let app_handle = window.app_handle();
+    let transport = transport(&app_handle);
+    let mut request = request;
+    for member in request.members.iter_mut().chain(request.aggregator.as_mut()) {
+        fill_credentials(
+            &app_handle,
+            &member.provider,
+            &mut member.api_key,
+            &mut member.base_url,
+            &mut member.headers,
+        )?;
     }
-    
-    let json: serde_json::Value = response.json().await.map_err(|e| format!("Failed to parse response: {}", e))?;
-    
-    let content = json["choices"][0]["message"]["content"]
-        .as_str()
-        .ok_or("Invalid response format")?
-        .to_string();
-    
-    Ok(content)
+
+    let outcome = registry
+        .run(run_id.clone(), moa::run(window.clone(), transport, run_id.clone(), request))
+        .await;
+    if let Some(run) = runs.finish(&run_id, &outcome) {
+        let _ = window.emit("moa-done", run);
+    }
+    outcome
 }
 
+// A MOA run started by `run_moa`, finished or not; `None` once it has been
+// forgotten
 #[tauri::command]
-async fn call_gemini_api(
-    api_key: String,
-    model: String,
-    messages: Vec<Message>,
-) -> Result<String, String> {
-    let client = reqwest::Client::new();
-    
-    // Convert messages to Gemini format
-    let mut contents = vec![];
-    for msg in messages {
-        contents.push(serde_json::json!({
-            "role": if msg.role == "assistant" { "model" } else { "user" },
-            "parts": [{ "text": msg.content }]
-        }));
+async fn get_moa_run(runs: tauri::State<'_, MoaRuns>, run_id: String) -> Result<Option<MoaRun>, String> {
+    Ok(runs.get(&run_id))
+}
+
+// Checks a key with the provider's cheapest authenticated call; uses the
+// stored key unless one is passed in (e.g. from the settings form)
+#[tauri::command]
+async fn validate_provider_key(
+    app_handle: tauri::AppHandle,
+    provider: String,
+    api_key: Option<String>,
+    base_url: Option<String>,
+) -> Result<KeyValidation, String> {
+    let mut credentials = Credentials {
+        provider,
+        api_key: api_key.filter(|k| !k.trim().is_empty()),
+        base_url: base_url.filter(|u| !u.trim().is_empty()),
+        headers: BTreeMap::new(),
+    };
+    let transport = transport(&app_handle);
+
+    if let Err(error) = fill_credentials(
+        &app_handle,
+        &credentials.provider,
+        &mut credentials.api_key,
+        &mut credentials.base_url,
+        &mut credentials.headers,
+    ) {
+        return Ok(KeyValidation {
+            provider: credentials.provider,
+            valid: false,
+            error: Some(error),
+            models: Vec::new(),
+        });
     }
-    
-    let payload = serde_json::json!({
-        "contents": contents,
-    });
-    
-    let url = format!(
-        "https://generativelanguage.googleapis.com/v1beta/models/{}:generateContent?key={}",
-        model, api_key
-    );
-    
-    let response = client
-        .post(&url)
-        .header("Content-Type", "application/json")
-        .json(&payload)
-        .send()
-        .await
-        .map_err(|e| format!("Request failed: {}", e))?;
-    
-    if !response.status().is_success() {
-        let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
-        return Err(format!("API error: {}", error_text));
+
+    Ok(providers::validate_key(&transport, &credentials).await)
+}
+
+// Normalized model list for a provider, served from the disk cache until
+// its TTL expires; `refresh` forces a fetch. Falls back to an expired copy
+// (flagged `stale`) when the provider cannot be reached.
+#[tauri::command]
+async fn list_models(
+    app_handle: tauri::AppHandle,
+    catalogs: tauri::State<'_, CatalogCache>,
+    ledger: tauri::State<'_, UsageLedger>,
+    provider: String,
+    refresh: Option<bool>,
+) -> Result<ModelCatalog, ProviderError> {
+    if !refresh.unwrap_or(false) {
+        if let Some(catalog) = catalogs.fresh(&provider) {
+            return Ok(catalog);
+        }
     }
-    
-    let json: serde_json::Value = response.json().await.map_err(|e| format!("Failed to parse response: {}", e))?;
-    
-    let content = json["candidates"][0]["content"]["parts"][0]["text"]
-        .as_str()
-        .ok_or("Invalid response format")?
-        .to_string();
-    
-    Ok(content)
+
+    let credentials = stored_credentials(&app_handle, &provider)?;
+
+    let mut models = match providers::list_models(&transport(&app_handle), &credentials).await {
+        Ok(models) => models,
+        Err(error) => return catalogs.cached(&provider).ok_or(error),
+    };
+
+    // Providers that do not publish prices get them from the local table
+    let prices = ledger.prices();
+    for model in models.iter_mut().filter(|m| m.pricing.is_none()) {
+        model.pricing = prices.price(&provider, &model.id).cloned();
+    }
+
+    let catalog = ModelCatalog::new(&provider, models);
+    let _ = catalogs.store(&app_handle, catalog.clone());
+    Ok(catalog)
 }
 
+// One vector per text; `model` defaults to the provider's usual embedding
+// model and `purpose` to `document`
 #[tauri::command]
-async fn call_mistral_api(
-    api_key: String,
-    model: String,
-    messages: Vec<Message>,
-) -> Result<String, String> {
-    let client = reqwest::Client::new();
-    
-    let payload = serde_json::json!({
-        "model": model,
-        "messages": messages,
-    });
-    
-    let response = client
-        .post("https://api.mistral.ai/v1/chat/completions")
-        .header("Authorization", format!("Bearer {}", api_key))
-        .header("Content-Type", "application/json")
-        .json(&payload)
-        .send()
+async fn embed_texts(
+    app_handle: tauri::AppHandle,
+    provider: String,
+    model: Option<String>,
+    texts: Vec<String>,
+    purpose: Option<EmbedPurpose>,
+) -> Result<Vec<Vec<f32>>, ProviderError> {
+    let model = embedding_model(&provider, model)?;
+    let credentials = stored_credentials(&app_handle, &provider)?;
+    providers::embed(&transport(&app_handle), &credentials, &model, &texts, purpose.unwrap_or_default()).await
+}
+
+// Embeds the project's source files for `semantic_search`, emitting
+// `index-progress` events; unchanged files keep their vectors. Cancel it with
+// `cancel_request` using the same request id.
+#[tauri::command]
+async fn index_project(
+    window: tauri::Window,
+    registry: tauri::State<'_, RequestRegistry>,
+    request_id: Option<String>,
+    root: String,
+    provider: String,
+    model: Option<String>,
+) -> Result<IndexStatus, ProviderError> {
+    let request_id = request_id.unwrap_or_else(|| registry.next_id());
+    let app_handle = window.app_handle();
+    let model = embedding_model(&provider, model)?;
+    let credentials = stored_credentials(&app_handle, &provider)?;
+    let transport = transport(&app_handle);
+
+    registry
+        .run(request_id.clone(), async move {
+            let emit_progress = |progress| {
+                let _ = window.emit(
+                    "index-progress",
+                    IndexProgressEvent {
+                        request_id: request_id.clone(),
+                        progress,
+                    },
+                );
+            };
+            app_handle
+                .state::<SemanticIndex>()
+                .index(&transport, &credentials, &model, &root, emit_progress)
+                .await
+        })
         .await
-        .map_err(|e| format!("Request failed: {}", e))?;
-    
-    if !response.status().is_success() {
-        let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
-        return Err(format!("API error: {}", error_text));
+}
+
+// Loads the saved index of a project for searching; `None` if it was never indexed
+#[tauri::command]
+async fn open_project_index(index: tauri::State<'_, SemanticIndex>, root: String) -> Result<Option<IndexStatus>, String> {
+    index.open(&root)
+}
+
+#[tauri::command]
+async fn clear_project_index(index: tauri::State<'_, SemanticIndex>, root: String) -> Result<(), String> {
+    index.clear(&root)
+}
+
+// Snippets of the current project closest in meaning to `query`, best first
+#[tauri::command]
+async fn semantic_search(
+    app_handle: tauri::AppHandle,
+    index: tauri::State<'_, SemanticIndex>,
+    query: String,
+    k: Option<usize>,
+) -> Result<Vec<SearchResult>, ProviderError> {
+    let (provider, model) = index
+        .embedding_model()
+        .ok_or_else(|| ProviderError::bad_request("No project has been indexed yet"))?;
+    let credentials = stored_credentials(&app_handle, &provider)?;
+    let vectors = providers::embed(
+        &transport(&app_handle),
+        &credentials,
+        &model,
+        &[query],
+        EmbedPurpose::Query,
+    )
+    .await?;
+
+    let query = vectors.into_iter().next().unwrap_or_default();
+    index.search(&query, k.unwrap_or(semantic::DEFAULT_RESULTS))
+}
+
+// Aborts an in-flight chat; the pending command rejects with a `cancelled` error
+#[tauri::command]
+async fn cancel_request(
+    registry: tauri::State<'_, RequestRegistry>,
+    request_id: String,
+) -> Result<bool, String> {
+    Ok(registry.cancel(&request_id))
+}
+
+#[tauri::command]
+async fn get_retry_policy(retry: tauri::State<'_, RetrySettings>) -> Result<RetryPolicy, String> {
+    Ok(retry.0.read().unwrap().clone())
+}
+
+#[tauri::command]
+async fn set_retry_policy(
+    app_handle: tauri::AppHandle,
+    retry: tauri::State<'_, RetrySettings>,
+    policy: RetryPolicy,
+) -> Result<(), String> {
+    if policy.max_attempts == 0 {
+        return Err("max_attempts must be at least 1".to_string());
     }
-    
-    let json: serde_json::Value = response.json().await.map_err(|e| format!("Failed to parse response: {}", e))?;
-    
-    let content = json["choices"][0]["message"]["content"]
-        .as_str()
-        .ok_or("Invalid response format")?
-        .to_string();
-    
-    Ok(content)
+    storage::save_json(&app_handle, RETRY_POLICY_FILE, &policy)?;
+    *retry.0.write().unwrap() = policy;
+    Ok(())
 }
 
 #[tauri::command]
-async fn call_cohere_api(
-    api_key: String,
-    model: String,
-    messages: Vec<Message>,
-) -> Result<String, String> {
-    let client = reqwest::Client::new();
-    
-    // Get the last user message
-    let message = messages.last()
-        .ok_or("No messages provided")?
-        .content.clone();
-    
-    // Build chat history (exclude last message)
-    let chat_history: Vec<_> = messages[..messages.len()-1]
-        .iter()
-        .map(|msg| serde_json::json!({
-            "role": if msg.role == "assistant" { "CHATBOT" } else { "USER" },
-            "message": msg.content
-        }))
-        .collect();
-    
-    let payload = serde_json::json!({
-        "model": model,
-        "message": message,
-        "chat_history": chat_history,
-    });
-    
-    let response = client
-        .post("https://api.cohere.ai/v1/chat")
-        .header("Authorization", format!("Bearer {}", api_key))
-        .header("Content-Type", "application/json")
-        .json(&payload)
-        .send()
-        .await
-        .map_err(|e| format!("Request failed: {}", e))?;
-    
-    if !response.status().is_success() {
-        let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
-        return Err(format!("API error: {}", error_text));
+async fn get_rate_limits(limiter: tauri::State<'_, RateLimiter>) -> Result<RateLimits, String> {
+    Ok(limiter.limits())
+}
+
+// Takes effect immediately, including for calls already waiting
+#[tauri::command]
+async fn set_rate_limits(
+    app_handle: tauri::AppHandle,
+    limiter: tauri::State<'_, RateLimiter>,
+    limits: RateLimits,
+) -> Result<(), String> {
+    for (provider, provider_limits) in &limits {
+        provider_limits.validate().map_err(|e| format!("{}: {}", provider, e))?;
     }
-    
-    let json: serde_json::Value = response.json().await.map_err(|e| format!("Failed to parse response: {}", e))?;
-    
-    let content = json["text"]
-        .as_str()
-        .ok_or("Invalid response format")?
-        .to_string();
-    
-    Ok(content)
+    storage::save_json(&app_handle, RATE_LIMITS_FILE, &limits)?;
+    limiter.set_limits(limits);
+    Ok(())
 }
 
+// Calls queued and in flight per provider, for showing why a request waits
 #[tauri::command]
-async fn call_ollama_api(
-    base_url: String,
+async fn get_rate_limit_status(limiter: tauri::State<'_, RateLimiter>) -> Result<Vec<QueueStatus>, String> {
+    Ok(limiter.status())
+}
+
+#[tauri::command]
+async fn get_context_policy(context: tauri::State<'_, ContextSettings>) -> Result<ContextPolicy, String> {
+    Ok(context.0.read().unwrap().clone())
+}
+
+#[tauri::command]
+async fn set_context_policy(
+    app_handle: tauri::AppHandle,
+    context: tauri::State<'_, ContextSettings>,
+    policy: ContextPolicy,
+) -> Result<(), String> {
+    if policy.summary_provider.is_some() && policy.summary_model.is_none() {
+        return Err("summary_model is required when summary_provider is set".to_string());
+    }
+    storage::save_json(&app_handle, CONTEXT_POLICY_FILE, &policy)?;
+    *context.0.write().unwrap() = policy;
+    Ok(())
+}
+
+#[tauri::command]
+async fn get_cache_settings(cache: tauri::State<'_, ResponseCache>) -> Result<CacheSettings, String> {
+    Ok(cache.settings())
+}
+
+#[tauri::command]
+async fn set_cache_settings(
+    app_handle: tauri::AppHandle,
+    cache: tauri::State<'_, ResponseCache>,
+    settings: CacheSettings,
+) -> Result<(), String> {
+    if settings.ttl_secs == 0 {
+        return Err("ttl_secs must be at least 1".to_string());
+    }
+    cache.set_settings(&app_handle, settings)
+}
+
+#[tauri::command]
+async fn get_ai_cache_stats(cache: tauri::State<'_, ResponseCache>) -> Result<CacheStats, String> {
+    Ok(cache.stats())
+}
+
+// Deletes every cached response and reports what was freed
+#[tauri::command]
+async fn clear_ai_cache(cache: tauri::State<'_, ResponseCache>) -> Result<CacheStats, String> {
+    Ok(cache.clear())
+}
+
+#[tauri::command]
+async fn create_conversation(
+    conversations: tauri::State<'_, ConversationStore>,
+    provider: String,
     model: String,
+    title: Option<String>,
+) -> Result<Conversation, String> {
+    conversations.create(&provider, &model, title)
+}
+
+#[tauri::command]
+async fn list_conversations(
+    conversations: tauri::State<'_, ConversationStore>,
+) -> Result<Vec<ConversationSummary>, String> {
+    Ok(conversations.list())
+}
+
+#[tauri::command]
+async fn load_conversation(conversations: tauri::State<'_, ConversationStore>, id: String) -> Result<Conversation, String> {
+    conversations.get(&id)
+}
+
+// Records a turn; `usage` is that of the reply, which comes last in `messages`
+#[tauri::command]
+async fn append_conversation_messages(
+    conversations: tauri::State<'_, ConversationStore>,
+    id: String,
     messages: Vec<Message>,
+    provider: Option<String>,
+    model: Option<String>,
+    usage: Option<Usage>,
+) -> Result<ConversationSummary, String> {
+    conversations.append(&id, messages, provider, model, usage)
+}
+
+#[tauri::command]
+async fn rename_conversation(
+    conversations: tauri::State<'_, ConversationStore>,
+    id: String,
+    title: String,
+) -> Result<ConversationSummary, String> {
+    conversations.rename(&id, &title)
+}
+
+#[tauri::command]
+async fn delete_conversation(conversations: tauri::State<'_, ConversationStore>, id: String) -> Result<(), String> {
+    conversations.delete(&id)
+}
+
+#[tauri::command]
+async fn search_conversations(
+    conversations: tauri::State<'_, ConversationStore>,
+    query: String,
+) -> Result<Vec<SearchHit>, String> {
+    Ok(conversations.search(&query))
+}
+
+// Returns the exported text, and also writes it to `path` when one is given
+#[tauri::command]
+async fn export_conversation(
+    conversations: tauri::State<'_, ConversationStore>,
+    id: String,
+    format: ExportFormat,
+    path: Option<String>,
 ) -> Result<String, String> {
-    let client = reqwest::Client::new();
-    
-    let payload = serde_json::json!({
-        "model": model,
-        "messages": messages,
-        "stream": false,
-    });
-    
-    let url = format!("{}/api/chat", base_url);
-    
-    let response = client
-        .post(&url)
-        .header("Content-Type", "application/json")
-        .json(&payload)
-        .send()
-        .await
-        .map_err(|e| format!("Request failed: {}", e))?;
-    
-    if !response.status().is_success() {
-        let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
-        return Err(format!("API error: {}", error_text));
+    let text = conversations.export(&id, format)?;
+    if let Some(path) = path {
+        fs::write(&path, &text).map_err(|e| format!("Failed to write export: {}", e))?;
     }
-    
-    let json: serde_json::Value = response.json().await.map_err(|e| format!("Failed to parse response: {}", e))?;
-    
-    let content = json["message"]["content"]
-        .as_str()
-        .ok_or("Invalid response format")?
-        .to_string();
-    
-    Ok(content)
+    Ok(text)
 }
 
 #[tauri::command]
-async fn get_ollama_models(base_url: String) -> Result<Vec<String>, String> {
-    let client = reqwest::Client::new();
-    
-    let url = format!("{}/api/tags", base_url);
-    
-    let response = client
-        .get(&url)
-        .send()
+async fn get_usage_report(
+    ledger: tauri::State<'_, UsageLedger>,
+    days: Option<u32>,
+) -> Result<UsageReport, String> {
+    Ok(ledger.report(days))
+}
+
+#[tauri::command]
+async fn get_price_table(ledger: tauri::State<'_, UsageLedger>) -> Result<PriceTable, String> {
+    Ok(ledger.prices())
+}
+
+#[tauri::command]
+async fn set_price_table(
+    app_handle: tauri::AppHandle,
+    ledger: tauri::State<'_, UsageLedger>,
+    prices: PriceTable,
+) -> Result<(), String> {
+    ledger.set_prices(&app_handle, prices)
+}
+
+#[tauri::command]
+async fn get_network_config(http: tauri::State<'_, HttpState>) -> Result<NetworkConfig, String> {
+    Ok(http.config())
+}
+
+// Rebuilds the shared client; an invalid proxy or CA bundle is rejected
+// before anything is saved
+#[tauri::command]
+async fn set_network_config(
+    app_handle: tauri::AppHandle,
+    http: tauri::State<'_, HttpState>,
+    config: NetworkConfig,
+) -> Result<(), String> {
+    http.update(&app_handle, config)
+}
+
+#[tauri::command]
+async fn get_ollama_models(app_handle: tauri::AppHandle, base_url: String) -> Result<Vec<String>, String> {
+    let credentials = ollama_credentials(&app_handle, Some(base_url)).map_err(|e| e.to_string())?;
+    let models = providers::list_models(&transport(&app_handle), &credentials)
         .await
-        .map_err(|e| format!("Request failed: {}", e))?;
-    
-    if !response.status().is_success() {
-        return Err("Failed to get models from Ollama".to_string());
-    }
-    
-    let json: serde_json::Value = response.json().await.map_err(|e| format!("Failed to parse response: {}", e))?;
-    
-    let models = json["models"]
-        .as_array()
-        .ok_or("Invalid response format")?
-        .iter()
-        .filter_map(|m| m["name"].as_str().map(String::from))
-        .collect();
-    
-    Ok(models)
+        .map_err(|e| e.to_string())?;
+    Ok(models.into_iter().map(|model| model.id).collect())
 }
 
+// Whether Ollama answers at the base URL, its version, and whether `model`
+// is installed; problems are reported in the status rather than as errors
 #[tauri::command]
-async fn save_api_key(app_handle: tauri::AppHandle, provider: String, key: String) -> Result<(), String> {
-    let app_dir = path::app_data_dir(&app_handle.config())
-        .ok_or("Failed to get app data directory")?;
-    
-    fs::create_dir_all(&app_dir).map_err(|e| format!("Failed to create app directory: {}", e))?;
-    
-    let key_file = app_dir.join(format!("{}_api_key.txt", provider));
-    fs::write(key_file, key).map_err(|e| format!("Failed to save API key: {}", e))?;
-    
+async fn ollama_status(
+    app_handle: tauri::AppHandle,
+    base_url: Option<String>,
+    model: Option<String>,
+) -> Result<OllamaStatus, ProviderError> {
+    let credentials = ollama_credentials(&app_handle, base_url)?;
+    Ok(providers::ollama_status(&transport(&app_handle), &credentials, model.as_deref()).await)
+}
+
+// Downloads a model, emitting `ollama-pull-progress` events; cancel it with
+// `cancel_request` using the same request id
+#[tauri::command]
+async fn pull_ollama_model(
+    window: tauri::Window,
+    registry: tauri::State<'_, RequestRegistry>,
+    request_id: Option<String>,
+    name: String,
+    base_url: Option<String>,
+) -> Result<(), ProviderError> {
+    let request_id = request_id.unwrap_or_else(|| registry.next_id());
+    let app_handle = window.app_handle();
+    let credentials = ollama_credentials(&app_handle, base_url)?;
+    let transport = transport(&app_handle);
+
+    registry
+        .run(request_id.clone(), async move {
+            let emit_progress = |progress| {
+                let _ = window.emit(
+                    "ollama-pull-progress",
+                    OllamaPullEvent {
+                        request_id: request_id.clone(),
+                        progress,
+                    },
+                );
+            };
+            providers::pull_ollama_model(&transport, &credentials, &name, emit_progress).await?;
+            let _ = app_handle.state::<CatalogCache>().invalidate(&app_handle, "ollama");
+            Ok(())
+        })
+        .await
+}
+
+#[tauri::command]
+async fn delete_ollama_model(
+    app_handle: tauri::AppHandle,
+    catalogs: tauri::State<'_, CatalogCache>,
+    name: String,
+    base_url: Option<String>,
+) -> Result<(), ProviderError> {
+    let credentials = ollama_credentials(&app_handle, base_url)?;
+    providers::delete_ollama_model(&transport(&app_handle), &credentials, &name).await?;
+    let _ = catalogs.invalidate(&app_handle, "ollama");
     Ok(())
 }
 
+// Parameters, prompt template and context size of an installed model
 #[tauri::command]
-async fn get_api_key(app_handle: tauri::AppHandle, provider: String) -> Result<String, String> {
-    let app_dir = path::app_data_dir(&app_handle.config())
-        .ok_or("Failed to get app data directory")?;
-    
-    let key_file = app_dir.join(format!("{}_api_key.txt", provider));
-    
-    if !key_file.exists() {
-        return Ok(String::new());
+async fn show_ollama_model(
+    app_handle: tauri::AppHandle,
+    name: String,
+    base_url: Option<String>,
+) -> Result<OllamaModelDetails, ProviderError> {
+    let credentials = ollama_credentials(&app_handle, base_url)?;
+    providers::show_ollama_model(&transport(&app_handle), &credentials, &name).await
+}
+
+// Models currently loaded into memory and how much of each sits in VRAM
+#[tauri::command]
+async fn list_running_ollama_models(
+    app_handle: tauri::AppHandle,
+    base_url: Option<String>,
+) -> Result<Vec<RunningModel>, ProviderError> {
+    let credentials = ollama_credentials(&app_handle, base_url)?;
+    providers::running_ollama_models(&transport(&app_handle), &credentials).await
+}
+
+#[tauri::command]
+async fn get_ollama_base_url(config: tauri::State<'_, OllamaConfig>) -> Result<Option<String>, String> {
+    Ok(config.0.read().unwrap().base_url.clone())
+}
+
+// An empty URL goes back to the default local server
+#[tauri::command]
+async fn set_ollama_base_url(
+    app_handle: tauri::AppHandle,
+    config: tauri::State<'_, OllamaConfig>,
+    catalogs: tauri::State<'_, CatalogCache>,
+    base_url: String,
+) -> Result<(), String> {
+    let settings = OllamaSettings {
+        base_url: Some(base_url.trim().to_string()).filter(|url| !url.is_empty()),
+    };
+    storage::save_json(&app_handle, OLLAMA_SETTINGS_FILE, &settings)?;
+    *config.0.write().unwrap() = settings;
+    // Another server has other models
+    catalogs.invalidate(&app_handle, "ollama")
+}
+
+#[tauri::command]
+async fn save_api_key(
+    app_handle: tauri::AppHandle,
+    vault: tauri::State<'_, Vault>,
+    catalogs: tauri::State<'_, CatalogCache>,
+    provider: String,
+    key: String,
+) -> Result<(), String> {
+    // A different key may see a different set of models
+    catalogs.invalidate(&app_handle, &provider)?;
+    if key.trim().is_empty() {
+        return vault.delete(&app_handle, &provider);
     }
-    
-    fs::read_to_string(key_file).map_err(|e| format!("Failed to read API key: {}", e))
+    vault.set(&app_handle, &provider, key.trim())
+}
+
+// Whether a key is stored, with a masked hint; the secret itself stays here
+#[tauri::command]
+async fn get_api_key_status(vault: tauri::State<'_, Vault>, provider: String) -> Result<ApiKeyStatus, String> {
+    Ok(vault.key_status(&provider))
+}
+
+#[tauri::command]
+async fn delete_api_key(
+    app_handle: tauri::AppHandle,
+    vault: tauri::State<'_, Vault>,
+    catalogs: tauri::State<'_, CatalogCache>,
+    provider: String,
+) -> Result<(), String> {
+    catalogs.invalidate(&app_handle, &provider)?;
+    vault.delete(&app_handle, &provider)
+}
+
+#[tauri::command]
+async fn list_endpoint_profiles(endpoints: tauri::State<'_, EndpointStore>) -> Result<Vec<EndpointProfile>, String> {
+    Ok(endpoints.list())
+}
+
+// Creates the profile when it has no id yet; returns it with the id filled in
+#[tauri::command]
+async fn save_endpoint_profile(
+    app_handle: tauri::AppHandle,
+    endpoints: tauri::State<'_, EndpointStore>,
+    catalogs: tauri::State<'_, CatalogCache>,
+    profile: EndpointProfile,
+) -> Result<EndpointProfile, String> {
+    let profile = endpoints.save(&app_handle, profile)?;
+    // The base URL or headers may have changed which models are served
+    catalogs.invalidate(&app_handle, &profile.provider_id())?;
+    Ok(profile)
+}
+
+// Removes the profile together with its stored key and cached models
+#[tauri::command]
+async fn delete_endpoint_profile(
+    app_handle: tauri::AppHandle,
+    endpoints: tauri::State<'_, EndpointStore>,
+    vault: tauri::State<'_, Vault>,
+    catalogs: tauri::State<'_, CatalogCache>,
+    id: String,
+) -> Result<(), String> {
+    let provider = format!("{}{}", CUSTOM_PREFIX, id);
+    endpoints.delete(&app_handle, &id)?;
+    catalogs.invalidate(&app_handle, &provider)?;
+    vault.delete(&app_handle, &provider)
+}
+
+// Providers with a stored key; readable while the vault is locked
+#[tauri::command]
+async fn list_api_key_providers(vault: tauri::State<'_, Vault>) -> Result<Vec<String>, String> {
+    Ok(vault.providers())
+}
+
+#[tauri::command]
+async fn vault_status(vault: tauri::State<'_, Vault>) -> Result<VaultStatus, String> {
+    Ok(vault.status())
+}
+
+// Without a password this opens (or creates) the machine-bound vault
+#[tauri::command]
+async fn unlock_vault(
+    app_handle: tauri::AppHandle,
+    vault: tauri::State<'_, Vault>,
+    password: Option<String>,
+) -> Result<VaultStatus, String> {
+    vault.unlock(&app_handle, password.as_deref())?;
+    migrate_ollama_url(&app_handle);
+    Ok(vault.status())
+}
+
+#[tauri::command]
+async fn lock_vault(vault: tauri::State<'_, Vault>) -> Result<VaultStatus, String> {
+    vault.lock();
+    Ok(vault.status())
+}
+
+// Sets a master password, or switches back to the machine key when `None`
+#[tauri::command]
+async fn set_vault_password(
+    app_handle: tauri::AppHandle,
+    vault: tauri::State<'_, Vault>,
+    password: Option<String>,
+) -> Result<VaultStatus, String> {
+    let password = password.filter(|p| !p.is_empty());
+    vault.change_password(&app_handle, password.as_deref())?;
+    Ok(vault.status())
 }
 
 #[tauri::command]
@@ -525,7 +1224,7 @@ async fn read_dir(path_str: String) -> Result<Vec<FileEntry>, String> {
 // Recursive directory reading
 #[tauri::command]
 async fn read_dir_recursive(path_str: String) -> Result<FileEntry, String> {
-    read_dir_tree(&Path::new(&path_str))
+    read_dir_tree(Path::new(&path_str))
 }
 
 fn read_dir_tree(path: &Path) -> Result<FileEntry, String> {
@@ -549,20 +1248,18 @@ fn read_dir_tree(path: &Path) -> Result<FileEntry, String> {
     
     match fs::read_dir(path) {
         Ok(entries) => {
-            for entry in entries {
-                if let Ok(entry) = entry {
-                    let child_path = entry.path();
-                    // Skip hidden files/folders
-                    if let Some(file_name) = child_path.file_name() {
-                        if let Some(name_str) = file_name.to_str() {
-                            if name_str.starts_with('.') {
-                                continue;
-                            }
+            for entry in entries.flatten() {
+                let child_path = entry.path();
+                // Skip hidden files/folders
+                if let Some(file_name) = child_path.file_name() {
+                    if let Some(name_str) = file_name.to_str() {
+                        if name_str.starts_with('.') {
+                            continue;
                         }
                     }
-                    if let Ok(child_entry) = read_dir_tree(&child_path) {
-                        children.push(child_entry);
-                    }
+                }
+                if let Ok(child_entry) = read_dir_tree(&child_path) {
+                    children.push(child_entry);
                 }
             }
         }
@@ -653,20 +1350,98 @@ async fn execute_code(command: String, working_dir: String) -> Result<ExecutionR
 
 fn main() {
     tauri::Builder::default()
+        .manage(RequestRegistry::default())
+        .manage(AgentApprovals::default())
+        .manage(MoaRuns::default())
+        .setup(|app| {
+            let policy: RetryPolicy = storage::load_json(&app.handle(), RETRY_POLICY_FILE);
+            app.manage(RetrySettings(RwLock::new(policy)));
+            let context: ContextPolicy = storage::load_json(&app.handle(), CONTEXT_POLICY_FILE);
+            app.manage(ContextSettings(RwLock::new(context)));
+            let ollama: OllamaSettings = storage::load_json(&app.handle(), OLLAMA_SETTINGS_FILE);
+            app.manage(OllamaConfig(RwLock::new(ollama)));
+            let limits: RateLimits = storage::load_json(&app.handle(), RATE_LIMITS_FILE);
+            app.manage(RateLimiter::new(limits));
+            app.manage(UsageLedger::load(&app.handle()));
+            app.manage(HttpState::load(&app.handle()));
+            app.manage(CatalogCache::load(&app.handle()));
+            app.manage(EndpointStore::load(&app.handle()));
+            app.manage(ResponseCache::load(&app.handle()));
+            app.manage(ConversationStore::load(&app.handle()));
+            app.manage(SemanticIndex::load(&app.handle()));
+
+            // Machine-bound vaults open without user input; password vaults
+            // wait for unlock_vault. Either way legacy key files migrate then.
+            // A failure stays locked and shows up in vault_status.
+            let vault = Vault::load(&app.handle());
+            if vault.status().mode != Some(vault::VaultMode::Password) {
+                let _ = vault.unlock(&app.handle(), None);
+            }
+            app.manage(vault);
+            migrate_ollama_url(&app.handle());
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             get_system_stats,
             clean_cache,
             optimize_memory,
-            call_openai_api,
-            call_claude_api,
-            call_openrouter_api,
-            call_gemini_api,
-            call_mistral_api,
-            call_cohere_api,
-            call_ollama_api,
+            chat,
+            chat_stream,
+            cancel_request,
+            run_agent,
+            respond_agent_approval,
+            run_moa,
+            get_moa_run,
+            validate_provider_key,
+            list_models,
+            embed_texts,
+            index_project,
+            open_project_index,
+            clear_project_index,
+            semantic_search,
+            get_retry_policy,
+            set_retry_policy,
+            get_rate_limits,
+            set_rate_limits,
+            get_rate_limit_status,
+            get_context_policy,
+            set_context_policy,
+            get_cache_settings,
+            set_cache_settings,
+            get_ai_cache_stats,
+            clear_ai_cache,
+            create_conversation,
+            list_conversations,
+            load_conversation,
+            append_conversation_messages,
+            rename_conversation,
+            delete_conversation,
+            search_conversations,
+            export_conversation,
+            get_usage_report,
+            get_price_table,
+            set_price_table,
+            get_network_config,
+            set_network_config,
             get_ollama_models,
+            ollama_status,
+            pull_ollama_model,
+            delete_ollama_model,
+            show_ollama_model,
+            list_running_ollama_models,
+            get_ollama_base_url,
+            set_ollama_base_url,
             save_api_key,
-            get_api_key,
+            get_api_key_status,
+            delete_api_key,
+            list_api_key_providers,
+            list_endpoint_profiles,
+            save_endpoint_profile,
+            delete_endpoint_profile,
+            vault_status,
+            unlock_vault,
+            lock_vault,
+            set_vault_password,
             read_file_content,
             write_file_content,
             execute_command,
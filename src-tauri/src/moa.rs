@@ -0,0 +1,394 @@
+// Mixture-of-agents runs - several models answer one prompt, side by side or
+// as a refinement chain, and an aggregator model combines their answers.
+// Runs live here rather than in the webview so they finish even when the
+// panel is closed or reloaded.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::Manager;
+use tokio::task::JoinSet;
+
+use crate::providers::{ChatRequest, ChatResponse, Message, ProviderError, Transport, Usage};
+
+const DEFAULT_TIMEOUT_SECS: u64 = 120;
+const MAX_TIMEOUT_SECS: u64 = 600;
+
+/// Finished runs kept for `get_moa_run`, so a reloaded panel can still
+/// collect the result of a run it started.
+const KEPT_RUNS: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MoaStrategy {
+    /// Every member answers the prompt at once.
+    Parallel,
+    /// Each member refines the previous answer; stops at the first failure.
+    Sequential,
+    /// Parallel, then the aggregator merges the answers into one.
+    Voting,
+    /// Parallel, each member answering in its own role.
+    Specialized,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct MoaMember {
+    /// Specialized runs: the member's role, e.g. `reviewer`.
+    #[serde(default)]
+    pub role: Option<String>,
+    /// Instructions put ahead of the prompt.
+    #[serde(default)]
+    pub system_prompt: Option<String>,
+    pub provider: String,
+    pub model: String,
+    #[serde(default)]
+    pub api_key: Option<String>,
+    #[serde(default)]
+    pub base_url: Option<String>,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct MoaRequest {
+    pub strategy: MoaStrategy,
+    pub prompt: String,
+    pub members: Vec<MoaMember>,
+    /// Combines the member answers; required for voting.
+    #[serde(default)]
+    pub aggregator: Option<MoaMember>,
+    /// Per-model limit; a member still running after it counts as failed.
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+}
+
+/// One member's answer, emitted as a `moa-member` event once it completes.
+#[derive(Debug, Clone, Serialize)]
+pub struct MemberResult {
+    /// Position in the request's `members`.
+    pub index: usize,
+    pub role: Option<String>,
+    pub provider: String,
+    pub model: String,
+    pub response: Option<String>,
+    pub error: Option<ProviderError>,
+    pub elapsed_ms: u64,
+    pub usage: Option<Usage>,
+    pub cost: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MoaResult {
+    pub strategy: MoaStrategy,
+    /// In request order; a sequential run ends at the first failure.
+    pub members: Vec<MemberResult>,
+    pub aggregated: Option<String>,
+    /// Set when the aggregator failed; the member answers still stand.
+    pub aggregation_error: Option<ProviderError>,
+    /// Estimated USD cost of every call; `None` if any was unpriced.
+    pub cost: Option<f64>,
+}
+
+/// A run as `get_moa_run` reports it; the final state is also sent as the
+/// `moa-done` event.
+#[derive(Debug, Clone, Serialize)]
+pub struct MoaRun {
+    pub run_id: String,
+    pub finished: bool,
+    /// Members answered so far, in the order they completed.
+    pub members: Vec<MemberResult>,
+    pub result: Option<MoaResult>,
+    pub error: Option<ProviderError>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct MemberEvent {
+    run_id: String,
+    #[serde(flatten)]
+    member: MemberResult,
+}
+
+/// Recent runs by id, in the order they started.
+#[derive(Default)]
+pub struct MoaRuns {
+    runs: Mutex<Vec<MoaRun>>,
+}
+
+impl MoaRuns {
+    pub fn get(&self, run_id: &str) -> Option<MoaRun> {
+        self.runs.lock().unwrap().iter().find(|run| run.run_id == run_id).cloned()
+    }
+
+    /// Records how the run ended, cancellation included. Returns `None` for
+    /// a run rejected before it started.
+    pub fn finish(&self, run_id: &str, outcome: &Result<MoaResult, ProviderError>) -> Option<MoaRun> {
+        let mut runs = self.runs.lock().unwrap();
+        let run = runs.iter_mut().find(|run| run.run_id == run_id)?;
+        run.finished = true;
+        match outcome {
+            Ok(result) => run.result = Some(result.clone()),
+            Err(error) => run.error = Some(error.clone()),
+        }
+        Some(run.clone())
+    }
+
+    fn start(&self, run_id: &str) {
+        let mut runs = self.runs.lock().unwrap();
+        runs.retain(|run| run.run_id != run_id);
+
+        // Forget the oldest finished runs past the limit
+        let mut excess = runs.iter().filter(|run| run.finished).count().saturating_sub(KEPT_RUNS);
+        runs.retain(|run| {
+            let drop = excess > 0 && run.finished;
+            excess -= drop as usize;
+            !drop
+        });
+
+        runs.push(MoaRun {
+            run_id: run_id.to_string(),
+            finished: false,
+            members: Vec::new(),
+            result: None,
+            error: None,
+        });
+    }
+
+    fn add_member(&self, run_id: &str, member: &MemberResult) {
+        if let Some(run) = self.runs.lock().unwrap().iter_mut().find(|run| run.run_id == run_id) {
+            run.members.push(member.clone());
+        }
+    }
+}
+
+impl MoaMember {
+    fn request(&self, prompt: &str) -> ChatRequest {
+        let content = match &self.system_prompt {
+            Some(instructions) if !instructions.trim().is_empty() => format!("{}\n\n{}", instructions, prompt),
+            _ => prompt.to_string(),
+        };
+
+        ChatRequest {
+            provider: self.provider.clone(),
+            model: self.model.clone(),
+            messages: vec![Message::user(content)],
+            tools: Vec::new(),
+            options: Default::default(),
+            api_key: self.api_key.clone(),
+            base_url: self.base_url.clone(),
+            headers: self.headers.clone(),
+        }
+    }
+}
+
+/// Runs every member, then the aggregator if there is one. Fails with the
+/// first member's error when none of them answered.
+pub async fn run(
+    window: tauri::Window,
+    transport: Transport,
+    run_id: String,
+    request: MoaRequest,
+) -> Result<MoaResult, ProviderError> {
+    if request.members.is_empty() {
+        return Err(ProviderError::bad_request("Add at least one model to the MOA run"));
+    }
+    if request.strategy == MoaStrategy::Voting && request.aggregator.is_none() {
+        return Err(ProviderError::bad_request("Voting needs an aggregator model"));
+    }
+
+    let app_handle = window.app_handle();
+    let runs = app_handle.state::<MoaRuns>();
+    runs.start(&run_id);
+
+    let transport = Arc::new(transport);
+    let limit = Duration::from_secs(
+        request
+            .timeout_secs
+            .unwrap_or(DEFAULT_TIMEOUT_SECS)
+            .clamp(1, MAX_TIMEOUT_SECS),
+    );
+    let report = |member: &MemberResult| {
+        let _ = window.emit(
+            "moa-member",
+            MemberEvent {
+                run_id: run_id.clone(),
+                member: member.clone(),
+            },
+        );
+        runs.add_member(&run_id, member);
+    };
+
+    let mut members = Vec::with_capacity(request.members.len());
+    if request.strategy == MoaStrategy::Sequential {
+        let mut prompt = request.prompt.clone();
+        for (index, member) in request.members.iter().enumerate() {
+            let started = Instant::now();
+            let outcome = ask(&app_handle, &transport, member.request(&prompt), limit).await;
+            let result = member_result(index, member, outcome, started.elapsed());
+            report(&result);
+
+            let answer = result.response.clone();
+            members.push(result);
+            match answer {
+                Some(answer) => {
+                    prompt = format!(
+                        "Original request: {}\n\nPrevious model's response:\n{}\n\nPlease review, improve, and refine the above response.",
+                        request.prompt, answer
+                    );
+                }
+                None => break,
+            }
+        }
+    } else {
+        // Dropping the set (e.g. when the run is cancelled) aborts the members
+        let mut tasks = JoinSet::new();
+        for (index, member) in request.members.iter().enumerate() {
+            let app_handle = app_handle.clone();
+            let transport = transport.clone();
+            let chat = member.request(&request.prompt);
+            tasks.spawn(async move {
+                let started = Instant::now();
+                let outcome = ask(&app_handle, &transport, chat, limit).await;
+                (index, outcome, started.elapsed())
+            });
+        }
+
+        while let Some(joined) = tasks.join_next().await {
+            let (index, outcome, elapsed) = joined.map_err(|e| ProviderError::Server {
+                status: None,
+                message: format!("MOA member task failed: {}", e),
+            })?;
+            let result = member_result(index, &request.members[index], outcome, elapsed);
+            report(&result);
+            members.push(result);
+        }
+        members.sort_by_key(|member| member.index);
+    }
+
+    if members.iter().all(|member| member.response.is_none()) {
+        return Err(members
+            .into_iter()
+            .find_map(|member| member.error)
+            .unwrap_or_else(|| ProviderError::bad_request("No model answered")));
+    }
+
+    let mut result = MoaResult {
+        strategy: request.strategy,
+        cost: members
+            .iter()
+            .filter(|member| member.response.is_some())
+            .try_fold(0.0, |total, member| Some(total + member.cost?)),
+        members,
+        aggregated: None,
+        aggregation_error: None,
+    };
+
+    if let Some(aggregator) = &request.aggregator {
+        let prompt = aggregation_prompt(&request.prompt, &result.members);
+        match ask(&app_handle, &transport, aggregator.request(&prompt), limit).await {
+            Ok(response) => {
+                result.cost = result.cost.zip(cost_of(&response)).map(|(total, cost)| total + cost);
+                result.aggregated = Some(response.content);
+            }
+            Err(error) => result.aggregation_error = Some(error),
+        }
+    }
+
+    Ok(result)
+}
+
+// One chat call made like the `chat` command's (context fit, response cache,
+// usage ledger), failed as a network error once `limit` passes
+async fn ask(
+    app_handle: &tauri::AppHandle,
+    transport: &Transport,
+    request: ChatRequest,
+    limit: Duration,
+) -> Result<ChatResponse, ProviderError> {
+    let model = request.model.clone();
+    match tokio::time::timeout(limit, crate::complete_chat(app_handle, transport, request, false)).await {
+        Ok(result) => result,
+        Err(_) => Err(ProviderError::Network {
+            message: format!("{} did not answer within {}s", model, limit.as_secs()),
+        }),
+    }
+}
+
+// A cached answer cost nothing this time
+fn cost_of(response: &ChatResponse) -> Option<f64> {
+    if response.meta.cached {
+        Some(0.0)
+    } else {
+        response.cost
+    }
+}
+
+fn member_result(
+    index: usize,
+    member: &MoaMember,
+    outcome: Result<ChatResponse, ProviderError>,
+    elapsed: Duration,
+) -> MemberResult {
+    let mut result = MemberResult {
+        index,
+        role: member.role.clone(),
+        provider: member.provider.clone(),
+        model: member.model.clone(),
+        response: None,
+        error: None,
+        elapsed_ms: elapsed.as_millis() as u64,
+        usage: None,
+        cost: None,
+    };
+
+    match outcome {
+        Ok(response) => {
+            result.cost = cost_of(&response);
+            result.usage = response.usage;
+            result.response = Some(response.content);
+        }
+        Err(error) => result.error = Some(error),
+    }
+    result
+}
+
+fn aggregation_prompt(prompt: &str, members: &[MemberResult]) -> String {
+    let responses = members
+        .iter()
+        .filter_map(|member| Some((member, member.response.as_ref()?)))
+        .enumerate()
+        .map(|(i, (member, response))| {
+            let source = match &member.role {
+                Some(role) => format!("{}: {} - {}", role, member.provider, member.model),
+                None => format!("{} - {}", member.provider, member.model),
+            };
+            format!("**Model {}** ({}):\n{}\n", i + 1, source, response)
+        })
+        .collect::<Vec<_>>()
+        .join("\n---\n\n");
+
+    format!(
+        "Multiple AI models have responded to the following query:
+\"{}\"
+
+Here are their responses:
+
+{}
+Please analyze these responses and provide:
+1. The best consolidated answer combining the strongest points from each
+2. Which model(s) provided the most accurate/helpful information
+3. Any contradictions or disagreements between models
+4. A confidence score (0-100) for your final answer
+
+Format your response as:
+FINAL ANSWER:
+[Your consolidated answer here]
+
+ANALYSIS:
+[Your analysis here]
+
+CONFIDENCE: [0-100]
+",
+        prompt, responses
+    )
+}
@@ -0,0 +1,221 @@
+use super::openai::parse_model_list;
+use super::options::set_opt;
+use super::{
+    ChatRequest, Credentials, LlmProvider, Message, ModelInfo, ProviderError, StreamChunk, ToolCall,
+    Usage,
+};
+
+const DEFAULT_BASE_URL: &str = "https://api.anthropic.com";
+
+/// `max_tokens` is mandatory on the Messages API.
+const DEFAULT_MAX_TOKENS: u32 = 4096;
+
+const JSON_MODE_INSTRUCTION: &str =
+    "Respond only with a single valid JSON object, without any surrounding text or code fences.";
+
+pub struct Anthropic;
+
+impl LlmProvider for Anthropic {
+    fn chat_request(
+        &self,
+        client: &reqwest::Client,
+        request: &ChatRequest,
+        stream: bool,
+    ) -> Result<reqwest::RequestBuilder, ProviderError> {
+        let options = &request.options;
+        let mut payload = serde_json::json!({
+            "model": request.model,
+            "messages": wire_messages(request.conversation()),
+            "max_tokens": options.max_tokens.unwrap_or(DEFAULT_MAX_TOKENS),
+            "stream": stream,
+        });
+        // No native JSON mode, so ask for it in the system prompt
+        let system = match (request.system_prompt(), options.json_mode) {
+            (Some(system), true) => Some(format!("{}\n\n{}", system, JSON_MODE_INSTRUCTION)),
+            (None, true) => Some(JSON_MODE_INSTRUCTION.to_string()),
+            (system, false) => system,
+        };
+        set_opt(&mut payload, "system", system);
+        set_opt(&mut payload, "temperature", options.temperature);
+        set_opt(&mut payload, "top_p", options.top_p);
+        set_opt(&mut payload, "stop_sequences", options.stop());
+        if !request.tools.is_empty() {
+            payload["tools"] = request
+                .tools
+                .iter()
+                .map(|tool| {
+                    serde_json::json!({
+                        "name": tool.name,
+                        "description": tool.description,
+                        "input_schema": tool.parameters,
+                    })
+                })
+                .collect();
+        }
+
+        Ok(client
+            .post(format!("{}/v1/messages", request.base_url(DEFAULT_BASE_URL)))
+            .header("x-api-key", request.api_key()?)
+            .header("anthropic-version", "2023-06-01")
+            .json(&payload))
+    }
+
+    // Text may be split around `tool_use` blocks
+    fn parse_chat_response(&self, json: &serde_json::Value) -> Result<String, ProviderError> {
+        let blocks = json["content"]
+            .as_array()
+            .ok_or_else(|| ProviderError::parse("Invalid response format"))?;
+        let text: Vec<&str> = blocks
+            .iter()
+            .filter(|block| block["type"] == "text")
+            .filter_map(|block| block["text"].as_str())
+            .collect();
+        if text.is_empty() {
+            return Err(ProviderError::parse("Invalid response format"));
+        }
+        Ok(text.join(""))
+    }
+
+    fn parse_tool_calls(&self, json: &serde_json::Value) -> Vec<ToolCall> {
+        json["content"]
+            .as_array()
+            .map(|blocks| {
+                blocks
+                    .iter()
+                    .filter(|block| block["type"] == "tool_use")
+                    .filter_map(|block| {
+                        Some(ToolCall {
+                            id: block["id"].as_str()?.to_string(),
+                            name: block["name"].as_str()?.to_string(),
+                            arguments: block["input"].clone(),
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn parse_usage(&self, json: &serde_json::Value) -> Option<Usage> {
+        let usage = &json["usage"];
+        usage.is_object().then(|| Usage {
+            prompt_tokens: usage["input_tokens"].as_u64().unwrap_or(0),
+            completion_tokens: usage["output_tokens"].as_u64().unwrap_or(0),
+        })
+    }
+
+    fn parse_stream_event(&self, json: &serde_json::Value) -> Result<StreamChunk, ProviderError> {
+        let mut chunk = StreamChunk::default();
+
+        match json["type"].as_str().unwrap_or_default() {
+            "message_start" => {
+                let usage = &json["message"]["usage"];
+                chunk.usage = Some(Usage {
+                    prompt_tokens: usage["input_tokens"].as_u64().unwrap_or(0),
+                    completion_tokens: usage["output_tokens"].as_u64().unwrap_or(0),
+                });
+            }
+            "content_block_delta" => {
+                chunk.delta = json["delta"]["text"].as_str().map(String::from);
+            }
+            "message_delta" => {
+                chunk.finish_reason = json["delta"]["stop_reason"].as_str().map(String::from);
+                chunk.usage = Some(Usage {
+                    prompt_tokens: 0,
+                    completion_tokens: json["usage"]["output_tokens"].as_u64().unwrap_or(0),
+                });
+            }
+            "message_stop" => chunk.done = true,
+            "error" => return Err(ProviderError::from_body(json)),
+            _ => {}
+        }
+
+        Ok(chunk)
+    }
+
+    fn models_request(
+        &self,
+        client: &reqwest::Client,
+        credentials: &Credentials,
+    ) -> Result<reqwest::RequestBuilder, ProviderError> {
+        Ok(client
+            .get(format!("{}/v1/models", credentials.base_url(DEFAULT_BASE_URL)))
+            .header("x-api-key", credentials.api_key()?)
+            .header("anthropic-version", "2023-06-01"))
+    }
+
+    fn parse_models(&self, json: &serde_json::Value) -> Vec<ModelInfo> {
+        parse_model_list(&json["data"], |model| {
+            let mut info = ModelInfo::new(model["id"].as_str()?);
+            if let Some(name) = model["display_name"].as_str() {
+                info.name = name.to_string();
+            }
+            Some(info)
+        })
+    }
+}
+
+/// Tool calls become `tool_use` blocks on the assistant turn, and results
+/// `tool_result` blocks on a user turn. Consecutive results are merged into
+/// one user turn since the API requires user and assistant to alternate.
+fn wire_messages(messages: Vec<&Message>) -> Vec<serde_json::Value> {
+    let mut wire: Vec<serde_json::Value> = Vec::new();
+
+    for message in messages {
+        if message.is_tool_result() {
+            let block = serde_json::json!({
+                "type": "tool_result",
+                "tool_use_id": message.tool_call_id,
+                "content": message.content.text(),
+            });
+            match wire.last_mut() {
+                Some(last) if last["role"] == "user" && last["content"].is_array() => {
+                    if let Some(blocks) = last["content"].as_array_mut() {
+                        blocks.push(block);
+                    }
+                }
+                _ => wire.push(serde_json::json!({ "role": "user", "content": [block] })),
+            }
+        } else if !message.tool_calls.is_empty() {
+            let mut blocks = Vec::new();
+            let text = message.content.text();
+            if !text.is_empty() {
+                blocks.push(serde_json::json!({ "type": "text", "text": text }));
+            }
+            for call in &message.tool_calls {
+                blocks.push(serde_json::json!({
+                    "type": "tool_use",
+                    "id": call.id,
+                    "name": call.name,
+                    "input": call.arguments,
+                }));
+            }
+            wire.push(serde_json::json!({ "role": "assistant", "content": blocks }));
+        } else if message.content.has_images() {
+            // Images first, as Anthropic recommends
+            let mut blocks: Vec<_> = message
+                .content
+                .images()
+                .iter()
+                .map(|image| {
+                    serde_json::json!({
+                        "type": "image",
+                        "source": {
+                            "type": "base64",
+                            "media_type": image.media_type,
+                            "data": image.data,
+                        },
+                    })
+                })
+                .collect();
+            let text = message.content.text();
+            if !text.is_empty() {
+                blocks.push(serde_json::json!({ "type": "text", "text": text }));
+            }
+            wire.push(serde_json::json!({ "role": message.role, "content": blocks }));
+        } else {
+            wire.push(serde_json::json!({ "role": message.role, "content": message.content.text() }));
+        }
+    }
+
+    wire
+}
@@ -0,0 +1,164 @@
+use super::embeddings::parse_vectors;
+use super::openai::parse_model_list;
+use super::options::set_opt;
+use super::{
+    ChatRequest, Credentials, EmbedPurpose, LlmProvider, ModelInfo, ProviderError, StreamChunk,
+    StreamFormat, ToolCall, Usage,
+};
+
+const DEFAULT_BASE_URL: &str = "https://api.cohere.ai";
+
+pub struct Cohere;
+
+impl LlmProvider for Cohere {
+    fn chat_request(
+        &self,
+        client: &reqwest::Client,
+        request: &ChatRequest,
+        stream: bool,
+    ) -> Result<reqwest::RequestBuilder, ProviderError> {
+        if !request.tools.is_empty() {
+            return Err(ProviderError::bad_request("Tool calling is not supported for Cohere"));
+        }
+        if request.messages.iter().any(|msg| msg.content.has_images()) {
+            return Err(ProviderError::bad_request("Image input is not supported for Cohere"));
+        }
+
+        // Cohere takes the last user message separately from the history
+        let conversation = request.conversation();
+        let (last, history) = conversation
+            .split_last()
+            .ok_or_else(|| ProviderError::bad_request("No messages provided"))?;
+
+        let chat_history: Vec<_> = history
+            .iter()
+            .map(|msg| {
+                serde_json::json!({
+                    "role": if msg.role == "assistant" { "CHATBOT" } else { "USER" },
+                    "message": msg.content.text()
+                })
+            })
+            .collect();
+
+        let options = &request.options;
+        let mut payload = serde_json::json!({
+            "model": request.model,
+            "message": last.content.text(),
+            "chat_history": chat_history,
+            "stream": stream,
+        });
+        set_opt(&mut payload, "preamble", request.system_prompt());
+        set_opt(&mut payload, "temperature", options.temperature);
+        set_opt(&mut payload, "p", options.top_p);
+        set_opt(&mut payload, "max_tokens", options.max_tokens);
+        set_opt(&mut payload, "stop_sequences", options.stop());
+        set_opt(&mut payload, "seed", options.seed);
+        if options.json_mode {
+            payload["response_format"] = serde_json::json!({ "type": "json_object" });
+        }
+
+        Ok(client
+            .post(format!("{}/v1/chat", request.base_url(DEFAULT_BASE_URL)))
+            .header("Authorization", format!("Bearer {}", request.api_key()?))
+            .json(&payload))
+    }
+
+    fn parse_chat_response(&self, json: &serde_json::Value) -> Result<String, ProviderError> {
+        json["text"]
+            .as_str()
+            .map(String::from)
+            .ok_or_else(|| ProviderError::parse("Invalid response format"))
+    }
+
+    fn parse_usage(&self, json: &serde_json::Value) -> Option<Usage> {
+        parse_usage(&json["meta"]["billed_units"])
+    }
+
+    fn stream_format(&self) -> StreamFormat {
+        StreamFormat::Ndjson
+    }
+
+    fn parse_tool_calls(&self, _json: &serde_json::Value) -> Vec<ToolCall> {
+        Vec::new()
+    }
+
+    fn parse_stream_event(&self, json: &serde_json::Value) -> Result<StreamChunk, ProviderError> {
+        let mut chunk = StreamChunk::default();
+
+        match json["event_type"].as_str().unwrap_or_default() {
+            "text-generation" => {
+                chunk.delta = json["text"].as_str().map(String::from);
+            }
+            "stream-end" => {
+                chunk.finish_reason = json["finish_reason"].as_str().map(String::from);
+                chunk.usage = parse_usage(&json["response"]["meta"]["billed_units"]);
+                chunk.done = true;
+            }
+            _ => {}
+        }
+
+        Ok(chunk)
+    }
+
+    fn models_request(
+        &self,
+        client: &reqwest::Client,
+        credentials: &Credentials,
+    ) -> Result<reqwest::RequestBuilder, ProviderError> {
+        Ok(client
+            .get(format!("{}/v1/models?page_size=1000", credentials.base_url(DEFAULT_BASE_URL)))
+            .header("Authorization", format!("Bearer {}", credentials.api_key()?)))
+    }
+
+    // Only models served by the chat endpoint (not embed/rerank)
+    fn parse_models(&self, json: &serde_json::Value) -> Vec<ModelInfo> {
+        parse_model_list(&json["models"], |model| {
+            let supports_chat = model["endpoints"]
+                .as_array()
+                .is_none_or(|endpoints| endpoints.iter().any(|e| e == "chat"));
+            if !supports_chat {
+                return None;
+            }
+
+            let mut info = ModelInfo::new(model["name"].as_str()?);
+            info.context_window = model["context_length"].as_u64();
+            Some(info)
+        })
+    }
+
+    // v3 models require `input_type`
+    fn embeddings_request(
+        &self,
+        client: &reqwest::Client,
+        credentials: &Credentials,
+        model: &str,
+        texts: &[String],
+        purpose: EmbedPurpose,
+    ) -> Option<Result<reqwest::RequestBuilder, ProviderError>> {
+        let input_type = match purpose {
+            EmbedPurpose::Document => "search_document",
+            EmbedPurpose::Query => "search_query",
+        };
+        let payload = serde_json::json!({ "model": model, "texts": texts, "input_type": input_type });
+        Some(credentials.api_key().map(|key| {
+            client
+                .post(format!("{}/v1/embed", credentials.base_url(DEFAULT_BASE_URL)))
+                .header("Authorization", format!("Bearer {}", key))
+                .json(&payload)
+        }))
+    }
+
+    fn parse_embeddings(&self, json: &serde_json::Value) -> Result<Vec<Vec<f32>>, ProviderError> {
+        let embeddings = json["embeddings"]
+            .as_array()
+            .ok_or_else(|| ProviderError::parse("Invalid embeddings response"))?;
+        parse_vectors(embeddings)
+    }
+}
+
+fn parse_usage(billed: &serde_json::Value) -> Option<Usage> {
+    billed.is_object().then(|| Usage {
+        prompt_tokens: billed["input_tokens"].as_u64().unwrap_or(0),
+        completion_tokens: billed["output_tokens"].as_u64().unwrap_or(0),
+    })
+}
@@ -0,0 +1,192 @@
+// Message content: plain text or a list of text and image parts. Images may
+// reference a file on disk; `inline_images` reads and base64-encodes them
+// before the request is handed to a provider.
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::path::Path;
+
+use super::{ChatRequest, ProviderError};
+
+/// Larger images are rejected by every provider we support.
+const MAX_IMAGE_BYTES: u64 = 20 * 1024 * 1024;
+
+/// Either a bare string (the common case, and what older callers send) or
+/// an array of parts.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Content {
+    Text(String),
+    Parts(Vec<ContentPart>),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ContentPart {
+    Text { text: String },
+    Image(ImageSource),
+}
+
+/// An image given either as a local `path` or as base64 `data` (a `data:`
+/// URL is accepted too). `media_type` is derived when omitted.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ImageSource {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub media_type: Option<String>,
+}
+
+/// An image ready to send: base64 payload plus its MIME type.
+pub struct InlineImage<'a> {
+    pub data: &'a str,
+    pub media_type: &'a str,
+}
+
+impl InlineImage<'_> {
+    pub fn data_url(&self) -> String {
+        format!("data:{};base64,{}", self.media_type, self.data)
+    }
+}
+
+impl Default for Content {
+    fn default() -> Self {
+        Content::Text(String::new())
+    }
+}
+
+impl From<String> for Content {
+    fn from(text: String) -> Self {
+        Content::Text(text)
+    }
+}
+
+impl Content {
+    /// All text parts joined together.
+    pub fn text(&self) -> Cow<'_, str> {
+        match self {
+            Content::Text(text) => Cow::Borrowed(text),
+            Content::Parts(parts) => Cow::Owned(
+                parts
+                    .iter()
+                    .filter_map(|part| match part {
+                        ContentPart::Text { text } => Some(text.as_str()),
+                        ContentPart::Image(_) => None,
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            ),
+        }
+    }
+
+    /// The inlined images, in order. Images still pointing at a file (see
+    /// `inline_images`) are skipped.
+    pub fn images(&self) -> Vec<InlineImage<'_>> {
+        match self {
+            Content::Text(_) => Vec::new(),
+            Content::Parts(parts) => parts
+                .iter()
+                .filter_map(|part| match part {
+                    ContentPart::Image(ImageSource {
+                        data: Some(data),
+                        media_type: Some(media_type),
+                        ..
+                    }) => Some(InlineImage { data, media_type }),
+                    _ => None,
+                })
+                .collect(),
+        }
+    }
+
+    pub fn has_images(&self) -> bool {
+        matches!(self, Content::Parts(parts)
+            if parts.iter().any(|part| matches!(part, ContentPart::Image(_))))
+    }
+}
+
+/// Replaces every image reference in the request with base64 data and a
+/// MIME type, reading files from disk where needed.
+pub fn inline_images(request: &mut ChatRequest) -> Result<(), ProviderError> {
+    for message in &mut request.messages {
+        if let Content::Parts(parts) = &mut message.content {
+            for part in parts {
+                if let ContentPart::Image(image) = part {
+                    inline_image(image)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn inline_image(image: &mut ImageSource) -> Result<(), ProviderError> {
+    if let Some(data) = image.data.take() {
+        // `data:image/png;base64,....`
+        let data = match data.strip_prefix("data:").and_then(|rest| rest.split_once(";base64,")) {
+            Some((media_type, payload)) => {
+                image.media_type.get_or_insert_with(|| media_type.to_string());
+                payload.to_string()
+            }
+            None => data,
+        };
+        if image.media_type.is_none() {
+            image.media_type = sniff_media_type(&data).map(String::from);
+        }
+        image.data = Some(data);
+    } else if let Some(path) = &image.path {
+        let path = Path::new(path);
+        let read_error =
+            |e: std::io::Error| ProviderError::bad_request(format!("Failed to read image {}: {}", path.display(), e));
+        let size = std::fs::metadata(path).map_err(read_error)?.len();
+        if size > MAX_IMAGE_BYTES {
+            return Err(ProviderError::bad_request(format!(
+                "Image {} is too large ({} MB, limit {} MB)",
+                path.display(),
+                size / (1024 * 1024),
+                MAX_IMAGE_BYTES / (1024 * 1024)
+            )));
+        }
+        let bytes = std::fs::read(path).map_err(read_error)?;
+        if image.media_type.is_none() {
+            image.media_type = media_type_for(path).map(String::from);
+        }
+        image.data = Some(BASE64.encode(bytes));
+    } else {
+        return Err(ProviderError::bad_request("Image part needs a `path` or `data`"));
+    }
+
+    if image.media_type.is_none() {
+        return Err(ProviderError::bad_request(
+            "Unsupported image format; use PNG, JPEG, GIF or WebP",
+        ));
+    }
+    Ok(())
+}
+
+fn media_type_for(path: &Path) -> Option<&'static str> {
+    let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+    match extension.as_str() {
+        "png" => Some("image/png"),
+        "jpg" | "jpeg" => Some("image/jpeg"),
+        "gif" => Some("image/gif"),
+        "webp" => Some("image/webp"),
+        _ => None,
+    }
+}
+
+// Recognizes the base64 encoding of each format's magic bytes
+fn sniff_media_type(data: &str) -> Option<&'static str> {
+    [
+        ("iVBORw0KGgo", "image/png"),
+        ("/9j/", "image/jpeg"),
+        ("R0lGOD", "image/gif"),
+        ("UklGR", "image/webp"),
+    ]
+    .iter()
+    .find(|(prefix, _)| data.starts_with(prefix))
+    .map(|(_, media_type)| *media_type)
+}
@@ -0,0 +1,324 @@
+// Keeps requests within the model's context window: rough token estimates,
+// known window sizes, and the strategies applied when a conversation has
+// outgrown them.
+
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+use super::{ChatRequest, Message, ProviderError, Transport, Usage};
+
+/// Rough average for English text and code across tokenizers.
+const CHARS_PER_TOKEN: u64 = 4;
+/// Role markers and separators every message costs.
+const MESSAGE_OVERHEAD: u64 = 4;
+/// Typical cost of one image at the providers' default detail level.
+const IMAGE_TOKENS: u64 = 1_000;
+
+/// Context sizes by model id prefix, longest prefix first; the first match
+/// wins, so `gpt-4.1` is not taken for `gpt-4`.
+const CONTEXT_WINDOWS: &[(&str, u64)] = &[
+    ("open-mixtral-8x22b", 64_000),
+    ("mistral-medium-3", 128_000),
+    ("claude-sonnet-4", 200_000),
+    ("gemini-1.5-pro", 2_097_152),
+    ("claude-haiku-4", 200_000),
+    ("mistral-medium", 32_000),
+    ("claude-opus-4", 200_000),
+    ("gpt-3.5-turbo", 16_385),
+    ("mistral-large", 128_000),
+    ("mistral-small", 32_000),
+    ("open-mistral", 32_000),
+    ("open-mixtral", 32_000),
+    ("gpt-4-turbo", 128_000),
+    ("chatgpt-4o", 128_000),
+    ("gemini-1.5", 1_048_576),
+    ("gemini-2.5", 1_048_576),
+    ("gemini-pro", 30_720),
+    ("gpt-4-0125", 128_000),
+    ("gpt-4-1106", 128_000),
+    ("o1-preview", 128_000),
+    ("codestral", 32_000),
+    ("command-a", 256_000),
+    ("command-r", 128_000),
+    ("gpt-4-32k", 32_768),
+    ("claude-2", 100_000),
+    ("claude-3", 200_000),
+    ("gemini-2", 1_048_576),
+    ("llama3.1", 131_072),
+    ("llama3.2", 131_072),
+    ("llama3.3", 131_072),
+    ("command", 4_096),
+    ("gpt-4.1", 1_047_576),
+    ("gpt-4.5", 128_000),
+    ("o1-mini", 128_000),
+    ("o4-mini", 200_000),
+    ("gpt-4o", 128_000),
+    ("llama2", 4_096),
+    ("llama3", 8_192),
+    ("gpt-4", 8_192),
+    ("gpt-5", 400_000),
+    ("o1", 200_000),
+    ("o3", 200_000),
+];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContextStrategy {
+    /// Send the request unchanged and let the provider reject it.
+    Off,
+    /// Drop whole turns, oldest first, until the request fits.
+    #[default]
+    DropOldest,
+    /// Keep system messages and the last `keep_last` messages.
+    KeepLast,
+    /// Replace everything but the last `keep_last` messages with a summary.
+    Summarize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ContextPolicy {
+    pub strategy: ContextStrategy,
+    /// Messages `keep_last` and `summarize` always send verbatim.
+    pub keep_last: usize,
+    /// Room left for the reply when the request sets no `max_tokens`.
+    pub reserve_tokens: u64,
+    /// Writes the summaries; defaults to the request's own provider/model.
+    pub summary_provider: Option<String>,
+    pub summary_model: Option<String>,
+}
+
+impl Default for ContextPolicy {
+    fn default() -> Self {
+        Self {
+            strategy: ContextStrategy::DropOldest,
+            keep_last: 6,
+            reserve_tokens: 1_024,
+            summary_provider: None,
+            summary_model: None,
+        }
+    }
+}
+
+/// What was done to make a request fit, returned in the response meta.
+#[derive(Debug, Clone, Serialize)]
+pub struct ContextTrim {
+    pub strategy: ContextStrategy,
+    pub context_window: u64,
+    pub tokens_before: u64,
+    pub tokens_after: u64,
+    pub dropped_messages: usize,
+    pub summarized_messages: usize,
+    /// Tokens the summary call used, to be recorded like any other call.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary_usage: Option<Usage>,
+    /// Why no summary was written; older turns were dropped instead.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary_error: Option<ProviderError>,
+}
+
+/// The built-in context size for a model, `None` when the table does not
+/// know it; OpenRouter-style `vendor/model` ids are matched on the model part
+/// too.
+pub fn context_window(model: &str) -> Option<u64> {
+    let bare = model.rsplit('/').next().unwrap_or(model);
+    [model, bare].iter().find_map(|candidate| {
+        CONTEXT_WINDOWS
+            .iter()
+            .find(|(prefix, _)| candidate.starts_with(prefix))
+            .map(|(_, window)| *window)
+    })
+}
+
+pub(super) fn text_tokens(text: &str) -> u64 {
+    (text.chars().count() as u64).div_ceil(CHARS_PER_TOKEN)
+}
+
+fn message_tokens(message: &Message) -> u64 {
+    let calls: u64 = message
+        .tool_calls
+        .iter()
+        .map(|call| text_tokens(&call.name) + text_tokens(&call.arguments.to_string()))
+        .sum();
+
+    MESSAGE_OVERHEAD
+        + text_tokens(&message.content.text())
+        + message.content.images().len() as u64 * IMAGE_TOKENS
+        + calls
+}
+
+/// Approximate prompt size of a request, including the system prompt and
+/// tool definitions.
+pub fn estimate_tokens(request: &ChatRequest) -> u64 {
+    let system = request.options.system.as_deref().map_or(0, text_tokens);
+    let tools: u64 = request
+        .tools
+        .iter()
+        .map(|tool| text_tokens(&serde_json::to_string(tool).unwrap_or_default()))
+        .sum();
+
+    system + tools + request.messages.iter().map(message_tokens).sum::<u64>()
+}
+
+// Non-system messages grouped into turns, each starting at a user message,
+// so a tool result is never separated from the call that produced it
+fn turns(messages: &[Message]) -> Vec<Range<usize>> {
+    let mut turns: Vec<Range<usize>> = Vec::new();
+    for (i, message) in messages.iter().enumerate() {
+        if message.role == "system" {
+            continue;
+        }
+        match turns.last_mut() {
+            Some(turn) if message.role != "user" => turn.end = i + 1,
+            _ => turns.push(i..i + 1),
+        }
+    }
+    turns
+}
+
+// Index of the turn holding the `keep_last`-th message from the end; the
+// last turn is always kept
+fn first_kept_turn(turns: &[Range<usize>], keep_last: usize) -> usize {
+    let total: usize = turns.iter().map(|turn| turn.len()).sum();
+    let mut to_drop = total.saturating_sub(keep_last);
+    for (i, turn) in turns.iter().enumerate() {
+        if to_drop < turn.len() {
+            return i;
+        }
+        to_drop -= turn.len();
+    }
+    turns.len().saturating_sub(1)
+}
+
+// Removes the non-system messages of `turns[..count]`, returning how many went
+fn drop_turns(request: &mut ChatRequest, count: usize) -> usize {
+    let turns = turns(&request.messages);
+    if count == 0 || turns.is_empty() {
+        return 0;
+    }
+    let end = turns[count.min(turns.len()) - 1].end;
+
+    let before = request.messages.len();
+    let mut index = 0;
+    request.messages.retain(|message| {
+        let keep = index >= end || message.role == "system";
+        index += 1;
+        keep
+    });
+    before - request.messages.len()
+}
+
+/// Trims `request` to fit `context_window` according to `policy`. Returns
+/// `None` when it already fit, and `ContextTooLong` when even the last turn
+/// alone does not.
+///
+/// `summarizer` is a request carrying the summary provider, model and
+/// credentials; only the `summarize` strategy uses it.
+pub async fn fit_context(
+    transport: &Transport,
+    request: &mut ChatRequest,
+    policy: &ContextPolicy,
+    context_window: u64,
+    summarizer: Option<ChatRequest>,
+) -> Result<Option<ContextTrim>, ProviderError> {
+    let reserve = request.options.max_tokens.map_or(policy.reserve_tokens, u64::from);
+    let budget = context_window.saturating_sub(reserve);
+    let tokens_before = estimate_tokens(request);
+    if policy.strategy == ContextStrategy::Off || tokens_before <= budget {
+        return Ok(None);
+    }
+
+    let mut trim = ContextTrim {
+        strategy: policy.strategy,
+        context_window,
+        tokens_before,
+        tokens_after: tokens_before,
+        dropped_messages: 0,
+        summarized_messages: 0,
+        summary_usage: None,
+        summary_error: None,
+    };
+
+    let keep_from = first_kept_turn(&turns(&request.messages), policy.keep_last);
+    match policy.strategy {
+        ContextStrategy::KeepLast => {
+            trim.dropped_messages += drop_turns(request, keep_from);
+        }
+        ContextStrategy::Summarize if keep_from > 0 => {
+            // A failed summary still leaves dropping turns as a fallback
+            if let Some(summarizer) = summarizer {
+                match summarize(transport, request, keep_from, summarizer).await {
+                    Ok((summarized, usage)) => {
+                        trim.summarized_messages = summarized;
+                        trim.summary_usage = usage;
+                    }
+                    Err(error) => trim.summary_error = Some(error),
+                }
+            }
+        }
+        _ => {}
+    }
+
+    // Whatever the strategy, shed the oldest turns that still do not fit
+    while estimate_tokens(request) > budget && turns(&request.messages).len() > 1 {
+        trim.dropped_messages += drop_turns(request, 1);
+    }
+
+    trim.tokens_after = estimate_tokens(request);
+    if trim.tokens_after > budget {
+        return Err(ProviderError::ContextTooLong {
+            message: format!(
+                "The latest message alone is about {} tokens, more than the {} tokens {} accepts",
+                trim.tokens_after, budget, request.model
+            ),
+        });
+    }
+    Ok(Some(trim))
+}
+
+// Replaces the messages of `turns[..count]` with one system message holding
+// a summary of them; returns how many messages it covers and what the
+// summary cost
+async fn summarize(
+    transport: &Transport,
+    request: &mut ChatRequest,
+    count: usize,
+    mut summarizer: ChatRequest,
+) -> Result<(usize, Option<Usage>), ProviderError> {
+    let turns = turns(&request.messages);
+    let end = turns[count - 1].end;
+    let earlier: Vec<&Message> = request.messages[..end]
+        .iter()
+        .filter(|message| message.role != "system")
+        .collect();
+
+    let transcript: Vec<String> = earlier
+        .iter()
+        .map(|message| format!("{}: {}", message.role, message.content.text()))
+        .collect();
+    summarizer.messages = vec![Message::system(
+        "Summarize the conversation below for another assistant that will continue it. \
+         Keep facts, decisions, file names, code identifiers and open questions; drop \
+         pleasantries. Answer with the summary only."
+            .to_string(),
+    )];
+    summarizer.messages.push(Message {
+        role: "user".to_string(),
+        content: transcript.join("\n\n").into(),
+        tool_calls: Vec::new(),
+        tool_call_id: None,
+        name: None,
+    });
+
+    let response = super::chat(transport, &summarizer).await?;
+    let summary = response.content;
+    let summarized = earlier.len();
+    drop_turns(request, count);
+
+    let insert_at = request.messages.iter().take_while(|m| m.role == "system").count();
+    request.messages.insert(
+        insert_at,
+        Message::system(format!("Summary of the earlier conversation:\n{}", summary.trim())),
+    );
+    Ok((summarized, response.usage))
+}
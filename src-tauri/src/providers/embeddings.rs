@@ -0,0 +1,88 @@
+// Text embeddings for retrieval. Providers expose them through the optional
+// `LlmProvider::embeddings_request`; Anthropic and OpenRouter have none.
+
+use serde::Deserialize;
+
+use super::context::text_tokens;
+use super::{provider_for, send_with_retries, Credentials, ProviderError, Transport};
+
+/// Texts sent per request; Cohere accepts at most 96 and Gemini 100.
+const BATCH_SIZE: usize = 96;
+
+/// Some providers embed documents and search queries differently.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EmbedPurpose {
+    #[default]
+    Document,
+    Query,
+}
+
+/// Model used when the caller does not pick one.
+pub fn default_embedding_model(provider: &str) -> Option<&'static str> {
+    match provider {
+        "openai" => Some("text-embedding-3-small"),
+        "mistral" => Some("mistral-embed"),
+        "cohere" => Some("embed-english-v3.0"),
+        "gemini" => Some("text-embedding-004"),
+        "ollama" => Some("nomic-embed-text"),
+        _ => None,
+    }
+}
+
+/// Embeds `texts`, returning one vector per text in the same order. Large
+/// inputs are split into batches, each retried and rate limited like a chat
+/// request.
+pub async fn embed(
+    transport: &Transport,
+    credentials: &Credentials,
+    model: &str,
+    texts: &[String],
+    purpose: EmbedPurpose,
+) -> Result<Vec<Vec<f32>>, ProviderError> {
+    let provider = provider_for(&credentials.provider)?;
+    let mut vectors = Vec::with_capacity(texts.len());
+
+    for batch in texts.chunks(BATCH_SIZE) {
+        let tokens = batch.iter().map(|text| text_tokens(text)).sum();
+        let (response, _, _permit) = send_with_retries(transport, &credentials.provider, tokens, || {
+            provider
+                .embeddings_request(&transport.client, credentials, model, batch, purpose)
+                .unwrap_or_else(|| {
+                    Err(ProviderError::bad_request(format!(
+                        "{} does not offer embeddings",
+                        credentials.provider
+                    )))
+                })
+        })
+        .await?;
+
+        let json: serde_json::Value = transport.within("the response body", response.json()).await?;
+        let embedded = provider.parse_embeddings(&json)?;
+        if embedded.len() != batch.len() {
+            return Err(ProviderError::parse(format!(
+                "Expected {} embeddings, got {}",
+                batch.len(),
+                embedded.len()
+            )));
+        }
+        vectors.extend(embedded);
+    }
+
+    Ok(vectors)
+}
+
+/// Reads a list of JSON number arrays.
+pub(super) fn parse_vectors<'a>(
+    vectors: impl IntoIterator<Item = &'a serde_json::Value>,
+) -> Result<Vec<Vec<f32>>, ProviderError> {
+    vectors
+        .into_iter()
+        .map(|vector| {
+            vector
+                .as_array()
+                .and_then(|values| values.iter().map(|v| v.as_f64().map(|v| v as f32)).collect())
+                .ok_or_else(|| ProviderError::parse("Invalid embeddings response"))
+        })
+        .collect()
+}
@@ -0,0 +1,229 @@
+// Provider failures, classified so the frontend can react to each kind.
+
+use serde::Serialize;
+use std::fmt;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ProviderError {
+    /// Missing, invalid or revoked API key.
+    Auth { message: String },
+    /// Too many requests; `retry_after` is in seconds when the provider said so.
+    RateLimited {
+        message: String,
+        retry_after: Option<u64>,
+    },
+    /// Out of credits or billing quota.
+    Quota { message: String },
+    /// The prompt does not fit the model's context window.
+    ContextTooLong { message: String },
+    /// The provider rejected the request (unknown model, bad parameters, ...).
+    BadRequest { message: String },
+    /// 5xx or an overloaded provider.
+    Server {
+        status: Option<u16>,
+        message: String,
+    },
+    /// The provider could not be reached or the connection dropped.
+    Network { message: String },
+    /// The provider answered with something we could not understand.
+    Parse { message: String },
+    /// A result could not be saved to or read from the app data directory.
+    Storage { message: String },
+    /// Aborted through `cancel_request`.
+    Cancelled { message: String },
+}
+
+impl ProviderError {
+    pub fn parse(message: impl Into<String>) -> Self {
+        ProviderError::Parse {
+            message: message.into(),
+        }
+    }
+
+    pub fn storage(message: impl Into<String>) -> Self {
+        ProviderError::Storage {
+            message: message.into(),
+        }
+    }
+
+    pub fn bad_request(message: impl Into<String>) -> Self {
+        ProviderError::BadRequest {
+            message: message.into(),
+        }
+    }
+
+    pub fn cancelled() -> Self {
+        ProviderError::Cancelled {
+            message: "Request cancelled".to_string(),
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            ProviderError::Auth { message }
+            | ProviderError::RateLimited { message, .. }
+            | ProviderError::Quota { message }
+            | ProviderError::ContextTooLong { message }
+            | ProviderError::BadRequest { message }
+            | ProviderError::Server { message, .. }
+            | ProviderError::Network { message }
+            | ProviderError::Parse { message }
+            | ProviderError::Storage { message }
+            | ProviderError::Cancelled { message } => message,
+        }
+    }
+
+    /// Classifies a non-success HTTP response from its status and body.
+    pub fn from_response(status: u16, retry_after: Option<u64>, body: &str) -> Self {
+        match serde_json::from_str::<serde_json::Value>(body) {
+            Ok(json) => {
+                let (code, message) = error_details(&json);
+                let message = message.unwrap_or_else(|| body.to_string());
+                classify(Some(status), &code, message, retry_after)
+            }
+            Err(_) => {
+                let message = if body.trim().is_empty() {
+                    format!("HTTP {}", status)
+                } else {
+                    body.trim().to_string()
+                };
+                classify(Some(status), "", message, retry_after)
+            }
+        }
+    }
+
+    /// Classifies an error object delivered inside a successful response,
+    /// e.g. an `error` event in the middle of a stream.
+    pub fn from_body(json: &serde_json::Value) -> Self {
+        let (code, message) = error_details(json);
+        let status = json["error"]["code"].as_u64().and_then(|c| u16::try_from(c).ok());
+        classify(
+            status,
+            &code,
+            message.unwrap_or_else(|| json.to_string()),
+            None,
+        )
+    }
+}
+
+// Without the URL: it names the endpoint, and error messages reach the UI
+impl From<reqwest::Error> for ProviderError {
+    fn from(e: reqwest::Error) -> Self {
+        let e = e.without_url();
+        if e.is_decode() {
+            ProviderError::parse(format!("Failed to parse response: {}", e))
+        } else {
+            ProviderError::Network {
+                message: format!("Request failed: {}", e),
+            }
+        }
+    }
+}
+
+impl fmt::Display for ProviderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProviderError::RateLimited {
+                retry_after: Some(secs),
+                message,
+            } => write!(f, "{} (retry after {}s)", message, secs),
+            other => f.write_str(other.message()),
+        }
+    }
+}
+
+/// Pulls the error code/type and human-readable message out of the various
+/// provider error shapes:
+/// OpenAI/Mistral/OpenRouter `{"error": {"message", "type", "code"}}`,
+/// Anthropic `{"type": "error", "error": {"type", "message"}}`,
+/// Gemini `{"error": {"code", "message", "status"}}`,
+/// Cohere/Mistral `{"message"}` and Ollama `{"error": "..."}`.
+fn error_details(json: &serde_json::Value) -> (String, Option<String>) {
+    let error = &json["error"];
+
+    let code = ["type", "code", "status"]
+        .iter()
+        .filter_map(|key| match &error[*key] {
+            serde_json::Value::String(s) => Some(s.clone()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    let message = error["message"]
+        .as_str()
+        .or_else(|| error.as_str())
+        .or_else(|| json["message"].as_str())
+        .or_else(|| json["detail"].as_str())
+        .map(String::from);
+
+    (code, message)
+}
+
+fn classify(
+    status: Option<u16>,
+    code: &str,
+    message: String,
+    retry_after: Option<u64>,
+) -> ProviderError {
+    let code = code.to_lowercase();
+    let text = format!("{} {}", code, message.to_lowercase());
+    let has_code = |names: &[&str]| code.split_whitespace().any(|c| names.contains(&c));
+    let mentions = |phrases: &[&str]| phrases.iter().any(|p| text.contains(p));
+
+    if status == Some(413)
+        || mentions(&[
+            "context_length_exceeded",
+            "maximum context length",
+            "context window",
+            "prompt is too long",
+            "too many tokens",
+            "input is too long",
+        ])
+    {
+        return ProviderError::ContextTooLong { message };
+    }
+
+    if status == Some(402)
+        || mentions(&[
+            "insufficient_quota",
+            "exceeded your current quota",
+            "billing",
+            "credit balance",
+            "insufficient credits",
+        ])
+    {
+        return ProviderError::Quota { message };
+    }
+
+    if matches!(status, Some(401) | Some(403))
+        || has_code(&[
+            "authentication_error",
+            "permission_error",
+            "invalid_api_key",
+            "unauthenticated",
+            "permission_denied",
+        ])
+        || mentions(&["api key not valid", "invalid api key", "incorrect api key"])
+    {
+        return ProviderError::Auth { message };
+    }
+
+    if status == Some(429)
+        || has_code(&["rate_limit_error", "rate_limit_exceeded", "resource_exhausted"])
+    {
+        return ProviderError::RateLimited {
+            message,
+            retry_after,
+        };
+    }
+
+    if status.is_some_and(|s| s >= 500)
+        || has_code(&["api_error", "overloaded_error", "internal", "unavailable"])
+    {
+        return ProviderError::Server { status, message };
+    }
+
+    ProviderError::BadRequest { message }
+}
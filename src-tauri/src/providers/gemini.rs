@@ -0,0 +1,272 @@
+use std::collections::HashMap;
+
+use super::embeddings::parse_vectors;
+use super::openai::parse_model_list;
+use super::options::set_opt;
+use super::tools::{parse_arguments, synthetic_id};
+use super::{
+    ChatRequest, Credentials, EmbedPurpose, LlmProvider, Message, ModelInfo, ProviderError, StreamChunk,
+    ToolCall, Usage,
+};
+
+const DEFAULT_BASE_URL: &str = "https://generativelanguage.googleapis.com/v1beta";
+/// Keeps the key out of the URL, which shows up in error messages and logs.
+const API_KEY_HEADER: &str = "x-goog-api-key";
+
+pub struct Gemini;
+
+impl LlmProvider for Gemini {
+    fn chat_request(
+        &self,
+        client: &reqwest::Client,
+        request: &ChatRequest,
+        stream: bool,
+    ) -> Result<reqwest::RequestBuilder, ProviderError> {
+        let options = &request.options;
+        let mut payload = serde_json::json!({
+            "contents": wire_contents(request.conversation()),
+        });
+        if let Some(system) = request.system_prompt() {
+            payload["systemInstruction"] = serde_json::json!({ "parts": [{ "text": system }] });
+        }
+
+        let mut config = serde_json::json!({});
+        set_opt(&mut config, "temperature", options.temperature);
+        set_opt(&mut config, "topP", options.top_p);
+        set_opt(&mut config, "maxOutputTokens", options.max_tokens);
+        set_opt(&mut config, "stopSequences", options.stop());
+        set_opt(&mut config, "seed", options.seed);
+        if options.json_mode {
+            config["responseMimeType"] = serde_json::json!("application/json");
+        }
+        if config.as_object().is_some_and(|c| !c.is_empty()) {
+            payload["generationConfig"] = config;
+        }
+        if !request.tools.is_empty() {
+            let declarations: Vec<_> = request
+                .tools
+                .iter()
+                .map(|tool| {
+                    serde_json::json!({
+                        "name": tool.name,
+                        "description": tool.description,
+                        "parameters": gemini_schema(&tool.parameters),
+                    })
+                })
+                .collect();
+            payload["tools"] = serde_json::json!([{ "functionDeclarations": declarations }]);
+        }
+
+        let method = if stream {
+            "streamGenerateContent?alt=sse"
+        } else {
+            "generateContent"
+        };
+        let url = format!("{}/models/{}:{}", request.base_url(DEFAULT_BASE_URL), request.model, method);
+
+        Ok(client
+            .post(url)
+            .header(API_KEY_HEADER, request.api_key()?)
+            .json(&payload))
+    }
+
+    // Text may be split around `functionCall` parts
+    fn parse_chat_response(&self, json: &serde_json::Value) -> Result<String, ProviderError> {
+        let text: Vec<&str> = json["candidates"][0]["content"]["parts"]
+            .as_array()
+            .map(|parts| parts.iter().filter_map(|part| part["text"].as_str()).collect())
+            .unwrap_or_default();
+        if text.is_empty() {
+            return Err(ProviderError::parse("Invalid response format"));
+        }
+        Ok(text.join(""))
+    }
+
+    fn parse_tool_calls(&self, json: &serde_json::Value) -> Vec<ToolCall> {
+        json["candidates"][0]["content"]["parts"]
+            .as_array()
+            .map(|parts| {
+                parts
+                    .iter()
+                    .filter_map(|part| part.get("functionCall"))
+                    .enumerate()
+                    .filter_map(|(index, call)| {
+                        let name = call["name"].as_str()?;
+                        Some(ToolCall {
+                            id: synthetic_id(name, index),
+                            name: name.to_string(),
+                            arguments: parse_arguments(&call["args"]),
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn parse_usage(&self, json: &serde_json::Value) -> Option<Usage> {
+        parse_usage(&json["usageMetadata"])
+    }
+
+    fn parse_stream_event(&self, json: &serde_json::Value) -> Result<StreamChunk, ProviderError> {
+        if json.get("error").is_some() {
+            return Err(ProviderError::from_body(json));
+        }
+
+        let candidate = &json["candidates"][0];
+        Ok(StreamChunk {
+            delta: candidate["content"]["parts"][0]["text"].as_str().map(String::from),
+            finish_reason: candidate["finishReason"].as_str().map(String::from),
+            usage: parse_usage(&json["usageMetadata"]),
+            done: false,
+        })
+    }
+
+    fn models_request(
+        &self,
+        client: &reqwest::Client,
+        credentials: &Credentials,
+    ) -> Result<reqwest::RequestBuilder, ProviderError> {
+        Ok(client
+            .get(format!("{}/models?pageSize=1000", credentials.base_url(DEFAULT_BASE_URL)))
+            .header(API_KEY_HEADER, credentials.api_key()?))
+    }
+
+    // Names come back as `models/gemini-pro`; chat requests use the bare id.
+    // Embedding and other non-chat models are skipped.
+    fn parse_models(&self, json: &serde_json::Value) -> Vec<ModelInfo> {
+        parse_model_list(&json["models"], |model| {
+            let supports_chat = model["supportedGenerationMethods"]
+                .as_array()
+                .is_some_and(|methods| methods.iter().any(|m| m == "generateContent"));
+            if !supports_chat {
+                return None;
+            }
+
+            let mut info = ModelInfo::new(model["name"].as_str()?.trim_start_matches("models/"));
+            if let Some(name) = model["displayName"].as_str() {
+                info.name = name.to_string();
+            }
+            info.context_window = model["inputTokenLimit"].as_u64();
+            Some(info)
+        })
+    }
+
+    fn embeddings_request(
+        &self,
+        client: &reqwest::Client,
+        credentials: &Credentials,
+        model: &str,
+        texts: &[String],
+        purpose: EmbedPurpose,
+    ) -> Option<Result<reqwest::RequestBuilder, ProviderError>> {
+        let task_type = match purpose {
+            EmbedPurpose::Document => "RETRIEVAL_DOCUMENT",
+            EmbedPurpose::Query => "RETRIEVAL_QUERY",
+        };
+        let requests: Vec<_> = texts
+            .iter()
+            .map(|text| {
+                serde_json::json!({
+                    "model": format!("models/{}", model),
+                    "content": { "parts": [{ "text": text }] },
+                    "taskType": task_type,
+                })
+            })
+            .collect();
+        Some(credentials.api_key().map(|key| {
+            client
+                .post(format!(
+                    "{}/models/{}:batchEmbedContents",
+                    credentials.base_url(DEFAULT_BASE_URL),
+                    model
+                ))
+                .header(API_KEY_HEADER, key)
+                .json(&serde_json::json!({ "requests": requests }))
+        }))
+    }
+
+    fn parse_embeddings(&self, json: &serde_json::Value) -> Result<Vec<Vec<f32>>, ProviderError> {
+        let embeddings = json["embeddings"]
+            .as_array()
+            .ok_or_else(|| ProviderError::parse("Invalid embeddings response"))?;
+        parse_vectors(embeddings.iter().map(|embedding| &embedding["values"]))
+    }
+}
+
+/// Gemini answers tool calls by function name, so results are matched to the
+/// name of the call they answer when the message does not carry one.
+fn wire_contents(messages: Vec<&Message>) -> Vec<serde_json::Value> {
+    let mut call_names: HashMap<&str, &str> = HashMap::new();
+    let mut contents: Vec<serde_json::Value> = Vec::new();
+
+    for message in messages {
+        if message.is_tool_result() {
+            let name = message.name.as_deref().or_else(|| {
+                message
+                    .tool_call_id
+                    .as_deref()
+                    .and_then(|id| call_names.get(id).copied())
+            });
+            let part = serde_json::json!({
+                "functionResponse": {
+                    "name": name.unwrap_or_default(),
+                    "response": { "content": message.content.text() },
+                }
+            });
+            // Answers to parallel calls go back together in one turn
+            match contents.last_mut() {
+                Some(last) if last["parts"][0].get("functionResponse").is_some() => {
+                    if let Some(parts) = last["parts"].as_array_mut() {
+                        parts.push(part);
+                    }
+                }
+                _ => contents.push(serde_json::json!({ "role": "user", "parts": [part] })),
+            }
+            continue;
+        }
+
+        let mut parts = Vec::new();
+        let text = message.content.text();
+        if !text.is_empty() || (message.tool_calls.is_empty() && !message.content.has_images()) {
+            parts.push(serde_json::json!({ "text": text }));
+        }
+        for image in message.content.images() {
+            parts.push(serde_json::json!({
+                "inline_data": { "mime_type": image.media_type, "data": image.data }
+            }));
+        }
+        for call in &message.tool_calls {
+            call_names.insert(&call.id, &call.name);
+            parts.push(serde_json::json!({
+                "functionCall": { "name": call.name, "args": call.arguments }
+            }));
+        }
+        contents.push(serde_json::json!({
+            "role": if message.role == "assistant" { "model" } else { "user" },
+            "parts": parts,
+        }));
+    }
+
+    contents
+}
+
+/// Gemini accepts an OpenAPI subset of JSON Schema and rejects keywords
+/// such as `additionalProperties` and `$schema`.
+fn gemini_schema(schema: &serde_json::Value) -> serde_json::Value {
+    match schema {
+        serde_json::Value::Object(map) => map
+            .iter()
+            .filter(|(key, _)| !matches!(key.as_str(), "additionalProperties" | "$schema"))
+            .map(|(key, value)| (key.clone(), gemini_schema(value)))
+            .collect(),
+        serde_json::Value::Array(items) => items.iter().map(gemini_schema).collect(),
+        other => other.clone(),
+    }
+}
+
+fn parse_usage(usage: &serde_json::Value) -> Option<Usage> {
+    usage.is_object().then(|| Usage {
+        prompt_tokens: usage["promptTokenCount"].as_u64().unwrap_or(0),
+        completion_tokens: usage["candidatesTokenCount"].as_u64().unwrap_or(0),
+    })
+}
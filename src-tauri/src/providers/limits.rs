@@ -0,0 +1,309 @@
+// Client-side rate limits per provider: requests and tokens per minute and
+// requests in flight. A call over a limit waits its turn in a FIFO queue
+// instead of earning a 429 from the provider.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+
+const WINDOW: Duration = Duration::from_secs(60);
+
+/// Unset fields are not limited.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProviderLimits {
+    pub requests_per_minute: Option<u32>,
+    /// Counted from the estimated prompt plus `max_tokens` when a call
+    /// starts, then corrected to the usage the provider reports.
+    pub tokens_per_minute: Option<u64>,
+    pub max_concurrent: Option<u32>,
+}
+
+/// Limits by provider id (`openai`, `custom:<id>`, ...).
+pub type RateLimits = BTreeMap<String, ProviderLimits>;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct QueueStatus {
+    pub provider: String,
+    /// Calls waiting for their turn.
+    pub queued: usize,
+    pub in_flight: u32,
+    pub requests_last_minute: usize,
+    pub tokens_last_minute: u64,
+}
+
+/// Shared by every `Transport`; cloning is cheap.
+#[derive(Clone, Default)]
+pub struct RateLimiter {
+    shared: Arc<Shared>,
+}
+
+#[derive(Default)]
+struct Shared {
+    limits: RwLock<RateLimits>,
+    providers: Mutex<HashMap<String, ProviderState>>,
+    /// Woken whenever a call starts or ends, or the limits change.
+    changed: Notify,
+}
+
+#[derive(Default)]
+struct ProviderState {
+    next_ticket: u64,
+    queue: VecDeque<u64>,
+    in_flight: u32,
+    /// Calls started within the last minute, oldest first.
+    recent: VecDeque<Started>,
+}
+
+struct Started {
+    ticket: u64,
+    at: Instant,
+    tokens: u64,
+}
+
+enum Admission {
+    Now,
+    After(Duration),
+    /// Once another call starts or ends.
+    Later,
+}
+
+impl ProviderLimits {
+    pub fn validate(&self) -> Result<(), String> {
+        let zero = [
+            ("requests_per_minute", self.requests_per_minute.map(u64::from)),
+            ("tokens_per_minute", self.tokens_per_minute),
+            ("max_concurrent", self.max_concurrent.map(u64::from)),
+        ]
+        .into_iter()
+        .find(|(_, limit)| *limit == Some(0));
+
+        match zero {
+            Some((name, _)) => Err(format!("{} must be at least 1", name)),
+            None => Ok(()),
+        }
+    }
+}
+
+impl ProviderState {
+    fn prune(&mut self, now: Instant) {
+        while self.recent.front().is_some_and(|call| call.at + WINDOW <= now) {
+            self.recent.pop_front();
+        }
+    }
+
+    fn tokens(&self) -> u64 {
+        self.recent.iter().map(|call| call.tokens).sum()
+    }
+
+    fn admission(&self, limits: &ProviderLimits, tokens: u64, now: Instant) -> Admission {
+        if limits.max_concurrent.is_some_and(|max| self.in_flight >= max) {
+            return Admission::Later;
+        }
+
+        let mut wait = Duration::ZERO;
+        if let Some(rpm) = limits.requests_per_minute {
+            let rpm = rpm as usize;
+            if self.recent.len() >= rpm {
+                let expiring = &self.recent[self.recent.len() - rpm];
+                wait = wait.max((expiring.at + WINDOW).saturating_duration_since(now));
+            }
+        }
+
+        // A call bigger than the whole budget goes once the window is empty
+        if let Some(tpm) = limits.tokens_per_minute {
+            let mut used = self.tokens();
+            if used > 0 && used + tokens > tpm {
+                for call in &self.recent {
+                    used -= call.tokens;
+                    if used == 0 || used + tokens <= tpm {
+                        wait = wait.max((call.at + WINDOW).saturating_duration_since(now));
+                        break;
+                    }
+                }
+            }
+        }
+
+        if wait.is_zero() {
+            Admission::Now
+        } else {
+            Admission::After(wait)
+        }
+    }
+}
+
+impl RateLimiter {
+    pub fn new(limits: RateLimits) -> Self {
+        let limiter = Self::default();
+        *limiter.shared.limits.write().unwrap() = limits;
+        limiter
+    }
+
+    pub fn limits(&self) -> RateLimits {
+        self.shared.limits.read().unwrap().clone()
+    }
+
+    /// Applies to queued calls too.
+    pub fn set_limits(&self, limits: RateLimits) {
+        *self.shared.limits.write().unwrap() = limits;
+        self.shared.changed.notify_waiters();
+    }
+
+    /// Providers with limits or recent calls.
+    pub fn status(&self) -> Vec<QueueStatus> {
+        let limits = self.shared.limits.read().unwrap();
+        let mut providers = self.shared.providers.lock().unwrap();
+        let now = Instant::now();
+
+        let mut status: Vec<QueueStatus> = providers
+            .iter_mut()
+            .map(|(provider, state)| {
+                state.prune(now);
+                QueueStatus {
+                    provider: provider.clone(),
+                    queued: state.queue.len(),
+                    in_flight: state.in_flight,
+                    requests_last_minute: state.recent.len(),
+                    tokens_last_minute: state.tokens(),
+                }
+            })
+            .filter(|s| limits.contains_key(&s.provider) || s.in_flight > 0 || s.requests_last_minute > 0)
+            .collect();
+        for provider in limits.keys().filter(|p| !providers.contains_key(*p)) {
+            status.push(QueueStatus {
+                provider: provider.clone(),
+                queued: 0,
+                in_flight: 0,
+                requests_last_minute: 0,
+                tokens_last_minute: 0,
+            });
+        }
+        status.sort_by(|a, b| a.provider.cmp(&b.provider));
+        status
+    }
+
+    /// Waits until a call of about `tokens` tokens may go to `provider`,
+    /// behind any calls queued before it. The call counts as in flight until
+    /// the permit is dropped; dropping the future gives up its place.
+    pub async fn acquire(&self, provider: &str, tokens: u64) -> Permit {
+        let ticket = {
+            let mut providers = self.shared.providers.lock().unwrap();
+            let state = providers.entry(provider.to_string()).or_default();
+            let ticket = state.next_ticket;
+            state.next_ticket += 1;
+            state.queue.push_back(ticket);
+            ticket
+        };
+        let mut place = QueuePlace {
+            limiter: self,
+            provider,
+            ticket,
+            admitted: false,
+        };
+
+        loop {
+            // Registered before checking so a wake-up in between is not lost
+            let changed = self.shared.changed.notified();
+            tokio::pin!(changed);
+            changed.as_mut().enable();
+
+            let admission = {
+                let limits = self
+                    .shared
+                    .limits
+                    .read()
+                    .unwrap()
+                    .get(provider)
+                    .cloned()
+                    .unwrap_or_default();
+                let mut providers = self.shared.providers.lock().unwrap();
+                let state = providers.entry(provider.to_string()).or_default();
+                let now = Instant::now();
+                state.prune(now);
+
+                if state.queue.front() != Some(&ticket) {
+                    Admission::Later
+                } else {
+                    let admission = state.admission(&limits, tokens, now);
+                    if let Admission::Now = admission {
+                        state.queue.pop_front();
+                        state.in_flight += 1;
+                        state.recent.push_back(Started { ticket, at: now, tokens });
+                    }
+                    admission
+                }
+            };
+
+            match admission {
+                Admission::Now => {
+                    place.admitted = true;
+                    // The next call in line may fit as well
+                    self.shared.changed.notify_waiters();
+                    return Permit {
+                        limiter: self.clone(),
+                        provider: provider.to_string(),
+                        ticket,
+                    };
+                }
+                Admission::Later => changed.await,
+                Admission::After(wait) => {
+                    tokio::select! {
+                        _ = changed => {}
+                        _ = tokio::time::sleep(wait) => {}
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Leaves the queue when `acquire` is dropped before its turn came
+struct QueuePlace<'a> {
+    limiter: &'a RateLimiter,
+    provider: &'a str,
+    ticket: u64,
+    admitted: bool,
+}
+
+impl Drop for QueuePlace<'_> {
+    fn drop(&mut self) {
+        if self.admitted {
+            return;
+        }
+        if let Some(state) = self.limiter.shared.providers.lock().unwrap().get_mut(self.provider) {
+            state.queue.retain(|ticket| *ticket != self.ticket);
+        }
+        self.limiter.shared.changed.notify_waiters();
+    }
+}
+
+/// A started call; frees its concurrency slot when dropped.
+pub struct Permit {
+    limiter: RateLimiter,
+    provider: String,
+    ticket: u64,
+}
+
+impl Permit {
+    /// Replaces the estimate with the tokens the provider reported.
+    pub fn settle(&self, tokens: u64) {
+        let mut providers = self.limiter.shared.providers.lock().unwrap();
+        let call = providers
+            .get_mut(&self.provider)
+            .and_then(|state| state.recent.iter_mut().find(|call| call.ticket == self.ticket));
+        if let Some(call) = call {
+            call.tokens = tokens;
+        }
+    }
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        if let Some(state) = self.limiter.shared.providers.lock().unwrap().get_mut(&self.provider) {
+            state.in_flight = state.in_flight.saturating_sub(1);
+        }
+        self.limiter.shared.changed.notify_waiters();
+    }
+}
@@ -0,0 +1,619 @@
+// Local mock LLM server for tests. It speaks just enough of the OpenAI,
+// Anthropic, Gemini, Cohere and Ollama wire formats (Mistral and OpenRouter
+// share OpenAI's) to exercise every provider path offline, including
+// streaming and error responses.
+//
+// Each provider is mounted under its own prefix, see `MockServer::base_url`.
+// Behaviour is picked by the request:
+// - API key `bad-key` gets a 401 in the provider's error shape.
+// - Model `rate-limited` gets a 429 with `Retry-After: 0`.
+// - Model `flaky` gets a 503 on its first request and succeeds afterwards.
+// - Model `too-long` gets a 400 context-length error.
+// - Model `stream-error` streams one delta, then an error event.
+// - Model `tool` answers with a `get_weather` call for Paris.
+// - Anything else answers "Hello from <prefix>" with 10 + 5 tokens of usage.
+// - Ollama's model management routes know only `mock-model`; pulling it
+//   streams progress up to `success`, anything else gets an error.
+// - Embedding requests get `[characters, words, 1.0]` for every input text
+//   (OpenAI-style replies list them in reverse, relying on `index`).
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+
+pub const MODEL: &str = "mock-model";
+pub const BAD_KEY: &str = "bad-key";
+pub const PROMPT_TOKENS: u64 = 10;
+pub const COMPLETION_TOKENS: u64 = 5;
+
+/// A request as the mock received it.
+#[derive(Debug, Clone)]
+pub struct Recorded {
+    pub method: String,
+    /// Path and query, including the provider prefix.
+    pub path: String,
+    /// Header names are lowercased.
+    pub headers: HashMap<String, String>,
+    pub body: serde_json::Value,
+}
+
+#[derive(Default)]
+struct State {
+    requests: Vec<Recorded>,
+    /// Requests seen per model, for `flaky`.
+    hits: HashMap<String, u32>,
+}
+
+pub struct MockServer {
+    addr: SocketAddr,
+    state: Arc<Mutex<State>>,
+    task: JoinHandle<()>,
+}
+
+impl MockServer {
+    pub async fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind mock server");
+        let addr = listener.local_addr().expect("mock server address");
+        let state = Arc::new(Mutex::new(State::default()));
+
+        let shared = state.clone();
+        let task = tokio::spawn(async move {
+            while let Ok((socket, _)) = listener.accept().await {
+                tokio::spawn(handle(socket, shared.clone()));
+            }
+        });
+
+        Self { addr, state, task }
+    }
+
+    /// Base URL to use as a request's `base_url` for `provider`.
+    pub fn base_url(&self, provider: &str) -> String {
+        format!("http://{}/{}", self.addr, flavor(provider))
+    }
+
+    pub fn requests(&self) -> Vec<Recorded> {
+        self.state.lock().unwrap().requests.clone()
+    }
+
+    pub fn last_request(&self) -> Recorded {
+        self.requests().pop().expect("no request reached the mock server")
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Prefix (and wire format) the mock serves a provider under.
+fn flavor(provider: &str) -> &'static str {
+    match provider {
+        "claude" | "anthropic" => "anthropic",
+        "gemini" => "gemini",
+        "cohere" => "cohere",
+        "ollama" => "ollama",
+        _ => "openai",
+    }
+}
+
+struct Reply {
+    status: u16,
+    content_type: &'static str,
+    headers: Vec<(&'static str, String)>,
+    /// Written one by one with a flush in between, so stream decoders see
+    /// the body arrive in pieces.
+    chunks: Vec<String>,
+}
+
+impl Reply {
+    fn json(status: u16, body: serde_json::Value) -> Self {
+        Self {
+            status,
+            content_type: "application/json",
+            headers: Vec::new(),
+            chunks: vec![body.to_string()],
+        }
+    }
+
+    fn sse(events: Vec<serde_json::Value>, done_marker: bool) -> Self {
+        // Each event is split in two to exercise buffering across chunks
+        let mut chunks = Vec::new();
+        for event in events {
+            let data = format!("data: {}\n\n", event);
+            let mut middle = data.len() / 2;
+            while !data.is_char_boundary(middle) {
+                middle += 1;
+            }
+            chunks.push(data[..middle].to_string());
+            chunks.push(data[middle..].to_string());
+        }
+        if done_marker {
+            chunks.push("data: [DONE]\n\n".to_string());
+        }
+        Self {
+            status: 200,
+            content_type: "text/event-stream",
+            headers: Vec::new(),
+            chunks,
+        }
+    }
+
+    fn ndjson(events: Vec<serde_json::Value>) -> Self {
+        Self {
+            status: 200,
+            content_type: "application/x-ndjson",
+            headers: Vec::new(),
+            chunks: events.iter().map(|event| format!("{}\n", event)).collect(),
+        }
+    }
+}
+
+async fn handle(mut socket: TcpStream, state: Arc<Mutex<State>>) {
+    let Some(request) = read_request(&mut socket).await else {
+        return;
+    };
+    let reply = respond(&request, &state);
+    state.lock().unwrap().requests.push(request);
+
+    let reason = match reply.status {
+        200 => "OK",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        429 => "Too Many Requests",
+        503 => "Service Unavailable",
+        _ => "Error",
+    };
+    let mut head = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nConnection: close\r\n",
+        reply.status, reason, reply.content_type
+    );
+    for (name, value) in &reply.headers {
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    head.push_str("\r\n");
+
+    if socket.write_all(head.as_bytes()).await.is_err() {
+        return;
+    }
+    for chunk in &reply.chunks {
+        if socket.write_all(chunk.as_bytes()).await.is_err() || socket.flush().await.is_err() {
+            return;
+        }
+        if reply.chunks.len() > 1 {
+            tokio::time::sleep(Duration::from_millis(2)).await;
+        }
+    }
+    let _ = socket.shutdown().await;
+}
+
+async fn read_request(socket: &mut TcpStream) -> Option<Recorded> {
+    let mut buffer = Vec::new();
+    let mut chunk = [0u8; 8192];
+    let header_end = loop {
+        let read = socket.read(&mut chunk).await.ok()?;
+        if read == 0 {
+            return None;
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+        if let Some(pos) = buffer.windows(4).position(|w| w == b"\r\n\r\n") {
+            break pos + 4;
+        }
+    };
+
+    let head = String::from_utf8_lossy(&buffer[..header_end]).to_string();
+    let mut lines = head.lines();
+    let mut request_line = lines.next()?.split_whitespace();
+    let method = request_line.next()?.to_string();
+    let path = request_line.next()?.to_string();
+    let headers: HashMap<String, String> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_lowercase(), value.trim().to_string()))
+        .collect();
+
+    let length: usize = headers
+        .get("content-length")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(0);
+    while buffer.len() < header_end + length {
+        let read = socket.read(&mut chunk).await.ok()?;
+        if read == 0 {
+            break;
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+    }
+    let body = serde_json::from_slice(&buffer[header_end..]).unwrap_or(serde_json::Value::Null);
+
+    Some(Recorded {
+        method,
+        path,
+        headers,
+        body,
+    })
+}
+
+fn respond(request: &Recorded, state: &Mutex<State>) -> Reply {
+    let (flavor, rest) = request.path[1..]
+        .split_once('/')
+        .unwrap_or((&request.path[1..], ""));
+    let rest = format!("/{}", rest);
+    let route = rest.split('?').next().unwrap_or_default();
+
+    if api_key(flavor, request).as_deref() == Some(BAD_KEY) {
+        return error_reply(flavor, 401, "authentication_error", "Invalid API key provided");
+    }
+
+    if request.method == "GET" {
+        return match (flavor, route) {
+            ("openai", "/models") => Reply::json(200, serde_json::json!({ "data": [{ "id": MODEL }] })),
+            ("openai", "/auth/key") => Reply::json(200, serde_json::json!({ "data": { "label": "mock" } })),
+            ("anthropic", "/v1/models") => Reply::json(
+                200,
+                serde_json::json!({ "data": [{ "id": MODEL, "display_name": "Mock Model" }] }),
+            ),
+            ("gemini", "/models") => Reply::json(
+                200,
+                serde_json::json!({ "models": [{
+                    "name": format!("models/{}", MODEL),
+                    "displayName": "Mock Model",
+                    "supportedGenerationMethods": ["generateContent"],
+                }] }),
+            ),
+            ("cohere", "/v1/models") => Reply::json(
+                200,
+                serde_json::json!({ "models": [{ "name": MODEL, "endpoints": ["chat"] }] }),
+            ),
+            ("ollama", "/api/tags") => Reply::json(200, serde_json::json!({ "models": [{ "name": MODEL }] })),
+            ("ollama", "/api/version") => Reply::json(200, serde_json::json!({ "version": "0.3.0" })),
+            ("ollama", "/api/ps") => Reply::json(
+                200,
+                serde_json::json!({ "models": [{
+                    "name": MODEL,
+                    "size": 5_000_000_000u64,
+                    "size_vram": 4_000_000_000u64,
+                    "expires_at": "2030-01-01T00:00:00Z",
+                    "details": { "parameter_size": "7B", "quantization_level": "Q4_0" },
+                }] }),
+            ),
+            _ => Reply::json(404, serde_json::json!({ "error": "not found" })),
+        };
+    }
+
+    if flavor == "ollama" {
+        if let Some(reply) = ollama_model_reply(route, &request.body) {
+            return reply;
+        }
+    }
+
+    if let Some(texts) = embedding_inputs(flavor, route, &request.body) {
+        return embeddings_reply(flavor, &texts);
+    }
+
+    // Gemini carries the model and streaming flag in the path
+    let (model, stream) = if flavor == "gemini" {
+        let call = route.trim_start_matches("/models/");
+        let (model, method) = call.split_once(':').unwrap_or((call, ""));
+        (model.to_string(), method == "streamGenerateContent")
+    } else {
+        (
+            request.body["model"].as_str().unwrap_or_default().to_string(),
+            request.body["stream"].as_bool().unwrap_or(false),
+        )
+    };
+
+    let hits = {
+        let mut state = state.lock().unwrap();
+        let hits = state.hits.entry(model.clone()).or_insert(0);
+        *hits += 1;
+        *hits
+    };
+
+    match model.as_str() {
+        "rate-limited" => {
+            let mut reply = error_reply(flavor, 429, "rate_limit_error", "Rate limit reached");
+            reply.headers.push(("Retry-After", "0".to_string()));
+            reply
+        }
+        "flaky" if hits == 1 => error_reply(flavor, 503, "overloaded_error", "Temporarily overloaded"),
+        "too-long" => error_reply(
+            flavor,
+            400,
+            "invalid_request_error",
+            "This model's maximum context length is 8192 tokens",
+        ),
+        "tool" => tool_reply(flavor),
+        "stream-error" if stream => stream_error_reply(flavor),
+        _ if stream => stream_reply(flavor, &format!("Hello from {}", flavor)),
+        _ => text_reply(flavor, &format!("Hello from {}", flavor)),
+    }
+}
+
+/// Replies to Ollama's pull, delete and show routes; `None` for other routes.
+fn ollama_model_reply(route: &str, body: &serde_json::Value) -> Option<Reply> {
+    let model = body["model"].as_str().unwrap_or_default();
+    let missing = || Reply::json(404, serde_json::json!({ "error": format!("model '{}' not found", model) }));
+
+    let reply = match route {
+        "/api/pull" if model == MODEL => Reply::ndjson(vec![
+            serde_json::json!({ "status": "pulling manifest" }),
+            serde_json::json!({ "status": "pulling abc123", "digest": "sha256:abc123", "total": 100, "completed": 40 }),
+            serde_json::json!({ "status": "pulling abc123", "digest": "sha256:abc123", "total": 100, "completed": 100 }),
+            serde_json::json!({ "status": "success" }),
+        ]),
+        "/api/pull" => Reply::ndjson(vec![
+            serde_json::json!({ "status": "pulling manifest" }),
+            serde_json::json!({ "error": "pull model manifest: file does not exist" }),
+        ]),
+        "/api/delete" if model == MODEL => Reply::json(200, serde_json::json!({})),
+        "/api/show" if model == MODEL => Reply::json(
+            200,
+            serde_json::json!({
+                "parameters": "num_ctx                        4096\nstop                           \"<|user|>\"\nstop                           \"<|end|>\"",
+                "template": "{{ .Prompt }}",
+                "details": { "family": "llama", "parameter_size": "7B", "quantization_level": "Q4_0" },
+                "model_info": { "general.architecture": "llama", "llama.context_length": 8192 },
+            }),
+        ),
+        "/api/delete" | "/api/show" => missing(),
+        _ => return None,
+    };
+    Some(reply)
+}
+
+/// The texts of an embeddings request, `None` for any other request.
+fn embedding_inputs(flavor: &str, route: &str, body: &serde_json::Value) -> Option<Vec<String>> {
+    let texts: Vec<&serde_json::Value> = match (flavor, route) {
+        ("openai", "/embeddings") | ("ollama", "/api/embed") => body["input"].as_array()?.iter().collect(),
+        ("cohere", "/v1/embed") => body["texts"].as_array()?.iter().collect(),
+        ("gemini", _) if route.ends_with(":batchEmbedContents") => body["requests"]
+            .as_array()?
+            .iter()
+            .map(|request| &request["content"]["parts"][0]["text"])
+            .collect(),
+        _ => return None,
+    };
+    Some(texts.iter().map(|text| text.as_str().unwrap_or_default().to_string()).collect())
+}
+
+fn embeddings_reply(flavor: &str, texts: &[String]) -> Reply {
+    let vectors: Vec<serde_json::Value> = texts
+        .iter()
+        .map(|text| serde_json::json!([text.chars().count(), text.split_whitespace().count(), 1.0]))
+        .collect();
+    let body = match flavor {
+        "gemini" => serde_json::json!({
+            "embeddings": vectors.iter().map(|values| serde_json::json!({ "values": values })).collect::<Vec<_>>(),
+        }),
+        "cohere" | "ollama" => serde_json::json!({ "embeddings": vectors }),
+        _ => serde_json::json!({
+            "data": vectors
+                .iter()
+                .enumerate()
+                .rev()
+                .map(|(index, embedding)| serde_json::json!({ "index": index, "embedding": embedding }))
+                .collect::<Vec<_>>(),
+        }),
+    };
+    Reply::json(200, body)
+}
+
+fn api_key(flavor: &str, request: &Recorded) -> Option<String> {
+    match flavor {
+        "anthropic" => request.headers.get("x-api-key").cloned(),
+        "gemini" => request.headers.get("x-goog-api-key").cloned(),
+        _ => request
+            .headers
+            .get("authorization")
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .map(String::from),
+    }
+}
+
+fn error_reply(flavor: &str, status: u16, kind: &str, message: &str) -> Reply {
+    let body = match flavor {
+        "anthropic" => serde_json::json!({ "type": "error", "error": { "type": kind, "message": message } }),
+        "gemini" => serde_json::json!({ "error": { "code": status, "message": message, "status": kind } }),
+        "cohere" => serde_json::json!({ "message": message }),
+        "ollama" => serde_json::json!({ "error": message }),
+        _ => serde_json::json!({ "error": { "message": message, "type": kind, "code": null } }),
+    };
+    Reply::json(status, body)
+}
+
+fn text_reply(flavor: &str, text: &str) -> Reply {
+    let body = match flavor {
+        "anthropic" => serde_json::json!({
+            "content": [{ "type": "text", "text": text }],
+            "stop_reason": "end_turn",
+            "usage": { "input_tokens": PROMPT_TOKENS, "output_tokens": COMPLETION_TOKENS },
+        }),
+        "gemini" => serde_json::json!({
+            "candidates": [{ "content": { "role": "model", "parts": [{ "text": text }] }, "finishReason": "STOP" }],
+            "usageMetadata": { "promptTokenCount": PROMPT_TOKENS, "candidatesTokenCount": COMPLETION_TOKENS },
+        }),
+        "cohere" => serde_json::json!({
+            "text": text,
+            "finish_reason": "COMPLETE",
+            "meta": { "billed_units": { "input_tokens": PROMPT_TOKENS, "output_tokens": COMPLETION_TOKENS } },
+        }),
+        "ollama" => serde_json::json!({
+            "message": { "role": "assistant", "content": text },
+            "done": true,
+            "done_reason": "stop",
+            "prompt_eval_count": PROMPT_TOKENS,
+            "eval_count": COMPLETION_TOKENS,
+        }),
+        _ => serde_json::json!({
+            "choices": [{ "message": { "role": "assistant", "content": text }, "finish_reason": "stop" }],
+            "usage": { "prompt_tokens": PROMPT_TOKENS, "completion_tokens": COMPLETION_TOKENS },
+        }),
+    };
+    Reply::json(200, body)
+}
+
+fn tool_reply(flavor: &str) -> Reply {
+    let body = match flavor {
+        "anthropic" => serde_json::json!({
+            "content": [{ "type": "tool_use", "id": "toolu_1", "name": "get_weather", "input": { "city": "Paris" } }],
+            "stop_reason": "tool_use",
+            "usage": { "input_tokens": PROMPT_TOKENS, "output_tokens": COMPLETION_TOKENS },
+        }),
+        "gemini" => serde_json::json!({
+            "candidates": [{ "content": { "role": "model", "parts": [
+                { "functionCall": { "name": "get_weather", "args": { "city": "Paris" } } }
+            ] } }],
+        }),
+        "cohere" => return text_reply(flavor, "Cohere does not call tools"),
+        "ollama" => serde_json::json!({
+            "message": { "role": "assistant", "content": "", "tool_calls": [
+                { "function": { "name": "get_weather", "arguments": { "city": "Paris" } } }
+            ] },
+            "done": true,
+        }),
+        _ => serde_json::json!({
+            "choices": [{ "message": { "role": "assistant", "content": null, "tool_calls": [{
+                "id": "call_1",
+                "type": "function",
+                "function": { "name": "get_weather", "arguments": "{\"city\":\"Paris\"}" },
+            }] }, "finish_reason": "tool_calls" }],
+        }),
+    };
+    Reply::json(200, body)
+}
+
+/// Deltas are the words of `text`, each but the first with a leading space.
+fn deltas(text: &str) -> Vec<String> {
+    text.split(' ')
+        .enumerate()
+        .map(|(i, word)| if i == 0 { word.to_string() } else { format!(" {}", word) })
+        .collect()
+}
+
+fn stream_reply(flavor: &str, text: &str) -> Reply {
+    let deltas = deltas(text);
+    match flavor {
+        "anthropic" => {
+            let mut events = vec![serde_json::json!({
+                "type": "message_start",
+                "message": { "usage": { "input_tokens": PROMPT_TOKENS, "output_tokens": 1 } },
+            })];
+            events.extend(deltas.iter().map(|delta| {
+                serde_json::json!({ "type": "content_block_delta", "delta": { "type": "text_delta", "text": delta } })
+            }));
+            events.push(serde_json::json!({
+                "type": "message_delta",
+                "delta": { "stop_reason": "end_turn" },
+                "usage": { "output_tokens": COMPLETION_TOKENS },
+            }));
+            events.push(serde_json::json!({ "type": "message_stop" }));
+            Reply::sse(events, false)
+        }
+        "gemini" => {
+            let last = deltas.len() - 1;
+            let events = deltas
+                .iter()
+                .enumerate()
+                .map(|(i, delta)| {
+                    let mut event = serde_json::json!({
+                        "candidates": [{ "content": { "role": "model", "parts": [{ "text": delta }] } }],
+                    });
+                    if i == last {
+                        event["candidates"][0]["finishReason"] = serde_json::json!("STOP");
+                        event["usageMetadata"] = serde_json::json!({
+                            "promptTokenCount": PROMPT_TOKENS,
+                            "candidatesTokenCount": COMPLETION_TOKENS,
+                        });
+                    }
+                    event
+                })
+                .collect();
+            Reply::sse(events, false)
+        }
+        "cohere" => {
+            let mut events = vec![serde_json::json!({ "event_type": "stream-start" })];
+            events.extend(
+                deltas
+                    .iter()
+                    .map(|delta| serde_json::json!({ "event_type": "text-generation", "text": delta })),
+            );
+            events.push(serde_json::json!({
+                "event_type": "stream-end",
+                "finish_reason": "COMPLETE",
+                "response": { "meta": { "billed_units": {
+                    "input_tokens": PROMPT_TOKENS,
+                    "output_tokens": COMPLETION_TOKENS,
+                } } },
+            }));
+            Reply::ndjson(events)
+        }
+        "ollama" => {
+            let mut events: Vec<_> = deltas
+                .iter()
+                .map(|delta| {
+                    serde_json::json!({ "message": { "role": "assistant", "content": delta }, "done": false })
+                })
+                .collect();
+            events.push(serde_json::json!({
+                "message": { "role": "assistant", "content": "" },
+                "done": true,
+                "done_reason": "stop",
+                "prompt_eval_count": PROMPT_TOKENS,
+                "eval_count": COMPLETION_TOKENS,
+            }));
+            Reply::ndjson(events)
+        }
+        _ => {
+            let mut events: Vec<_> = deltas
+                .iter()
+                .map(|delta| serde_json::json!({ "choices": [{ "delta": { "content": delta }, "finish_reason": null }] }))
+                .collect();
+            events.push(serde_json::json!({ "choices": [{ "delta": {}, "finish_reason": "stop" }] }));
+            events.push(serde_json::json!({
+                "choices": [],
+                "usage": { "prompt_tokens": PROMPT_TOKENS, "completion_tokens": COMPLETION_TOKENS },
+            }));
+            Reply::sse(events, true)
+        }
+    }
+}
+
+fn stream_error_reply(flavor: &str) -> Reply {
+    let error = serde_json::json!({ "message": "Overloaded mid-stream", "type": "overloaded_error" });
+    match flavor {
+        "anthropic" => Reply::sse(
+            vec![
+                serde_json::json!({ "type": "content_block_delta", "delta": { "text": "Partial" } }),
+                serde_json::json!({ "type": "error", "error": error }),
+            ],
+            false,
+        ),
+        "gemini" => Reply::sse(
+            vec![
+                serde_json::json!({ "candidates": [{ "content": { "parts": [{ "text": "Partial" }] } }] }),
+                serde_json::json!({ "error": { "code": 503, "message": "Overloaded mid-stream", "status": "UNAVAILABLE" } }),
+            ],
+            false,
+        ),
+        "ollama" => Reply::ndjson(vec![
+            serde_json::json!({ "message": { "content": "Partial" }, "done": false }),
+            serde_json::json!({ "error": "Overloaded mid-stream" }),
+        ]),
+        // Cohere has no error event; cut the stream short instead
+        "cohere" => Reply::ndjson(vec![
+            serde_json::json!({ "event_type": "text-generation", "text": "Partial" }),
+        ]),
+        _ => Reply::sse(
+            vec![
+                serde_json::json!({ "choices": [{ "delta": { "content": "Partial" } }] }),
+                serde_json::json!({ "error": error }),
+            ],
+            false,
+        ),
+    }
+}
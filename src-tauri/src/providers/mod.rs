@@ -0,0 +1,701 @@
+// LLM provider abstraction - every chat backend implements `LlmProvider`
+// and the `chat` command dispatches on the provider id.
+
+mod anthropic;
+mod cohere;
+mod content;
+mod context;
+mod embeddings;
+mod error;
+mod gemini;
+mod limits;
+mod ollama;
+mod ollama_models;
+mod openai;
+mod options;
+mod retry;
+mod stream;
+mod tools;
+
+#[cfg(test)]
+mod mock;
+#[cfg(test)]
+mod tests;
+
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::time::{Duration, SystemTime};
+
+use crate::usage::ModelPrice;
+
+use anthropic::Anthropic;
+use cohere::Cohere;
+use gemini::Gemini;
+use ollama::Ollama;
+use openai::OpenAiCompatible;
+pub use content::{inline_images, Content};
+pub use context::{context_window, fit_context, ContextPolicy, ContextStrategy, ContextTrim};
+pub use embeddings::{default_embedding_model, embed, EmbedPurpose};
+pub use error::ProviderError;
+pub use limits::{Permit, QueueStatus, RateLimiter, RateLimits};
+pub use ollama_models::{
+    delete_ollama_model, ollama_status, pull_ollama_model, running_ollama_models, show_ollama_model,
+    OllamaModelDetails, OllamaStatus, PullProgress, RunningModel,
+};
+pub use options::GenerationOptions;
+pub use retry::{ResponseMeta, RetryPolicy};
+pub use stream::{StreamChunk, StreamFormat, StreamSummary, Usage};
+pub use tools::{ToolCall, ToolDefinition};
+use stream::EventDecoder;
+
+/// One chat turn. Besides `system`/`user`/`assistant`, `tool` messages carry
+/// the result of a call the assistant requested in `tool_calls`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Message {
+    pub role: String,
+    /// Text, or text and image parts.
+    #[serde(default)]
+    pub content: Content,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tool_calls: Vec<ToolCall>,
+    /// For `tool` messages: the id of the call this result answers.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_call_id: Option<String>,
+    /// For `tool` messages: the tool that produced the result (Gemini and
+    /// Ollama match results by name rather than id).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+impl Message {
+    pub fn is_tool_result(&self) -> bool {
+        self.role == "tool"
+    }
+
+    pub fn system(content: String) -> Self {
+        Self {
+            role: "system".to_string(),
+            content: content.into(),
+            tool_calls: Vec::new(),
+            tool_call_id: None,
+            name: None,
+        }
+    }
+
+    pub fn user(content: String) -> Self {
+        Self {
+            role: "user".to_string(),
+            content: content.into(),
+            tool_calls: Vec::new(),
+            tool_call_id: None,
+            name: None,
+        }
+    }
+
+    pub fn assistant(content: String, tool_calls: Vec<ToolCall>) -> Self {
+        Self {
+            role: "assistant".to_string(),
+            content: content.into(),
+            tool_calls,
+            tool_call_id: None,
+            name: None,
+        }
+    }
+
+    pub fn tool_result(call: &ToolCall, content: String) -> Self {
+        Self {
+            role: "tool".to_string(),
+            content: content.into(),
+            tool_calls: Vec::new(),
+            tool_call_id: Some(call.id.clone()),
+            name: Some(call.name.clone()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ChatRequest {
+    pub provider: String,
+    pub model: String,
+    pub messages: Vec<Message>,
+    /// Functions the model may call; the reply then lists them in
+    /// `tool_calls` for the caller to run.
+    #[serde(default)]
+    pub tools: Vec<ToolDefinition>,
+    #[serde(default)]
+    pub options: GenerationOptions,
+    #[serde(default)]
+    pub api_key: Option<String>,
+    /// Overrides the provider's default endpoint (a proxy, a self-hosted
+    /// gateway, or a mock server in tests). Needs an explicit `api_key`:
+    /// stored keys are not sent to a caller-chosen URL.
+    #[serde(default)]
+    pub base_url: Option<String>,
+    /// Extra HTTP headers from a custom endpoint profile; only
+    /// OpenAI-compatible providers send them.
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ChatResponse {
+    pub content: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tool_calls: Vec<ToolCall>,
+    pub usage: Option<Usage>,
+    /// Estimated USD cost, filled in by the usage ledger.
+    pub cost: Option<f64>,
+    pub meta: ResponseMeta,
+}
+
+impl ChatRequest {
+    pub fn api_key(&self) -> Result<&str, ProviderError> {
+        require_key(&self.provider, self.api_key.as_deref())
+    }
+
+    pub fn base_url<'a>(&'a self, default: &'a str) -> &'a str {
+        resolve_base_url(self.base_url.as_deref(), default)
+    }
+
+    /// `options.system` followed by any `system` messages, for providers
+    /// that take the system prompt outside the conversation.
+    pub fn system_prompt(&self) -> Option<String> {
+        let parts: Vec<Cow<str>> = self
+            .options
+            .system
+            .iter()
+            .map(|system| Cow::Borrowed(system.as_str()))
+            .chain(
+                self.messages
+                    .iter()
+                    .filter(|m| m.role == "system")
+                    .map(|m| m.content.text()),
+            )
+            .filter(|text| !text.trim().is_empty())
+            .collect();
+        (!parts.is_empty()).then(|| parts.join("\n\n"))
+    }
+
+    /// The conversation without its `system` messages.
+    pub fn conversation(&self) -> Vec<&Message> {
+        self.messages.iter().filter(|m| m.role != "system").collect()
+    }
+
+    /// The conversation with the combined system prompt as its first
+    /// message, for providers that take it inline.
+    pub fn messages_with_system(&self) -> Vec<Message> {
+        let mut messages = Vec::with_capacity(self.messages.len() + 1);
+        if let Some(system) = self.system_prompt() {
+            messages.push(Message {
+                role: "system".to_string(),
+                content: system.into(),
+                tool_calls: Vec::new(),
+                tool_call_id: None,
+                name: None,
+            });
+        }
+        messages.extend(self.conversation().into_iter().cloned());
+        messages
+    }
+}
+
+/// Provider credentials for calls that are not tied to a conversation, such
+/// as listing models or checking a key.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Credentials {
+    pub provider: String,
+    #[serde(default)]
+    pub api_key: Option<String>,
+    #[serde(default)]
+    pub base_url: Option<String>,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+}
+
+impl Credentials {
+    pub fn api_key(&self) -> Result<&str, ProviderError> {
+        require_key(&self.provider, self.api_key.as_deref())
+    }
+
+    pub fn base_url<'a>(&'a self, default: &'a str) -> &'a str {
+        resolve_base_url(self.base_url.as_deref(), default)
+    }
+}
+
+fn resolve_base_url<'a>(custom: Option<&'a str>, default: &'a str) -> &'a str {
+    custom
+        .map(|url| url.trim().trim_end_matches('/'))
+        .filter(|url| !url.is_empty())
+        .unwrap_or(default)
+}
+
+/// Fills `api_key` from `stored_key` when the caller sent none. A stored key
+/// only goes to the provider's own endpoint: a call the caller redirected
+/// with `base_url` has to bring its own key, or the webview could send saved
+/// secrets to any host.
+pub fn attach_stored_key<F>(
+    provider: &str,
+    api_key: &mut Option<String>,
+    base_url: Option<&str>,
+    stored_key: F,
+) -> Result<(), ProviderError>
+where
+    F: FnOnce() -> Result<Option<String>, ProviderError>,
+{
+    if api_key.is_some() {
+        return Ok(());
+    }
+    if base_url.is_some_and(|url| !url.trim().is_empty()) {
+        return Err(ProviderError::bad_request(format!(
+            "A custom base URL for {} needs an explicit API key; stored keys are only sent to the provider itself",
+            provider
+        )));
+    }
+    *api_key = stored_key()?;
+    Ok(())
+}
+
+fn require_key<'a>(provider: &str, key: Option<&'a str>) -> Result<&'a str, ProviderError> {
+    match key {
+        Some(key) if !key.trim().is_empty() => Ok(key.trim()),
+        _ => Err(ProviderError::Auth {
+            message: format!("No API key configured for {}", provider),
+        }),
+    }
+}
+
+/// One entry of a provider's model catalog, normalized across providers.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelInfo {
+    pub id: String,
+    pub name: String,
+    pub context_window: Option<u64>,
+    /// `input->output` in OpenRouter notation, e.g. `text+image->text`.
+    pub modality: Option<String>,
+    pub pricing: Option<ModelPrice>,
+}
+
+impl ModelInfo {
+    pub fn new(id: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            name: id.to_string(),
+            context_window: None,
+            modality: None,
+            pricing: None,
+        }
+    }
+}
+
+/// Outcome of `validate_key`; `error.kind` tells a bad key apart from an
+/// unreachable provider.
+#[derive(Debug, Clone, Serialize)]
+pub struct KeyValidation {
+    pub provider: String,
+    pub valid: bool,
+    pub error: Option<ProviderError>,
+    /// Models the key can access (empty when validation failed).
+    pub models: Vec<String>,
+}
+
+pub trait LlmProvider: Send + Sync {
+    /// Builds the provider-specific HTTP request for a chat completion,
+    /// asking for an incremental response when `stream` is set.
+    fn chat_request(
+        &self,
+        client: &reqwest::Client,
+        request: &ChatRequest,
+        stream: bool,
+    ) -> Result<reqwest::RequestBuilder, ProviderError>;
+
+    /// Extracts the assistant reply from a successful response body.
+    fn parse_chat_response(&self, json: &serde_json::Value) -> Result<String, ProviderError>;
+
+    /// Extracts token counts from a successful response body.
+    fn parse_usage(&self, json: &serde_json::Value) -> Option<Usage>;
+
+    /// Extracts the tool calls the assistant requested, if any.
+    fn parse_tool_calls(&self, json: &serde_json::Value) -> Vec<ToolCall>;
+
+    /// Wire format of the streamed response body.
+    fn stream_format(&self) -> StreamFormat {
+        StreamFormat::Sse
+    }
+
+    /// Interprets one decoded event of a streamed response.
+    fn parse_stream_event(&self, json: &serde_json::Value) -> Result<StreamChunk, ProviderError>;
+
+    /// Builds the request listing the models available to these credentials.
+    fn models_request(
+        &self,
+        client: &reqwest::Client,
+        credentials: &Credentials,
+    ) -> Result<reqwest::RequestBuilder, ProviderError>;
+
+    /// Normalizes a model listing response.
+    fn parse_models(&self, json: &serde_json::Value) -> Vec<ModelInfo>;
+
+    /// Extra authenticated request for providers whose model listing is
+    /// public and so proves nothing about the key.
+    fn key_check_request(
+        &self,
+        _client: &reqwest::Client,
+        _credentials: &Credentials,
+    ) -> Option<Result<reqwest::RequestBuilder, ProviderError>> {
+        None
+    }
+
+    /// Builds the request embedding `texts`, or `None` when the provider has
+    /// no embeddings endpoint.
+    fn embeddings_request(
+        &self,
+        _client: &reqwest::Client,
+        _credentials: &Credentials,
+        _model: &str,
+        _texts: &[String],
+        _purpose: EmbedPurpose,
+    ) -> Option<Result<reqwest::RequestBuilder, ProviderError>> {
+        None
+    }
+
+    /// Extracts one vector per input text, in input order.
+    fn parse_embeddings(&self, _json: &serde_json::Value) -> Result<Vec<Vec<f32>>, ProviderError> {
+        Err(ProviderError::parse("Embeddings are not supported"))
+    }
+}
+
+static OPENAI: OpenAiCompatible = OpenAiCompatible {
+    base_url: "https://api.openai.com/v1",
+    extra_headers: &[],
+    temperature: Some(0.7),
+    seed_param: "seed",
+    stream_usage: true,
+    key_check_path: None,
+    requires_key: true,
+    embeddings: true,
+};
+
+static OPENROUTER: OpenAiCompatible = OpenAiCompatible {
+    base_url: "https://openrouter.ai/api/v1",
+    extra_headers: &[
+        ("HTTP-Referer", "https://bonzo-devassist.app"),
+        ("X-Title", "BONZO DevAssist AI"),
+    ],
+    temperature: None,
+    seed_param: "seed",
+    stream_usage: true,
+    // The model list is public; `/auth/key` rejects bad keys
+    key_check_path: Some("/auth/key"),
+    requires_key: true,
+    embeddings: false,
+};
+
+static MISTRAL: OpenAiCompatible = OpenAiCompatible {
+    base_url: "https://api.mistral.ai/v1",
+    extra_headers: &[],
+    temperature: None,
+    seed_param: "random_seed",
+    // Mistral always reports usage on the final chunk and rejects `stream_options`
+    stream_usage: false,
+    key_check_path: None,
+    requires_key: true,
+    embeddings: true,
+};
+
+/// Self-hosted servers speaking the OpenAI API (vLLM, LM Studio, llama.cpp,
+/// LocalAI); the base URL always comes from an endpoint profile.
+static CUSTOM: OpenAiCompatible = OpenAiCompatible {
+    base_url: "",
+    extra_headers: &[],
+    temperature: None,
+    seed_param: "seed",
+    // Not every server understands `stream_options`
+    stream_usage: false,
+    key_check_path: None,
+    requires_key: false,
+    embeddings: true,
+};
+
+pub fn provider_for(id: &str) -> Result<&'static dyn LlmProvider, ProviderError> {
+    match id {
+        "openai" => Ok(&OPENAI),
+        "claude" | "anthropic" => Ok(&Anthropic),
+        "openrouter" => Ok(&OPENROUTER),
+        "gemini" => Ok(&Gemini),
+        "mistral" => Ok(&MISTRAL),
+        "cohere" => Ok(&Cohere),
+        "ollama" => Ok(&Ollama),
+        custom if custom == "custom" || custom.starts_with("custom:") => Ok(&CUSTOM),
+        _ => Err(ProviderError::bad_request(format!("Unknown provider: {}", id))),
+    }
+}
+
+/// Everything a provider call needs besides the request itself.
+pub struct Transport {
+    pub client: reqwest::Client,
+    /// Maximum wait for the response headers, a full body, or the next
+    /// streamed chunk.
+    pub read_timeout: Duration,
+    pub retry: RetryPolicy,
+    pub limiter: RateLimiter,
+}
+
+impl Transport {
+    async fn within<T>(
+        &self,
+        what: &str,
+        future: impl Future<Output = Result<T, reqwest::Error>>,
+    ) -> Result<T, ProviderError> {
+        match tokio::time::timeout(self.read_timeout, future).await {
+            Ok(result) => Ok(result?),
+            Err(_) => Err(ProviderError::Network {
+                message: format!(
+                    "Timed out after {}s waiting for {}",
+                    self.read_timeout.as_secs(),
+                    what
+                ),
+            }),
+        }
+    }
+}
+
+/// Sends a single request and turns a non-success status into the matching
+/// `ProviderError`.
+async fn execute(
+    transport: &Transport,
+    builder: reqwest::RequestBuilder,
+) -> Result<reqwest::Response, ProviderError> {
+    let response = transport.within("the response", builder.send()).await?;
+
+    let status = response.status();
+    if !status.is_success() {
+        let retry_after = response
+            .headers()
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| retry::parse_retry_after(v, SystemTime::now()));
+        let error_text = response.text().await.unwrap_or_default();
+        return Err(ProviderError::from_response(
+            status.as_u16(),
+            retry_after,
+            &error_text,
+        ));
+    }
+
+    Ok(response)
+}
+
+/// Sends a chat request, retrying transient failures according to the
+/// transport's retry policy. Streams are only retried until the response
+/// headers arrive.
+async fn send(
+    transport: &Transport,
+    provider: &dyn LlmProvider,
+    request: &ChatRequest,
+    stream: bool,
+) -> Result<(reqwest::Response, ResponseMeta, Permit), ProviderError> {
+    let tokens = context::estimate_tokens(request) + request.options.max_tokens.unwrap_or(0) as u64;
+    send_with_retries(transport, &request.provider, tokens, || {
+        Ok(provider
+            .chat_request(&transport.client, request, stream)?
+            .header("Content-Type", "application/json"))
+    })
+    .await
+}
+
+/// Sends the request `build` makes, building a fresh one for every attempt.
+/// Each attempt waits for its own turn under `provider`'s rate limits, so
+/// retries count against them too; the permit of the attempt that got
+/// through is returned with the response.
+async fn send_with_retries<F>(
+    transport: &Transport,
+    provider: &str,
+    tokens: u64,
+    mut build: F,
+) -> Result<(reqwest::Response, ResponseMeta, Permit), ProviderError>
+where
+    F: FnMut() -> Result<reqwest::RequestBuilder, ProviderError>,
+{
+    let mut meta = ResponseMeta::default();
+
+    loop {
+        meta.attempts += 1;
+        let error = match build() {
+            Ok(builder) => {
+                let permit = transport.limiter.acquire(provider, tokens).await;
+                match execute(transport, builder).await {
+                    Ok(response) => return Ok((response, meta, permit)),
+                    Err(error) => {
+                        // A rejected attempt still counts as a request, but
+                        // used no tokens
+                        permit.settle(0);
+                        error
+                    }
+                }
+            }
+            Err(error) => error,
+        };
+
+        let Some(delay) = transport.retry.delay_after(meta.attempts, &error) else {
+            return Err(error);
+        };
+        meta.retries.push(retry::RetryRecord {
+            attempt: meta.attempts,
+            error,
+            delay_ms: delay.as_millis() as u64,
+        });
+        tokio::time::sleep(delay).await;
+    }
+}
+
+pub async fn chat(transport: &Transport, request: &ChatRequest) -> Result<ChatResponse, ProviderError> {
+    let provider = provider_for(&request.provider)?;
+    let (response, meta, permit) = send(transport, provider, request, false).await?;
+
+    let json: serde_json::Value = transport.within("the response body", response.json()).await?;
+
+    // A reply that only calls tools has no text to extract
+    let tool_calls = provider.parse_tool_calls(&json);
+    let content = match provider.parse_chat_response(&json) {
+        Ok(content) => content,
+        Err(_) if !tool_calls.is_empty() => String::new(),
+        Err(e) => return Err(e),
+    };
+
+    let usage = provider.parse_usage(&json);
+    settle(&permit, usage.as_ref());
+
+    Ok(ChatResponse {
+        content,
+        tool_calls,
+        usage,
+        cost: None,
+        meta,
+    })
+}
+
+/// Streams a chat completion, calling `on_delta` for every text fragment as it
+/// arrives, and returns the assembled reply once the provider finishes.
+pub async fn chat_stream<F>(
+    transport: &Transport,
+    request: &ChatRequest,
+    mut on_delta: F,
+) -> Result<StreamSummary, ProviderError>
+where
+    F: FnMut(&str),
+{
+    let provider = provider_for(&request.provider)?;
+    if !request.tools.is_empty() {
+        return Err(ProviderError::bad_request(
+            "Tool calling is only supported on non-streaming chat requests",
+        ));
+    }
+    // Held until the stream ends, so a long reply keeps its concurrency slot
+    let (mut response, meta, permit) = send(transport, provider, request, true).await?;
+
+    let mut decoder = EventDecoder::new(provider.stream_format());
+    let mut summary = StreamSummary {
+        meta,
+        ..StreamSummary::default()
+    };
+
+    while let Some(bytes) = transport
+        .within("the next chunk", response.chunk())
+        .await
+        .map_err(|e| ProviderError::Network {
+            message: format!("Stream interrupted: {}", e),
+        })?
+    {
+        for event in decoder.push(&bytes) {
+            if apply_stream_event(provider, &event, &mut summary, &mut on_delta)? {
+                settle(&permit, summary.usage.as_ref());
+                return Ok(summary);
+            }
+        }
+    }
+
+    for event in decoder.finish() {
+        if apply_stream_event(provider, &event, &mut summary, &mut on_delta)? {
+            break;
+        }
+    }
+
+    settle(&permit, summary.usage.as_ref());
+    Ok(summary)
+}
+
+// Counts the reported usage against the tokens-per-minute limit in place of
+// the estimate; providers that report none keep the estimate
+fn settle(permit: &Permit, usage: Option<&Usage>) {
+    if let Some(usage) = usage.filter(|u| u.prompt_tokens + u.completion_tokens > 0) {
+        permit.settle(usage.prompt_tokens + usage.completion_tokens);
+    }
+}
+
+/// Returns `true` once the provider signalled the end of the stream.
+fn apply_stream_event<F>(
+    provider: &dyn LlmProvider,
+    event: &str,
+    summary: &mut StreamSummary,
+    on_delta: &mut F,
+) -> Result<bool, ProviderError>
+where
+    F: FnMut(&str),
+{
+    if event == "[DONE]" {
+        return Ok(true);
+    }
+
+    let json: serde_json::Value = serde_json::from_str(event)
+        .map_err(|e| ProviderError::parse(format!("Failed to parse stream event: {}", e)))?;
+    let chunk = provider.parse_stream_event(&json)?;
+
+    if let Some(delta) = chunk.delta.as_deref().filter(|d| !d.is_empty()) {
+        on_delta(delta);
+    }
+    summary.apply(&chunk);
+
+    Ok(chunk.done)
+}
+
+async fn fetch_json(
+    transport: &Transport,
+    builder: reqwest::RequestBuilder,
+) -> Result<serde_json::Value, ProviderError> {
+    let response = execute(transport, builder).await?;
+    transport.within("the response body", response.json()).await
+}
+
+/// Lists the models the credentials can access. Not retried: this backs
+/// interactive checks where a fast answer matters more.
+pub async fn list_models(
+    transport: &Transport,
+    credentials: &Credentials,
+) -> Result<Vec<ModelInfo>, ProviderError> {
+    let provider = provider_for(&credentials.provider)?;
+
+    if let Some(check) = provider.key_check_request(&transport.client, credentials) {
+        fetch_json(transport, check?).await?;
+    }
+
+    let json = fetch_json(transport, provider.models_request(&transport.client, credentials)?).await?;
+    Ok(provider.parse_models(&json))
+}
+
+/// Performs the cheapest authenticated call for the provider.
+pub async fn validate_key(transport: &Transport, credentials: &Credentials) -> KeyValidation {
+    let result = list_models(transport, credentials).await;
+
+    let (models, error) = match result {
+        Ok(models) => (models.into_iter().map(|m| m.id).collect(), None),
+        Err(error) => (Vec::new(), Some(error)),
+    };
+    KeyValidation {
+        provider: credentials.provider.clone(),
+        valid: error.is_none(),
+        error,
+        models,
+    }
+}
@@ -0,0 +1,216 @@
+use super::embeddings::parse_vectors;
+use super::openai::{parse_model_list, wire_tool};
+use super::options::set_opt;
+use super::tools::{parse_arguments, synthetic_id};
+use super::{
+    ChatRequest, Credentials, EmbedPurpose, LlmProvider, Message, ModelInfo, ProviderError, StreamChunk,
+    StreamFormat, ToolCall, Usage,
+};
+
+pub const DEFAULT_BASE_URL: &str = "http://localhost:11434";
+const DEFAULT_PORT: u16 = 11434;
+
+/// A checked Ollama server address. Accepts what users tend to type
+/// (`localhost`, `host:11434/`, `http://host/api`) and keeps scheme, host,
+/// port and any reverse-proxy path prefix.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OllamaEndpoint(String);
+
+impl OllamaEndpoint {
+    pub fn parse(input: &str) -> Result<Self, ProviderError> {
+        let input = input.trim();
+        if input.is_empty() {
+            return Ok(Self(DEFAULT_BASE_URL.to_string()));
+        }
+
+        // Like `OLLAMA_HOST`, a bare host means plain HTTP on Ollama's port
+        let bare = !input.contains("://");
+        let invalid = |reason: String| ProviderError::bad_request(format!("Invalid Ollama URL {}: {}", input, reason));
+        let mut url = reqwest::Url::parse(&if bare { format!("http://{}", input) } else { input.to_string() })
+            .map_err(|e| invalid(e.to_string()))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(invalid(format!("unsupported scheme {}", url.scheme())));
+        }
+        if url.host_str().is_none_or(str::is_empty) {
+            return Err(invalid("missing host".to_string()));
+        }
+        if bare && url.port().is_none() {
+            let _ = url.set_port(Some(DEFAULT_PORT));
+        }
+        url.set_query(None);
+        url.set_fragment(None);
+
+        let url = url.as_str().trim_end_matches('/');
+        Ok(Self(url.strip_suffix("/api").unwrap_or(url).to_string()))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// `path` starts with `/api/`.
+    pub fn url(&self, path: &str) -> String {
+        format!("{}{}", self.0, path)
+    }
+}
+
+pub struct Ollama;
+
+impl LlmProvider for Ollama {
+    fn chat_request(
+        &self,
+        client: &reqwest::Client,
+        request: &ChatRequest,
+        stream: bool,
+    ) -> Result<reqwest::RequestBuilder, ProviderError> {
+        let options = &request.options;
+        let mut payload = serde_json::json!({
+            "model": request.model,
+            "messages": request.messages_with_system().iter().map(wire_message).collect::<Vec<_>>(),
+            "stream": stream,
+        });
+        if !request.tools.is_empty() {
+            payload["tools"] = request.tools.iter().map(wire_tool).collect();
+        }
+
+        // Sampling settings live in the model `options` object
+        let mut model_options = serde_json::json!({});
+        set_opt(&mut model_options, "temperature", options.temperature);
+        set_opt(&mut model_options, "top_p", options.top_p);
+        set_opt(&mut model_options, "num_predict", options.max_tokens);
+        set_opt(&mut model_options, "stop", options.stop());
+        set_opt(&mut model_options, "seed", options.seed);
+        if model_options.as_object().is_some_and(|o| !o.is_empty()) {
+            payload["options"] = model_options;
+        }
+        if options.json_mode {
+            payload["format"] = serde_json::json!("json");
+        }
+
+        let endpoint = OllamaEndpoint::parse(request.base_url(DEFAULT_BASE_URL))?;
+        Ok(client.post(endpoint.url("/api/chat")).json(&payload))
+    }
+
+    fn parse_chat_response(&self, json: &serde_json::Value) -> Result<String, ProviderError> {
+        json["message"]["content"]
+            .as_str()
+            .map(String::from)
+            .ok_or_else(|| ProviderError::parse("Invalid response format"))
+    }
+
+    fn parse_usage(&self, json: &serde_json::Value) -> Option<Usage> {
+        parse_usage(json)
+    }
+
+    fn stream_format(&self) -> StreamFormat {
+        StreamFormat::Ndjson
+    }
+
+    fn parse_tool_calls(&self, json: &serde_json::Value) -> Vec<ToolCall> {
+        json["message"]["tool_calls"]
+            .as_array()
+            .map(|calls| {
+                calls
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(index, call)| {
+                        let name = call["function"]["name"].as_str()?;
+                        Some(ToolCall {
+                            id: synthetic_id(name, index),
+                            name: name.to_string(),
+                            arguments: parse_arguments(&call["function"]["arguments"]),
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn parse_stream_event(&self, json: &serde_json::Value) -> Result<StreamChunk, ProviderError> {
+        if json["error"].is_string() {
+            return Err(ProviderError::from_body(json));
+        }
+
+        let done = json["done"].as_bool().unwrap_or(false);
+        Ok(StreamChunk {
+            delta: json["message"]["content"].as_str().map(String::from),
+            finish_reason: json["done_reason"].as_str().map(String::from),
+            usage: if done { parse_usage(json) } else { None },
+            done,
+        })
+    }
+
+    // No key involved; this checks that the server is reachable
+    fn models_request(
+        &self,
+        client: &reqwest::Client,
+        credentials: &Credentials,
+    ) -> Result<reqwest::RequestBuilder, ProviderError> {
+        let endpoint = OllamaEndpoint::parse(credentials.base_url(DEFAULT_BASE_URL))?;
+        Ok(client.get(endpoint.url("/api/tags")))
+    }
+
+    fn parse_models(&self, json: &serde_json::Value) -> Vec<ModelInfo> {
+        parse_model_list(&json["models"], |model| Some(ModelInfo::new(model["name"].as_str()?)))
+    }
+
+    fn embeddings_request(
+        &self,
+        client: &reqwest::Client,
+        credentials: &Credentials,
+        model: &str,
+        texts: &[String],
+        _purpose: EmbedPurpose,
+    ) -> Option<Result<reqwest::RequestBuilder, ProviderError>> {
+        let payload = serde_json::json!({ "model": model, "input": texts });
+        Some(
+            OllamaEndpoint::parse(credentials.base_url(DEFAULT_BASE_URL))
+                .map(|endpoint| client.post(endpoint.url("/api/embed")).json(&payload)),
+        )
+    }
+
+    fn parse_embeddings(&self, json: &serde_json::Value) -> Result<Vec<Vec<f32>>, ProviderError> {
+        let embeddings = json["embeddings"]
+            .as_array()
+            .ok_or_else(|| ProviderError::parse("Invalid embeddings response"))?;
+        parse_vectors(embeddings)
+    }
+}
+
+// Same shape as OpenAI, except arguments are a JSON object and results are
+// matched by tool name instead of call id
+fn wire_message(message: &Message) -> serde_json::Value {
+    let mut wire = serde_json::json!({
+        "role": message.role,
+        "content": message.content.text(),
+    });
+    let images = message.content.images();
+    if !images.is_empty() {
+        wire["images"] = images.iter().map(|image| image.data).collect();
+    }
+    if let Some(name) = message.name.as_deref().filter(|_| message.is_tool_result()) {
+        wire["tool_name"] = serde_json::json!(name);
+    }
+    if !message.tool_calls.is_empty() {
+        wire["tool_calls"] = message
+            .tool_calls
+            .iter()
+            .map(|call| {
+                serde_json::json!({
+                    "function": { "name": call.name, "arguments": call.arguments }
+                })
+            })
+            .collect();
+    }
+    wire
+}
+
+// Ollama reports counts at the top level of the final (or only) message
+fn parse_usage(json: &serde_json::Value) -> Option<Usage> {
+    let prompt_tokens = json["prompt_eval_count"].as_u64();
+    let completion_tokens = json["eval_count"].as_u64();
+    (prompt_tokens.is_some() || completion_tokens.is_some()).then(|| Usage {
+        prompt_tokens: prompt_tokens.unwrap_or(0),
+        completion_tokens: completion_tokens.unwrap_or(0),
+    })
+}
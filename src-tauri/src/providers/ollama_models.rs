@@ -0,0 +1,271 @@
+// Local model management through Ollama's own API: server status, pulling,
+// deleting and inspecting models, and listing the ones currently loaded.
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use super::ollama::{OllamaEndpoint, DEFAULT_BASE_URL};
+use super::stream::EventDecoder;
+use super::{execute, fetch_json, Credentials, ProviderError, StreamFormat, Transport};
+
+/// One status line of a pull; `total` and `completed` count the bytes of
+/// the layer named by `digest`.
+#[derive(Debug, Clone, Serialize)]
+pub struct PullProgress {
+    pub status: String,
+    pub digest: Option<String>,
+    pub total: Option<u64>,
+    pub completed: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OllamaModelDetails {
+    pub name: String,
+    pub family: Option<String>,
+    pub parameter_size: Option<String>,
+    pub quantization_level: Option<String>,
+    /// Context length the model was trained with, from its metadata.
+    pub context_length: Option<u64>,
+    /// Modelfile `PARAMETER`s; some, like `stop`, repeat.
+    pub parameters: BTreeMap<String, Vec<String>>,
+    pub template: Option<String>,
+    pub system: Option<String>,
+    pub license: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RunningModel {
+    pub name: String,
+    /// Bytes held in memory, `size_vram` of them on the GPU.
+    pub size: u64,
+    pub size_vram: u64,
+    pub parameter_size: Option<String>,
+    pub quantization_level: Option<String>,
+    /// When Ollama unloads the model if it stays idle (RFC 3339).
+    pub expires_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OllamaStatus {
+    /// The normalized base URL, or the input when it could not be parsed.
+    pub base_url: String,
+    pub reachable: bool,
+    pub version: Option<String>,
+    /// Installed models.
+    pub models: Vec<String>,
+    /// Whether the model asked about is installed; `None` when none was
+    /// asked about or the server could not be reached.
+    pub model_available: Option<bool>,
+    pub error: Option<ProviderError>,
+}
+
+fn url(credentials: &Credentials, path: &str) -> Result<String, ProviderError> {
+    Ok(OllamaEndpoint::parse(credentials.base_url(DEFAULT_BASE_URL))?.url(path))
+}
+
+fn text(json: &serde_json::Value) -> Option<String> {
+    json.as_str().filter(|s| !s.is_empty()).map(String::from)
+}
+
+/// Tells an unreachable server apart from a missing model: checks the
+/// address, asks for the version, then looks `model` up among the installed
+/// ones (a name without a tag means `:latest`).
+pub async fn ollama_status(transport: &Transport, credentials: &Credentials, model: Option<&str>) -> OllamaStatus {
+    let mut status = OllamaStatus {
+        base_url: credentials.base_url(DEFAULT_BASE_URL).to_string(),
+        reachable: false,
+        version: None,
+        models: Vec::new(),
+        model_available: None,
+        error: None,
+    };
+    let endpoint = match OllamaEndpoint::parse(&status.base_url) {
+        Ok(endpoint) => endpoint,
+        Err(error) => {
+            status.error = Some(error);
+            return status;
+        }
+    };
+    status.base_url = endpoint.as_str().to_string();
+
+    match fetch_json(transport, transport.client.get(endpoint.url("/api/version"))).await {
+        Ok(json) => {
+            status.reachable = true;
+            status.version = text(&json["version"]);
+        }
+        Err(ProviderError::Network { message }) => {
+            status.error = Some(ProviderError::Network {
+                message: format!("Ollama is not running at {} ({})", endpoint.as_str(), message),
+            });
+            return status;
+        }
+        Err(error) => {
+            status.error = Some(error);
+            return status;
+        }
+    }
+
+    match fetch_json(transport, transport.client.get(endpoint.url("/api/tags"))).await {
+        Ok(json) => {
+            status.models = json["models"]
+                .as_array()
+                .map(|models| models.iter().filter_map(|m| text(&m["name"])).collect())
+                .unwrap_or_default();
+            status.model_available = model.map(|model| {
+                let tagged = if model.contains(':') { model.to_string() } else { format!("{}:latest", model) };
+                status.models.iter().any(|name| *name == tagged || name == model)
+            });
+        }
+        Err(error) => status.error = Some(error),
+    }
+    status
+}
+
+/// Downloads `name`, calling `on_progress` for every status line. Only
+/// succeeds once Ollama reports the pull complete.
+pub async fn pull_ollama_model<F>(
+    transport: &Transport,
+    credentials: &Credentials,
+    name: &str,
+    mut on_progress: F,
+) -> Result<(), ProviderError>
+where
+    F: FnMut(PullProgress),
+{
+    let builder = transport
+        .client
+        .post(url(credentials, "/api/pull")?)
+        .json(&serde_json::json!({ "model": name, "stream": true }));
+    let mut response = execute(transport, builder).await?;
+
+    let mut decoder = EventDecoder::new(StreamFormat::Ndjson);
+    let mut finished = false;
+    loop {
+        let bytes = transport
+            .within("pull progress", response.chunk())
+            .await
+            .map_err(|e| ProviderError::Network {
+                message: format!("Pull interrupted: {}", e),
+            })?;
+        let events = match &bytes {
+            Some(bytes) => decoder.push(bytes),
+            None => decoder.finish(),
+        };
+
+        for event in events {
+            let json: serde_json::Value = serde_json::from_str(&event)
+                .map_err(|e| ProviderError::parse(format!("Failed to parse pull progress: {}", e)))?;
+            if json.get("error").is_some() {
+                return Err(ProviderError::from_body(&json));
+            }
+
+            let progress = PullProgress {
+                status: json["status"].as_str().unwrap_or_default().to_string(),
+                digest: text(&json["digest"]),
+                total: json["total"].as_u64(),
+                completed: json["completed"].as_u64(),
+            };
+            finished |= progress.status == "success";
+            on_progress(progress);
+        }
+
+        if bytes.is_none() {
+            break;
+        }
+    }
+
+    if finished {
+        Ok(())
+    } else {
+        Err(ProviderError::Network {
+            message: format!("The pull of {} ended before it completed", name),
+        })
+    }
+}
+
+pub async fn delete_ollama_model(
+    transport: &Transport,
+    credentials: &Credentials,
+    name: &str,
+) -> Result<(), ProviderError> {
+    let builder = transport
+        .client
+        .delete(url(credentials, "/api/delete")?)
+        .json(&serde_json::json!({ "model": name }));
+    execute(transport, builder).await?;
+    Ok(())
+}
+
+pub async fn show_ollama_model(
+    transport: &Transport,
+    credentials: &Credentials,
+    name: &str,
+) -> Result<OllamaModelDetails, ProviderError> {
+    let builder = transport
+        .client
+        .post(url(credentials, "/api/show")?)
+        .json(&serde_json::json!({ "model": name }));
+    let json = fetch_json(transport, builder).await?;
+
+    // Metadata keys are prefixed with the architecture, e.g. `llama.context_length`
+    let context_length = json["model_info"].as_object().and_then(|info| {
+        info.iter()
+            .find(|(key, _)| key.ends_with(".context_length"))
+            .and_then(|(_, value)| value.as_u64())
+    });
+
+    let details = &json["details"];
+    Ok(OllamaModelDetails {
+        name: name.to_string(),
+        family: text(&details["family"]),
+        parameter_size: text(&details["parameter_size"]),
+        quantization_level: text(&details["quantization_level"]),
+        context_length,
+        parameters: parse_parameters(json["parameters"].as_str().unwrap_or_default()),
+        template: text(&json["template"]),
+        system: text(&json["system"]),
+        license: text(&json["license"]),
+    })
+}
+
+pub async fn running_ollama_models(
+    transport: &Transport,
+    credentials: &Credentials,
+) -> Result<Vec<RunningModel>, ProviderError> {
+    let json = fetch_json(transport, transport.client.get(url(credentials, "/api/ps")?)).await?;
+
+    Ok(json["models"]
+        .as_array()
+        .map(|models| {
+            models
+                .iter()
+                .filter_map(|model| {
+                    Some(RunningModel {
+                        name: model["name"].as_str()?.to_string(),
+                        size: model["size"].as_u64().unwrap_or(0),
+                        size_vram: model["size_vram"].as_u64().unwrap_or(0),
+                        parameter_size: text(&model["details"]["parameter_size"]),
+                        quantization_level: text(&model["details"]["quantization_level"]),
+                        expires_at: text(&model["expires_at"]),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default())
+}
+
+// One `name value` pair per line, values optionally quoted
+fn parse_parameters(text: &str) -> BTreeMap<String, Vec<String>> {
+    let mut parameters: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for line in text.lines() {
+        if let Some((name, value)) = line.trim().split_once(char::is_whitespace) {
+            let value = value.trim();
+            let value = value
+                .strip_prefix('"')
+                .and_then(|v| v.strip_suffix('"'))
+                .unwrap_or(value);
+            parameters.entry(name.to_string()).or_default().push(value.to_string());
+        }
+    }
+    parameters
+}
@@ -0,0 +1,315 @@
+use super::embeddings::parse_vectors;
+use super::options::set_opt;
+use super::tools::{parse_arguments, synthetic_id};
+use super::{
+    ChatRequest, Content, Credentials, EmbedPurpose, LlmProvider, Message, ModelInfo, ProviderError,
+    StreamChunk, ToolCall, ToolDefinition, Usage,
+};
+use crate::usage::ModelPrice;
+use std::collections::BTreeMap;
+
+/// Any backend speaking the OpenAI chat completions wire format
+/// (OpenAI itself, OpenRouter, Mistral).
+pub struct OpenAiCompatible {
+    /// Used unless the request carries its own `base_url`.
+    pub base_url: &'static str,
+    pub extra_headers: &'static [(&'static str, &'static str)],
+    /// Used when the request does not set a temperature.
+    pub temperature: Option<f32>,
+    /// Name of the sampling seed parameter (`random_seed` on Mistral).
+    pub seed_param: &'static str,
+    /// Whether to request a trailing usage chunk via `stream_options`.
+    pub stream_usage: bool,
+    /// Authenticated endpoint to check a key against when `/models` is public.
+    pub key_check_path: Option<&'static str>,
+    /// Self-hosted servers often run without authentication.
+    pub requires_key: bool,
+    /// Whether the backend serves `/embeddings` (OpenRouter does not).
+    pub embeddings: bool,
+}
+
+impl OpenAiCompatible {
+    fn url(&self, base_url: &str, path: &str) -> Result<String, ProviderError> {
+        if base_url.is_empty() {
+            return Err(ProviderError::bad_request("No base URL configured for this endpoint"));
+        }
+        Ok(format!("{}{}", base_url, path))
+    }
+
+    /// Adds the bearer token (unless the key is optional and missing) and
+    /// any static or per-request headers.
+    fn authorize(
+        &self,
+        mut builder: reqwest::RequestBuilder,
+        api_key: Result<&str, ProviderError>,
+        headers: &BTreeMap<String, String>,
+    ) -> Result<reqwest::RequestBuilder, ProviderError> {
+        match api_key {
+            Ok(key) => builder = builder.header("Authorization", format!("Bearer {}", key)),
+            Err(_) if !self.requires_key => {}
+            Err(e) => return Err(e),
+        }
+        for (name, value) in self.extra_headers {
+            builder = builder.header(*name, *value);
+        }
+        for (name, value) in headers {
+            builder = builder.header(name, value);
+        }
+        Ok(builder)
+    }
+}
+
+impl LlmProvider for OpenAiCompatible {
+    fn chat_request(
+        &self,
+        client: &reqwest::Client,
+        request: &ChatRequest,
+        stream: bool,
+    ) -> Result<reqwest::RequestBuilder, ProviderError> {
+        let options = &request.options;
+        let mut payload = serde_json::json!({
+            "model": request.model,
+            "messages": request.messages_with_system().iter().map(wire_message).collect::<Vec<_>>(),
+        });
+        if !request.tools.is_empty() {
+            payload["tools"] = request.tools.iter().map(wire_tool).collect();
+        }
+        set_opt(&mut payload, "temperature", options.temperature.or(self.temperature));
+        set_opt(&mut payload, "top_p", options.top_p);
+        set_opt(&mut payload, "max_tokens", options.max_tokens);
+        set_opt(&mut payload, "stop", options.stop());
+        set_opt(&mut payload, self.seed_param, options.seed);
+        if options.json_mode {
+            payload["response_format"] = serde_json::json!({ "type": "json_object" });
+        }
+        if stream {
+            payload["stream"] = serde_json::json!(true);
+            if self.stream_usage {
+                payload["stream_options"] = serde_json::json!({ "include_usage": true });
+            }
+        }
+
+        let url = self.url(request.base_url(self.base_url), "/chat/completions")?;
+        let builder = self.authorize(client.post(url), request.api_key(), &request.headers)?;
+
+        Ok(builder.json(&payload))
+    }
+
+    fn parse_chat_response(&self, json: &serde_json::Value) -> Result<String, ProviderError> {
+        json["choices"][0]["message"]["content"]
+            .as_str()
+            .map(String::from)
+            .ok_or_else(|| ProviderError::parse("Invalid response format"))
+    }
+
+    fn parse_usage(&self, json: &serde_json::Value) -> Option<Usage> {
+        parse_usage(&json["usage"])
+    }
+
+    fn parse_tool_calls(&self, json: &serde_json::Value) -> Vec<ToolCall> {
+        json["choices"][0]["message"]["tool_calls"]
+            .as_array()
+            .map(|calls| {
+                calls
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(index, call)| {
+                        let name = call["function"]["name"].as_str()?;
+                        Some(ToolCall {
+                            id: call["id"]
+                                .as_str()
+                                .map(String::from)
+                                .unwrap_or_else(|| synthetic_id(name, index)),
+                            name: name.to_string(),
+                            arguments: parse_arguments(&call["function"]["arguments"]),
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn parse_stream_event(&self, json: &serde_json::Value) -> Result<StreamChunk, ProviderError> {
+        if json.get("error").is_some() {
+            return Err(ProviderError::from_body(json));
+        }
+
+        let choice = &json["choices"][0];
+        Ok(StreamChunk {
+            delta: choice["delta"]["content"].as_str().map(String::from),
+            finish_reason: choice["finish_reason"].as_str().map(String::from),
+            usage: parse_usage(&json["usage"]),
+            done: false,
+        })
+    }
+
+    fn models_request(
+        &self,
+        client: &reqwest::Client,
+        credentials: &Credentials,
+    ) -> Result<reqwest::RequestBuilder, ProviderError> {
+        let url = self.url(credentials.base_url(self.base_url), "/models")?;
+        self.authorize(client.get(url), credentials.api_key(), &credentials.headers)
+    }
+
+    fn parse_models(&self, json: &serde_json::Value) -> Vec<ModelInfo> {
+        parse_model_list(&json["data"], parse_model)
+    }
+
+    fn key_check_request(
+        &self,
+        client: &reqwest::Client,
+        credentials: &Credentials,
+    ) -> Option<Result<reqwest::RequestBuilder, ProviderError>> {
+        let path = self.key_check_path?;
+        Some(
+            self.url(credentials.base_url(self.base_url), path)
+                .and_then(|url| self.authorize(client.get(url), credentials.api_key(), &credentials.headers)),
+        )
+    }
+
+    fn embeddings_request(
+        &self,
+        client: &reqwest::Client,
+        credentials: &Credentials,
+        model: &str,
+        texts: &[String],
+        _purpose: EmbedPurpose,
+    ) -> Option<Result<reqwest::RequestBuilder, ProviderError>> {
+        if !self.embeddings {
+            return None;
+        }
+        let payload = serde_json::json!({ "model": model, "input": texts });
+        Some(
+            self.url(credentials.base_url(self.base_url), "/embeddings")
+                .and_then(|url| self.authorize(client.post(url), credentials.api_key(), &credentials.headers))
+                .map(|builder| builder.json(&payload)),
+        )
+    }
+
+    // Entries carry their input position in `index`
+    fn parse_embeddings(&self, json: &serde_json::Value) -> Result<Vec<Vec<f32>>, ProviderError> {
+        let mut data: Vec<&serde_json::Value> = json["data"]
+            .as_array()
+            .ok_or_else(|| ProviderError::parse("Invalid embeddings response"))?
+            .iter()
+            .collect();
+        data.sort_by_key(|entry| entry["index"].as_u64().unwrap_or(0));
+        parse_vectors(data.into_iter().map(|entry| &entry["embedding"]))
+    }
+}
+
+fn wire_message(message: &Message) -> serde_json::Value {
+    if message.is_tool_result() {
+        let mut wire = serde_json::json!({
+            "role": "tool",
+            "tool_call_id": message.tool_call_id,
+            "content": message.content.text(),
+        });
+        if let Some(name) = &message.name {
+            wire["name"] = serde_json::json!(name);
+        }
+        return wire;
+    }
+
+    let mut wire = serde_json::json!({
+        "role": message.role,
+        "content": wire_content(&message.content),
+    });
+    if !message.tool_calls.is_empty() {
+        wire["tool_calls"] = message
+            .tool_calls
+            .iter()
+            .map(|call| {
+                serde_json::json!({
+                    "id": call.id,
+                    "type": "function",
+                    "function": {
+                        "name": call.name,
+                        "arguments": call.arguments.to_string(),
+                    },
+                })
+            })
+            .collect();
+    }
+    wire
+}
+
+// Plain string unless there are images, which go as `image_url` data URLs
+fn wire_content(content: &Content) -> serde_json::Value {
+    let images = content.images();
+    if images.is_empty() {
+        return serde_json::json!(content.text());
+    }
+
+    let mut parts = Vec::new();
+    let text = content.text();
+    if !text.is_empty() {
+        parts.push(serde_json::json!({ "type": "text", "text": text }));
+    }
+    for image in images {
+        parts.push(serde_json::json!({
+            "type": "image_url",
+            "image_url": { "url": image.data_url() },
+        }));
+    }
+    serde_json::json!(parts)
+}
+
+pub(super) fn wire_tool(tool: &ToolDefinition) -> serde_json::Value {
+    serde_json::json!({
+        "type": "function",
+        "function": {
+            "name": tool.name,
+            "description": tool.description,
+            "parameters": tool.parameters,
+        },
+    })
+}
+
+fn parse_usage(usage: &serde_json::Value) -> Option<Usage> {
+    usage.is_object().then(|| Usage {
+        prompt_tokens: usage["prompt_tokens"].as_u64().unwrap_or(0),
+        completion_tokens: usage["completion_tokens"].as_u64().unwrap_or(0),
+    })
+}
+
+/// Applies `parse` to every entry of a model listing array, skipping entries
+/// it rejects.
+pub(super) fn parse_model_list<F>(list: &serde_json::Value, parse: F) -> Vec<ModelInfo>
+where
+    F: Fn(&serde_json::Value) -> Option<ModelInfo>,
+{
+    list.as_array()
+        .map(|models| models.iter().filter_map(parse).collect())
+        .unwrap_or_default()
+}
+
+// OpenAI only returns ids; OpenRouter and Mistral add names, context sizes,
+// modalities and (OpenRouter) per-token prices.
+fn parse_model(model: &serde_json::Value) -> Option<ModelInfo> {
+    let mut info = ModelInfo::new(model["id"].as_str()?);
+    if let Some(name) = model["name"].as_str().filter(|n| !n.is_empty()) {
+        info.name = name.to_string();
+    }
+    info.context_window = model["context_length"]
+        .as_u64()
+        .or_else(|| model["max_context_length"].as_u64());
+    info.modality = match model["architecture"]["modality"].as_str() {
+        Some(modality) => Some(modality.to_string()),
+        None => model["capabilities"]["vision"].as_bool().map(|vision| {
+            if vision { "text+image->text" } else { "text->text" }.to_string()
+        }),
+    };
+
+    // OpenRouter quotes USD per token as strings
+    let per_token = |field: &str| model["pricing"][field].as_str()?.parse::<f64>().ok();
+    if let (Some(prompt), Some(completion)) = (per_token("prompt"), per_token("completion")) {
+        info.pricing = Some(ModelPrice {
+            prompt: prompt * 1_000_000.0,
+            completion: completion * 1_000_000.0,
+        });
+    }
+
+    Some(info)
+}
@@ -0,0 +1,46 @@
+// Provider-neutral generation settings; each provider maps them to its own
+// parameter names and drops the ones it has no equivalent for.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GenerationOptions {
+    /// System prompt, sent ahead of any `system` messages in the
+    /// conversation.
+    #[serde(default)]
+    pub system: Option<String>,
+    #[serde(default)]
+    pub temperature: Option<f32>,
+    #[serde(default)]
+    pub top_p: Option<f32>,
+    /// Upper bound on generated tokens. Anthropic requires one, so it falls
+    /// back to a default there.
+    #[serde(default)]
+    pub max_tokens: Option<u32>,
+    /// Sequences that end generation when produced.
+    #[serde(default)]
+    pub stop: Vec<String>,
+    /// Sampling seed for reproducible output; ignored by Anthropic, which
+    /// has no equivalent.
+    #[serde(default)]
+    pub seed: Option<u64>,
+    /// Constrain the reply to a JSON object. Anthropic has no such mode and
+    /// gets an instruction in the system prompt instead.
+    #[serde(default)]
+    pub json_mode: bool,
+}
+
+impl GenerationOptions {
+    /// The stop sequences, or `None` when there are none.
+    pub(super) fn stop(&self) -> Option<&[String]> {
+        (!self.stop.is_empty()).then_some(self.stop.as_slice())
+    }
+}
+
+/// Sets `payload[key]` only when the option is present, leaving the
+/// provider's own default in place otherwise.
+pub(super) fn set_opt<T: Serialize>(payload: &mut serde_json::Value, key: &str, value: Option<T>) {
+    if let Some(value) = value {
+        payload[key] = serde_json::json!(value);
+    }
+}
@@ -0,0 +1,151 @@
+// Retry policy for transient provider failures (429, 5xx, dropped connections).
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use super::{ContextTrim, ProviderError};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetryPolicy {
+    /// Total attempts including the first one; 1 disables retries.
+    pub max_attempts: u32,
+    pub base_delay_ms: u64,
+    pub max_delay_ms: u64,
+    /// Randomize each backoff between half and the full delay.
+    pub jitter: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay_ms: 500,
+            max_delay_ms: 30_000,
+            jitter: true,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// How long to wait before the next attempt, or `None` to give up.
+    /// `attempt` is the 1-based number of the attempt that just failed.
+    pub fn delay_after(&self, attempt: u32, error: &ProviderError) -> Option<Duration> {
+        if attempt >= self.max_attempts {
+            return None;
+        }
+
+        let delay_ms = match error {
+            // The provider told us when to come back; waiting less is pointless
+            // and a wait beyond our ceiling is better reported to the user.
+            ProviderError::RateLimited {
+                retry_after: Some(secs),
+                ..
+            } => {
+                let ms = secs.saturating_mul(1000);
+                if ms > self.max_delay_ms {
+                    return None;
+                }
+                return Some(Duration::from_millis(ms));
+            }
+            ProviderError::RateLimited { .. }
+            | ProviderError::Server { .. }
+            | ProviderError::Network { .. } => self.backoff_ms(attempt),
+            _ => return None,
+        };
+
+        Some(Duration::from_millis(delay_ms))
+    }
+
+    fn backoff_ms(&self, attempt: u32) -> u64 {
+        let exp = self
+            .base_delay_ms
+            .saturating_mul(1u64 << attempt.saturating_sub(1).min(20))
+            .min(self.max_delay_ms);
+
+        if self.jitter && exp > 1 {
+            rand::thread_rng().gen_range(exp / 2..=exp)
+        } else {
+            exp
+        }
+    }
+}
+
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// Seconds to wait according to a `Retry-After` header: either a number of
+/// seconds or an HTTP date such as `Wed, 21 Oct 2015 07:28:00 GMT`, which
+/// counts from `now`. A date in the past means right away. The obsolete
+/// RFC 850 and asctime date forms are not recognized.
+pub(super) fn parse_retry_after(value: &str, now: SystemTime) -> Option<u64> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(secs);
+    }
+
+    let at = http_date(value)?;
+    let now = now.duration_since(UNIX_EPOCH).ok()?.as_secs();
+    Some(at.saturating_sub(now))
+}
+
+// Seconds since the Unix epoch of an IMF-fixdate (RFC 9110, section 5.6.7)
+fn http_date(value: &str) -> Option<u64> {
+    let (_weekday, rest) = value.split_once(", ")?;
+    let [day, month, year, time, "GMT"] = rest.split(' ').collect::<Vec<_>>()[..] else {
+        return None;
+    };
+    let day: u64 = day.parse().ok()?;
+    let month = MONTHS.iter().position(|m| *m == month)? as u64 + 1;
+    let year: u64 = year.parse().ok()?;
+
+    let mut clock = time.split(':').map(|part| part.parse::<u64>().ok());
+    let (hour, minute, second) = (clock.next()??, clock.next()??, clock.next()??);
+    if clock.next().is_some() || !(1..=31).contains(&day) || year < 1970 || hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+
+    Some(days_since_epoch(year, month, day) * 86_400 + hour * 3_600 + minute * 60 + second)
+}
+
+// Civil date to days since 1970-01-01, counting years from March so the
+// leap day comes last
+fn days_since_epoch(year: u64, month: u64, day: u64) -> u64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year / 400;
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RetryRecord {
+    pub attempt: u32,
+    pub error: ProviderError,
+    pub delay_ms: u64,
+}
+
+/// Bookkeeping returned alongside a reply.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ResponseMeta {
+    pub attempts: u32,
+    pub retries: Vec<RetryRecord>,
+    /// Served from the response cache without contacting the provider.
+    pub cached: bool,
+    /// Set when older messages were dropped or summarized to fit the
+    /// model's context window.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context: Option<ContextTrim>,
+}
+
+impl ResponseMeta {
+    pub fn cached() -> Self {
+        Self {
+            cached: true,
+            ..Self::default()
+        }
+    }
+}
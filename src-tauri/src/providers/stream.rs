@@ -0,0 +1,136 @@
+// Incremental decoding of streamed chat responses (SSE and NDJSON).
+
+use serde::{Deserialize, Serialize};
+
+use super::ResponseMeta;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamFormat {
+    /// `text/event-stream`: events are `data:` lines separated by a blank line.
+    Sse,
+    /// Newline-delimited JSON: every non-empty line is one event.
+    Ndjson,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Usage {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+}
+
+impl Usage {
+    /// Providers report usage piecewise across events, so only non-zero
+    /// counters overwrite what has been seen so far.
+    pub fn merge(&mut self, other: &Usage) {
+        if other.prompt_tokens > 0 {
+            self.prompt_tokens = other.prompt_tokens;
+        }
+        if other.completion_tokens > 0 {
+            self.completion_tokens = other.completion_tokens;
+        }
+    }
+}
+
+/// What a provider extracted from a single stream event.
+#[derive(Debug, Default)]
+pub struct StreamChunk {
+    pub delta: Option<String>,
+    pub finish_reason: Option<String>,
+    pub usage: Option<Usage>,
+    pub done: bool,
+}
+
+/// Final state of a streamed reply, sent to the frontend once the stream ends.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct StreamSummary {
+    pub content: String,
+    pub finish_reason: Option<String>,
+    pub usage: Option<Usage>,
+    /// Estimated USD cost, filled in by the usage ledger.
+    pub cost: Option<f64>,
+    pub meta: ResponseMeta,
+}
+
+impl StreamSummary {
+    pub fn apply(&mut self, chunk: &StreamChunk) {
+        if let Some(delta) = &chunk.delta {
+            self.content.push_str(delta);
+        }
+        if chunk.finish_reason.is_some() {
+            self.finish_reason = chunk.finish_reason.clone();
+        }
+        if let Some(usage) = &chunk.usage {
+            self.usage.get_or_insert_with(Usage::default).merge(usage);
+        }
+    }
+}
+
+/// Buffers raw bytes and yields complete event payloads.
+pub struct EventDecoder {
+    format: StreamFormat,
+    buffer: Vec<u8>,
+    data_lines: Vec<String>,
+}
+
+impl EventDecoder {
+    pub fn new(format: StreamFormat) -> Self {
+        Self {
+            format,
+            buffer: Vec::new(),
+            data_lines: Vec::new(),
+        }
+    }
+
+    /// Feeds a network chunk and returns every payload it completed.
+    pub fn push(&mut self, bytes: &[u8]) -> Vec<String> {
+        self.buffer.extend_from_slice(bytes);
+
+        let mut events = Vec::new();
+        while let Some(pos) = self.buffer.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=pos).collect();
+            let line = String::from_utf8_lossy(&line);
+            if let Some(event) = self.decode_line(line.trim_end_matches(['\r', '\n'])) {
+                events.push(event);
+            }
+        }
+        events
+    }
+
+    /// Flushes whatever is left once the connection closes.
+    pub fn finish(&mut self) -> Vec<String> {
+        let rest = std::mem::take(&mut self.buffer);
+        let rest = String::from_utf8_lossy(&rest);
+
+        let mut events = Vec::new();
+        if let Some(event) = self.decode_line(rest.trim_end_matches(['\r', '\n'])) {
+            events.push(event);
+        }
+        if let Some(event) = self.decode_line("") {
+            events.push(event);
+        }
+        events
+    }
+
+    fn decode_line(&mut self, line: &str) -> Option<String> {
+        match self.format {
+            StreamFormat::Ndjson => {
+                let line = line.trim();
+                (!line.is_empty()).then(|| line.to_string())
+            }
+            StreamFormat::Sse => {
+                if line.is_empty() {
+                    if self.data_lines.is_empty() {
+                        return None;
+                    }
+                    return Some(std::mem::take(&mut self.data_lines).join("\n"));
+                }
+                // `event:`, `id:` and `:` comment lines carry nothing we need;
+                // every provider repeats the event type inside the JSON body.
+                if let Some(data) = line.strip_prefix("data:") {
+                    self.data_lines.push(data.trim_start().to_string());
+                }
+                None
+            }
+        }
+    }
+}
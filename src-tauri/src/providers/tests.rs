@@ -0,0 +1,997 @@
+// Provider round trips against the local mock server (see `mock.rs`).
+
+use std::time::Duration;
+
+use super::mock::{MockServer, BAD_KEY, COMPLETION_TOKENS, MODEL, PROMPT_TOKENS};
+use super::*;
+
+const ALL_PROVIDERS: [&str; 7] = [
+    "openai", "claude", "openrouter", "gemini", "mistral", "cohere", "ollama",
+];
+const TOOL_PROVIDERS: [&str; 6] = ["openai", "claude", "openrouter", "gemini", "mistral", "ollama"];
+const EMBEDDING_PROVIDERS: [&str; 5] = ["openai", "gemini", "mistral", "cohere", "ollama"];
+const STREAM_ERROR_PROVIDERS: [&str; 6] = ["openai", "claude", "openrouter", "gemini", "mistral", "ollama"];
+
+fn transport(max_attempts: u32) -> Transport {
+    Transport {
+        client: reqwest::Client::builder().no_proxy().build().unwrap(),
+        read_timeout: Duration::from_secs(5),
+        retry: RetryPolicy {
+            max_attempts,
+            base_delay_ms: 1,
+            max_delay_ms: 10,
+            jitter: false,
+        },
+        limiter: RateLimiter::default(),
+    }
+}
+
+fn user(text: &str) -> Message {
+    Message {
+        role: "user".to_string(),
+        content: text.to_string().into(),
+        tool_calls: Vec::new(),
+        tool_call_id: None,
+        name: None,
+    }
+}
+
+fn request(server: &MockServer, provider: &str, model: &str) -> ChatRequest {
+    ChatRequest {
+        provider: provider.to_string(),
+        model: model.to_string(),
+        messages: vec![user("Hi")],
+        tools: Vec::new(),
+        options: GenerationOptions::default(),
+        api_key: Some("test-key".to_string()),
+        base_url: Some(server.base_url(provider)),
+        headers: BTreeMap::new(),
+    }
+}
+
+fn credentials(server: &MockServer, provider: &str, api_key: &str) -> Credentials {
+    Credentials {
+        provider: provider.to_string(),
+        api_key: Some(api_key.to_string()),
+        base_url: Some(server.base_url(provider)),
+        headers: BTreeMap::new(),
+    }
+}
+
+fn expected_reply(server: &MockServer, provider: &str) -> String {
+    let flavor = server.base_url(provider).rsplit('/').next().unwrap().to_string();
+    format!("Hello from {}", flavor)
+}
+
+fn weather_tool() -> ToolDefinition {
+    ToolDefinition {
+        name: "get_weather".to_string(),
+        description: "Current weather for a city".to_string(),
+        parameters: serde_json::json!({
+            "type": "object",
+            "properties": { "city": { "type": "string" } },
+            "required": ["city"],
+        }),
+    }
+}
+
+#[tokio::test]
+async fn chat_returns_reply_and_usage_for_every_provider() {
+    let server = MockServer::start().await;
+
+    for provider in ALL_PROVIDERS {
+        let response = chat(&transport(1), &request(&server, provider, MODEL))
+            .await
+            .unwrap_or_else(|e| panic!("{}: {:?}", provider, e));
+
+        assert_eq!(response.content, expected_reply(&server, provider), "{}", provider);
+        let usage = response.usage.unwrap_or_else(|| panic!("{}: no usage", provider));
+        assert_eq!(usage.prompt_tokens, PROMPT_TOKENS, "{}", provider);
+        assert_eq!(usage.completion_tokens, COMPLETION_TOKENS, "{}", provider);
+        assert_eq!(response.meta.attempts, 1, "{}", provider);
+    }
+}
+
+#[tokio::test]
+async fn chat_stream_assembles_deltas_for_every_provider() {
+    let server = MockServer::start().await;
+
+    for provider in ALL_PROVIDERS {
+        let mut deltas = Vec::new();
+        let summary = chat_stream(&transport(1), &request(&server, provider, MODEL), |delta| {
+            deltas.push(delta.to_string())
+        })
+        .await
+        .unwrap_or_else(|e| panic!("{}: {:?}", provider, e));
+
+        let expected = expected_reply(&server, provider);
+        assert_eq!(summary.content, expected, "{}", provider);
+        assert_eq!(deltas.concat(), expected, "{}", provider);
+        assert!(deltas.len() > 1, "{}: reply was not streamed", provider);
+        assert!(summary.finish_reason.is_some(), "{}: no finish reason", provider);
+        let usage = summary.usage.unwrap_or_else(|| panic!("{}: no usage", provider));
+        assert_eq!(usage.prompt_tokens, PROMPT_TOKENS, "{}", provider);
+        assert_eq!(usage.completion_tokens, COMPLETION_TOKENS, "{}", provider);
+    }
+}
+
+#[tokio::test]
+async fn stream_error_events_become_errors() {
+    let server = MockServer::start().await;
+
+    for provider in STREAM_ERROR_PROVIDERS {
+        let mut deltas = Vec::new();
+        let result = chat_stream(&transport(1), &request(&server, provider, "stream-error"), |delta| {
+            deltas.push(delta.to_string())
+        })
+        .await;
+
+        assert!(result.is_err(), "{}: stream error was swallowed", provider);
+        assert_eq!(deltas, vec!["Partial".to_string()], "{}", provider);
+    }
+}
+
+#[tokio::test]
+async fn list_models_for_every_provider() {
+    let server = MockServer::start().await;
+
+    for provider in ALL_PROVIDERS {
+        let models = list_models(&transport(1), &credentials(&server, provider, "test-key"))
+            .await
+            .unwrap_or_else(|e| panic!("{}: {:?}", provider, e));
+
+        let ids: Vec<_> = models.iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(ids, vec![MODEL], "{}", provider);
+    }
+}
+
+#[tokio::test]
+async fn validate_key_rejects_bad_keys() {
+    let server = MockServer::start().await;
+
+    for provider in ALL_PROVIDERS.iter().filter(|p| **p != "ollama") {
+        let good = validate_key(&transport(1), &credentials(&server, provider, "test-key")).await;
+        assert!(good.valid, "{}: {:?}", provider, good.error);
+        assert_eq!(good.models, vec![MODEL.to_string()], "{}", provider);
+
+        let bad = validate_key(&transport(1), &credentials(&server, provider, BAD_KEY)).await;
+        assert!(!bad.valid, "{}", provider);
+        assert!(
+            matches!(bad.error, Some(ProviderError::Auth { .. })),
+            "{}: {:?}",
+            provider,
+            bad.error
+        );
+    }
+}
+
+#[tokio::test]
+async fn openrouter_checks_keys_against_an_authenticated_endpoint() {
+    let server = MockServer::start().await;
+
+    validate_key(&transport(1), &credentials(&server, "openrouter", "test-key")).await;
+
+    let paths: Vec<_> = server.requests().into_iter().map(|r| r.path).collect();
+    assert_eq!(paths, vec!["/openai/auth/key", "/openai/models"]);
+}
+
+#[tokio::test]
+async fn custom_endpoints_work_without_a_key_and_send_profile_headers() {
+    let server = MockServer::start().await;
+    let mut request = request(&server, "custom:lm-studio", MODEL);
+    request.api_key = None;
+    request.headers.insert("X-Team".to_string(), "devtools".to_string());
+
+    let response = chat(&transport(1), &request).await.unwrap();
+    assert_eq!(response.content, "Hello from openai");
+
+    let sent = server.last_request();
+    assert_eq!(sent.headers.get("x-team").map(String::as_str), Some("devtools"));
+    assert!(!sent.headers.contains_key("authorization"));
+
+    let mut credentials = credentials(&server, "custom:lm-studio", "");
+    credentials.api_key = None;
+    let models = list_models(&transport(1), &credentials).await.unwrap();
+    assert_eq!(models[0].id, MODEL);
+}
+
+#[tokio::test]
+async fn custom_endpoints_need_a_base_url() {
+    let server = MockServer::start().await;
+    let mut request = request(&server, "custom:missing", MODEL);
+    request.base_url = None;
+
+    let error = chat(&transport(1), &request).await.unwrap_err();
+
+    assert!(matches!(error, ProviderError::BadRequest { .. }), "{:?}", error);
+}
+
+#[tokio::test]
+async fn missing_key_fails_without_a_request() {
+    let server = MockServer::start().await;
+    let mut request = request(&server, "openai", MODEL);
+    request.api_key = None;
+
+    let error = chat(&transport(1), &request).await.unwrap_err();
+
+    assert!(matches!(error, ProviderError::Auth { .. }), "{:?}", error);
+    assert!(server.requests().is_empty());
+}
+
+#[tokio::test]
+async fn rate_limits_are_retried_then_reported() {
+    let server = MockServer::start().await;
+
+    for provider in ALL_PROVIDERS {
+        let before = server.requests().len();
+        let error = chat(&transport(3), &request(&server, provider, "rate-limited"))
+            .await
+            .unwrap_err();
+
+        assert!(matches!(error, ProviderError::RateLimited { .. }), "{}: {:?}", provider, error);
+        assert_eq!(server.requests().len() - before, 3, "{}", provider);
+    }
+}
+
+#[tokio::test]
+async fn server_errors_are_retried() {
+    for provider in ALL_PROVIDERS {
+        // `flaky` only fails the first request the server sees
+        let server = MockServer::start().await;
+
+        let response = chat(&transport(2), &request(&server, provider, "flaky"))
+            .await
+            .unwrap_or_else(|e| panic!("{}: {:?}", provider, e));
+
+        assert_eq!(response.meta.attempts, 2, "{}", provider);
+        assert!(
+            matches!(response.meta.retries[0].error, ProviderError::Server { .. }),
+            "{}: {:?}",
+            provider,
+            response.meta.retries[0].error
+        );
+    }
+}
+
+#[tokio::test]
+async fn context_length_errors_are_classified() {
+    let server = MockServer::start().await;
+
+    for provider in ALL_PROVIDERS {
+        let error = chat(&transport(3), &request(&server, provider, "too-long"))
+            .await
+            .unwrap_err();
+
+        assert!(matches!(error, ProviderError::ContextTooLong { .. }), "{}: {:?}", provider, error);
+    }
+}
+
+#[tokio::test]
+async fn tool_calls_are_parsed_for_every_tool_provider() {
+    let server = MockServer::start().await;
+
+    for provider in TOOL_PROVIDERS {
+        let mut request = request(&server, provider, "tool");
+        request.tools = vec![weather_tool()];
+
+        let response = chat(&transport(1), &request)
+            .await
+            .unwrap_or_else(|e| panic!("{}: {:?}", provider, e));
+
+        assert_eq!(response.tool_calls.len(), 1, "{}", provider);
+        let call = &response.tool_calls[0];
+        assert_eq!(call.name, "get_weather", "{}", provider);
+        assert_eq!(call.arguments, serde_json::json!({ "city": "Paris" }), "{}", provider);
+        assert!(!call.id.is_empty(), "{}", provider);
+    }
+}
+
+#[tokio::test]
+async fn cohere_rejects_tools_before_sending() {
+    let server = MockServer::start().await;
+    let mut request = request(&server, "cohere", "tool");
+    request.tools = vec![weather_tool()];
+
+    let error = chat(&transport(1), &request).await.unwrap_err();
+
+    assert!(matches!(error, ProviderError::BadRequest { .. }), "{:?}", error);
+    assert!(server.requests().is_empty());
+}
+
+#[tokio::test]
+async fn system_prompt_and_options_use_each_provider_field() {
+    let server = MockServer::start().await;
+    let options = GenerationOptions {
+        system: Some("Be brief.".to_string()),
+        temperature: Some(0.2),
+        top_p: Some(0.9),
+        max_tokens: Some(64),
+        stop: vec!["END".to_string()],
+        seed: Some(7),
+        json_mode: true,
+    };
+
+    let body_for = |provider: &'static str| {
+        let mut request = request(&server, provider, MODEL);
+        request.options = options.clone();
+        let transport = transport(1);
+        async move {
+            chat(&transport, &request).await.unwrap();
+        }
+    };
+
+    body_for("openai").await;
+    let body = server.last_request().body;
+    assert_eq!(body["messages"][0], serde_json::json!({ "role": "system", "content": "Be brief." }));
+    assert_eq!(body["max_tokens"], 64);
+    assert_eq!(body["stop"], serde_json::json!(["END"]));
+    assert_eq!(body["seed"], 7);
+    assert_eq!(body["response_format"]["type"], "json_object");
+
+    body_for("mistral").await;
+    assert_eq!(server.last_request().body["random_seed"], 7);
+
+    body_for("claude").await;
+    let body = server.last_request().body;
+    assert!(body["system"].as_str().unwrap().starts_with("Be brief."));
+    assert_eq!(body["max_tokens"], 64);
+    assert_eq!(body["stop_sequences"], serde_json::json!(["END"]));
+    assert_eq!(body["messages"].as_array().unwrap().len(), 1);
+
+    body_for("gemini").await;
+    let body = server.last_request().body;
+    assert_eq!(body["systemInstruction"]["parts"][0]["text"], "Be brief.");
+    assert_eq!(body["generationConfig"]["maxOutputTokens"], 64);
+    assert_eq!(body["generationConfig"]["responseMimeType"], "application/json");
+
+    body_for("cohere").await;
+    let body = server.last_request().body;
+    assert_eq!(body["preamble"], "Be brief.");
+    assert_eq!(body["p"], serde_json::json!(0.9f32));
+
+    body_for("ollama").await;
+    let body = server.last_request().body;
+    assert_eq!(body["messages"][0]["role"], "system");
+    assert_eq!(body["options"]["num_predict"], 64);
+    assert_eq!(body["format"], "json");
+}
+
+#[tokio::test]
+async fn images_use_each_provider_encoding() {
+    let server = MockServer::start().await;
+    // 1x1 PNG header is enough for type sniffing
+    let png = "iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNkYPhfDwAChwGA60e6kgAAAABJRU5ErkJggg==";
+
+    let send = |provider: &'static str| {
+        let mut request = request(&server, provider, MODEL);
+        request.messages = vec![Message {
+            content: serde_json::from_value(serde_json::json!([
+                { "type": "text", "text": "What is this?" },
+                { "type": "image", "data": png },
+            ]))
+            .unwrap(),
+            ..user("")
+        }];
+        let transport = transport(1);
+        async move {
+            inline_images(&mut request).unwrap();
+            chat(&transport, &request).await
+        }
+    };
+
+    send("openai").await.unwrap();
+    let content = &server.last_request().body["messages"][0]["content"];
+    assert_eq!(content[1]["image_url"]["url"], format!("data:image/png;base64,{}", png));
+
+    send("claude").await.unwrap();
+    let content = &server.last_request().body["messages"][0]["content"];
+    assert_eq!(content[0]["source"]["media_type"], "image/png");
+    assert_eq!(content[0]["source"]["data"], png);
+
+    send("gemini").await.unwrap();
+    let parts = &server.last_request().body["contents"][0]["parts"];
+    assert_eq!(parts[1]["inline_data"]["mime_type"], "image/png");
+
+    send("ollama").await.unwrap();
+    let message = &server.last_request().body["messages"][0];
+    assert_eq!(message["images"], serde_json::json!([png]));
+    assert_eq!(message["content"], "What is this?");
+
+    let error = send("cohere").await.unwrap_err();
+    assert!(matches!(error, ProviderError::BadRequest { .. }), "{:?}", error);
+}
+
+#[tokio::test]
+async fn requests_hit_the_overridden_base_url() {
+    let server = MockServer::start().await;
+
+    for provider in ALL_PROVIDERS {
+        chat(&transport(1), &request(&server, provider, MODEL)).await.unwrap();
+        let prefix = server.base_url(provider);
+        let prefix = prefix.rsplit('/').next().unwrap();
+        assert!(
+            server.last_request().path.starts_with(&format!("/{}/", prefix)),
+            "{}",
+            provider
+        );
+    }
+}
+
+// `turns` user/assistant exchanges of roughly 100 tokens per message
+fn long_conversation(turns: usize) -> Vec<Message> {
+    (0..turns)
+        .flat_map(|i| {
+            [
+                user(&format!("Question {} {}", i, "x".repeat(400))),
+                Message::assistant(format!("Answer {} {}", i, "y".repeat(400)), Vec::new()),
+            ]
+        })
+        .collect()
+}
+
+fn context_policy(strategy: ContextStrategy) -> ContextPolicy {
+    ContextPolicy {
+        strategy,
+        keep_last: 4,
+        reserve_tokens: 100,
+        ..ContextPolicy::default()
+    }
+}
+
+#[tokio::test]
+async fn oversized_conversations_drop_the_oldest_turns() {
+    let server = MockServer::start().await;
+    let mut request = request(&server, "openai", MODEL);
+    request.messages = vec![Message::system("Be brief.".to_string())];
+    request.messages.extend(long_conversation(10));
+
+    let trim = fit_context(&transport(1), &mut request, &context_policy(ContextStrategy::DropOldest), 1_000, None)
+        .await
+        .unwrap()
+        .expect("request should have been trimmed");
+
+    assert!(trim.tokens_before > 1_000);
+    assert!(trim.tokens_after <= 900);
+    assert_eq!(trim.dropped_messages % 2, 0, "whole turns are dropped");
+    assert_eq!(request.messages[0].role, "system");
+    assert_eq!(request.messages[1].role, "user");
+    assert!(request.messages.last().unwrap().content.text().starts_with("Answer 9"));
+    assert!(server.requests().is_empty());
+}
+
+#[tokio::test]
+async fn requests_within_the_window_are_left_alone() {
+    let server = MockServer::start().await;
+    let mut request = request(&server, "openai", MODEL);
+    request.messages = long_conversation(2);
+
+    let policy = context_policy(ContextStrategy::DropOldest);
+    let trim = fit_context(&transport(1), &mut request, &policy, 8_192, None).await.unwrap();
+
+    assert!(trim.is_none());
+    assert_eq!(request.messages.len(), 4);
+}
+
+#[tokio::test]
+async fn keep_last_keeps_system_and_recent_messages() {
+    let server = MockServer::start().await;
+    let mut request = request(&server, "openai", MODEL);
+    request.messages = vec![Message::system("Be brief.".to_string())];
+    request.messages.extend(long_conversation(10));
+
+    let policy = context_policy(ContextStrategy::KeepLast);
+    let trim = fit_context(&transport(1), &mut request, &policy, 2_000, None).await.unwrap().unwrap();
+
+    assert_eq!(trim.dropped_messages, 16);
+    assert_eq!(request.messages.len(), 5);
+    assert!(request.messages[1].content.text().starts_with("Question 8"));
+}
+
+#[tokio::test]
+async fn summarize_replaces_earlier_turns_with_a_summary() {
+    let server = MockServer::start().await;
+    let mut request = request(&server, "openai", MODEL);
+    request.messages = long_conversation(10);
+    let summarizer = ChatRequest {
+        messages: Vec::new(),
+        ..request.clone()
+    };
+
+    let policy = context_policy(ContextStrategy::Summarize);
+    let trim = fit_context(&transport(1), &mut request, &policy, 2_000, Some(summarizer))
+        .await
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(trim.summarized_messages, 16);
+    assert_eq!(trim.dropped_messages, 0);
+    assert_eq!(trim.summary_usage.as_ref().map(|u| u.completion_tokens), Some(COMPLETION_TOKENS));
+    assert!(trim.summary_error.is_none());
+    assert_eq!(request.messages.len(), 5);
+    assert_eq!(request.messages[0].role, "system");
+    assert_eq!(
+        request.messages[0].content.text(),
+        format!("Summary of the earlier conversation:\n{}", expected_reply(&server, "openai"))
+    );
+
+    let sent = server.last_request().body.to_string();
+    assert!(sent.contains("Question 0") && !sent.contains("Question 9"));
+}
+
+#[tokio::test]
+async fn a_failed_summary_is_reported_and_turns_are_dropped() {
+    let server = MockServer::start().await;
+    let mut request = request(&server, "openai", MODEL);
+    request.messages = long_conversation(10);
+    let summarizer = ChatRequest {
+        messages: Vec::new(),
+        api_key: Some(BAD_KEY.to_string()),
+        ..request.clone()
+    };
+
+    let policy = context_policy(ContextStrategy::Summarize);
+    let trim = fit_context(&transport(1), &mut request, &policy, 2_000, Some(summarizer))
+        .await
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(trim.summarized_messages, 0);
+    assert!(trim.dropped_messages > 0);
+    assert!(trim.summary_usage.is_none());
+    assert!(matches!(trim.summary_error, Some(ProviderError::Auth { .. })), "{:?}", trim.summary_error);
+}
+
+#[tokio::test]
+async fn a_last_message_over_the_window_is_a_context_error() {
+    let server = MockServer::start().await;
+    let mut request = request(&server, "openai", MODEL);
+    request.messages = vec![user(&"z".repeat(40_000))];
+
+    let policy = context_policy(ContextStrategy::DropOldest);
+    let error = fit_context(&transport(1), &mut request, &policy, 4_096, None).await.unwrap_err();
+
+    assert!(matches!(error, ProviderError::ContextTooLong { .. }), "{:?}", error);
+}
+
+#[test]
+fn context_windows_match_the_longest_model_prefix() {
+    assert_eq!(context_window("gpt-4"), Some(8_192));
+    assert_eq!(context_window("gpt-4-turbo-preview"), Some(128_000));
+    assert_eq!(context_window("gpt-4.1-mini"), Some(1_047_576));
+    assert_eq!(context_window("gpt-4o-mini"), Some(128_000));
+    assert_eq!(context_window("gpt-5"), Some(400_000));
+    assert_eq!(context_window("o1-mini"), Some(128_000));
+    assert_eq!(context_window("anthropic/claude-3-haiku"), Some(200_000));
+    assert_eq!(context_window("claude-sonnet-4-20250514"), Some(200_000));
+    assert_eq!(context_window("llama3.1:8b"), Some(131_072));
+    assert_eq!(context_window("llama3:8b"), Some(8_192));
+}
+
+#[test]
+fn unknown_models_have_no_context_window() {
+    assert_eq!(context_window("some-unknown-model"), None);
+    assert_eq!(context_window("my-finetune/qwen-local"), None);
+}
+
+#[tokio::test]
+async fn embeddings_come_back_in_input_order_for_every_embedding_provider() {
+    let server = MockServer::start().await;
+    let texts = vec!["a".to_string(), "two words".to_string(), "three more words".to_string()];
+
+    for provider in EMBEDDING_PROVIDERS {
+        let vectors = embed(
+            &transport(1),
+            &credentials(&server, provider, "test-key"),
+            MODEL,
+            &texts,
+            EmbedPurpose::Document,
+        )
+        .await
+        .unwrap_or_else(|e| panic!("{}: {:?}", provider, e));
+
+        let lengths: Vec<f32> = vectors.iter().map(|vector| vector[0]).collect();
+        assert_eq!(lengths, vec![1.0, 9.0, 16.0], "{}", provider);
+        assert!(vectors.iter().all(|vector| vector.len() == 3), "{}", provider);
+    }
+}
+
+#[tokio::test]
+async fn embeddings_are_sent_in_batches() {
+    let server = MockServer::start().await;
+    let texts: Vec<String> = (0..200).map(|i| format!("chunk {}", i)).collect();
+
+    let vectors = embed(
+        &transport(1),
+        &credentials(&server, "openai", "test-key"),
+        MODEL,
+        &texts,
+        EmbedPurpose::Document,
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(vectors.len(), 200);
+    assert_eq!(vectors[199][0], "chunk 199".len() as f32);
+    assert_eq!(server.requests().len(), 3);
+}
+
+#[tokio::test]
+async fn embedding_purpose_uses_each_provider_field() {
+    let server = MockServer::start().await;
+    let texts = vec!["find the parser".to_string()];
+
+    embed(&transport(1), &credentials(&server, "cohere", "test-key"), MODEL, &texts, EmbedPurpose::Query)
+        .await
+        .unwrap();
+    assert_eq!(server.last_request().body["input_type"], "search_query");
+
+    embed(&transport(1), &credentials(&server, "gemini", "test-key"), MODEL, &texts, EmbedPurpose::Query)
+        .await
+        .unwrap();
+    let request = server.last_request();
+    assert!(request.path.ends_with(&format!("/models/{}:batchEmbedContents", MODEL)));
+    assert_eq!(request.headers.get("x-goog-api-key").map(String::as_str), Some("test-key"));
+    assert_eq!(request.body["requests"][0]["taskType"], "RETRIEVAL_QUERY");
+    assert_eq!(request.body["requests"][0]["model"], format!("models/{}", MODEL));
+}
+
+#[tokio::test]
+async fn providers_without_embeddings_fail_without_a_request() {
+    let server = MockServer::start().await;
+
+    for provider in ["claude", "openrouter"] {
+        let error = embed(
+            &transport(1),
+            &credentials(&server, provider, "test-key"),
+            MODEL,
+            &["text".to_string()],
+            EmbedPurpose::Document,
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(error, ProviderError::BadRequest { .. }), "{}: {:?}", provider, error);
+    }
+    assert!(server.requests().is_empty());
+}
+
+#[tokio::test]
+async fn ollama_pull_reports_progress_until_success() {
+    let server = MockServer::start().await;
+    let credentials = credentials(&server, "ollama", "");
+    let mut progress = Vec::new();
+
+    pull_ollama_model(&transport(1), &credentials, MODEL, |p| progress.push(p))
+        .await
+        .unwrap();
+
+    let statuses: Vec<&str> = progress.iter().map(|p| p.status.as_str()).collect();
+    assert_eq!(statuses, ["pulling manifest", "pulling abc123", "pulling abc123", "success"]);
+    assert_eq!(progress[1].completed, Some(40));
+    assert_eq!(progress[1].total, Some(100));
+    assert_eq!(server.last_request().body["model"], MODEL);
+}
+
+#[tokio::test]
+async fn ollama_pull_errors_mid_stream_are_reported() {
+    let server = MockServer::start().await;
+    let credentials = credentials(&server, "ollama", "");
+
+    let error = pull_ollama_model(&transport(1), &credentials, "no-such-model", |_| {})
+        .await
+        .unwrap_err();
+    assert!(error.message().contains("file does not exist"), "{:?}", error);
+}
+
+#[tokio::test]
+async fn ollama_show_parses_parameters_and_context_length() {
+    let server = MockServer::start().await;
+    let credentials = credentials(&server, "ollama", "");
+
+    let details = show_ollama_model(&transport(1), &credentials, MODEL).await.unwrap();
+    assert_eq!(details.context_length, Some(8192));
+    assert_eq!(details.family.as_deref(), Some("llama"));
+    assert_eq!(details.parameters["num_ctx"], ["4096"]);
+    assert_eq!(details.parameters["stop"], ["<|user|>", "<|end|>"]);
+    assert_eq!(details.template.as_deref(), Some("{{ .Prompt }}"));
+
+    let error = show_ollama_model(&transport(1), &credentials, "no-such-model").await.unwrap_err();
+    assert!(error.message().contains("not found"), "{:?}", error);
+}
+
+#[tokio::test]
+async fn ollama_delete_and_running_models() {
+    let server = MockServer::start().await;
+    let credentials = credentials(&server, "ollama", "");
+
+    delete_ollama_model(&transport(1), &credentials, MODEL).await.unwrap();
+    let request = server.last_request();
+    assert_eq!(request.method, "DELETE");
+    assert!(request.path.ends_with("/api/delete"));
+    assert!(delete_ollama_model(&transport(1), &credentials, "no-such-model").await.is_err());
+
+    let running = running_ollama_models(&transport(1), &credentials).await.unwrap();
+    assert_eq!(running.len(), 1);
+    assert_eq!(running[0].name, MODEL);
+    assert_eq!(running[0].size_vram, 4_000_000_000);
+    assert_eq!(running[0].parameter_size.as_deref(), Some("7B"));
+}
+
+#[test]
+fn ollama_endpoints_are_normalized() {
+    let parse = |input: &str| ollama::OllamaEndpoint::parse(input).map(|endpoint| endpoint.as_str().to_string());
+
+    assert_eq!(parse("").unwrap(), "http://localhost:11434");
+    assert_eq!(parse("localhost").unwrap(), "http://localhost:11434");
+    assert_eq!(parse(" 192.168.1.20:11434/ ").unwrap(), "http://192.168.1.20:11434");
+    assert_eq!(parse("http://gpu-box:8080/api/").unwrap(), "http://gpu-box:8080");
+    assert_eq!(parse("https://example.com/ollama/").unwrap(), "https://example.com/ollama");
+    assert_eq!(parse("http://localhost:11434?x=1").unwrap(), "http://localhost:11434");
+    assert!(matches!(parse("ftp://localhost"), Err(ProviderError::BadRequest { .. })));
+    assert!(matches!(parse("http://"), Err(ProviderError::BadRequest { .. })));
+}
+
+#[tokio::test]
+async fn ollama_status_reports_version_and_model_presence() {
+    let server = MockServer::start().await;
+    let credentials = credentials(&server, "ollama", "");
+
+    let status = ollama_status(&transport(1), &credentials, Some(MODEL)).await;
+    assert!(status.reachable, "{:?}", status.error);
+    assert_eq!(status.version.as_deref(), Some("0.3.0"));
+    assert_eq!(status.models, [MODEL]);
+    assert_eq!(status.model_available, Some(true));
+    assert!(status.error.is_none());
+
+    let status = ollama_status(&transport(1), &credentials, Some("llama3")).await;
+    assert_eq!(status.model_available, Some(false));
+
+    let status = ollama_status(&transport(1), &credentials, None).await;
+    assert_eq!(status.model_available, None);
+}
+
+#[tokio::test]
+async fn ollama_status_tells_a_stopped_server_apart() {
+    // Nothing listens on the port a dropped listener had
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    drop(listener);
+
+    let credentials = Credentials {
+        provider: "ollama".to_string(),
+        api_key: None,
+        base_url: Some(address.to_string()),
+        headers: BTreeMap::new(),
+    };
+    let status = ollama_status(&transport(1), &credentials, Some(MODEL)).await;
+    assert!(!status.reachable);
+    assert_eq!(status.base_url, format!("http://{}", address));
+    assert_eq!(status.model_available, None);
+    assert!(matches!(status.error, Some(ProviderError::Network { .. })), "{:?}", status.error);
+}
+
+fn limited(provider: &str, provider_limits: limits::ProviderLimits) -> RateLimiter {
+    RateLimiter::new(RateLimits::from([(provider.to_string(), provider_limits)]))
+}
+
+fn queue(limiter: &RateLimiter, provider: &str) -> QueueStatus {
+    limiter
+        .status()
+        .into_iter()
+        .find(|status| status.provider == provider)
+        .unwrap()
+}
+
+#[tokio::test]
+async fn calls_over_the_concurrency_cap_wait_their_turn() {
+    let limiter = limited(
+        "openai",
+        limits::ProviderLimits {
+            max_concurrent: Some(1),
+            ..limits::ProviderLimits::default()
+        },
+    );
+
+    let first = limiter.acquire("openai", 10).await;
+    let waiting = tokio::spawn({
+        let limiter = limiter.clone();
+        async move { limiter.acquire("openai", 10).await }
+    });
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert_eq!(queue(&limiter, "openai").queued, 1);
+    assert_eq!(queue(&limiter, "openai").in_flight, 1);
+    assert!(!waiting.is_finished());
+
+    drop(first);
+    let second = tokio::time::timeout(Duration::from_secs(1), waiting).await.unwrap().unwrap();
+    assert_eq!(queue(&limiter, "openai").queued, 0);
+    assert_eq!(queue(&limiter, "openai").in_flight, 1);
+    drop(second);
+    assert_eq!(queue(&limiter, "openai").in_flight, 0);
+}
+
+#[tokio::test]
+async fn calls_over_the_per_minute_limits_wait_for_new_limits() {
+    let limiter = limited(
+        "mistral",
+        limits::ProviderLimits {
+            requests_per_minute: Some(1),
+            ..limits::ProviderLimits::default()
+        },
+    );
+    limiter.acquire("mistral", 10).await;
+
+    let waiting = tokio::spawn({
+        let limiter = limiter.clone();
+        async move { limiter.acquire("mistral", 10).await }
+    });
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert_eq!(queue(&limiter, "mistral").queued, 1);
+
+    // Queued calls are checked against the new limits right away
+    limiter.set_limits(RateLimits::from([(
+        "mistral".to_string(),
+        limits::ProviderLimits {
+            requests_per_minute: Some(10),
+            tokens_per_minute: Some(20),
+            ..limits::ProviderLimits::default()
+        },
+    )]));
+    tokio::time::timeout(Duration::from_secs(1), waiting).await.unwrap().unwrap();
+
+    // The 20 token budget is spent, so even a small call waits
+    let waiting = tokio::spawn({
+        let limiter = limiter.clone();
+        async move { limiter.acquire("mistral", 1).await }
+    });
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert_eq!(queue(&limiter, "mistral").tokens_last_minute, 20);
+    assert!(!waiting.is_finished());
+    waiting.abort();
+}
+
+#[tokio::test]
+async fn abandoned_calls_leave_the_queue() {
+    let limiter = limited(
+        "gemini",
+        limits::ProviderLimits {
+            max_concurrent: Some(1),
+            ..limits::ProviderLimits::default()
+        },
+    );
+    let first = limiter.acquire("gemini", 10).await;
+
+    let abandoned = tokio::spawn({
+        let limiter = limiter.clone();
+        async move { limiter.acquire("gemini", 10).await }
+    });
+    let next = tokio::spawn({
+        let limiter = limiter.clone();
+        async move { limiter.acquire("gemini", 10).await }
+    });
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert_eq!(queue(&limiter, "gemini").queued, 2);
+
+    abandoned.abort();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert_eq!(queue(&limiter, "gemini").queued, 1);
+
+    drop(first);
+    tokio::time::timeout(Duration::from_secs(1), next).await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn chat_counts_reported_usage_against_the_limits() {
+    let server = MockServer::start().await;
+    let mut transport = transport(1);
+    transport.limiter = limited(
+        "openai",
+        limits::ProviderLimits {
+            tokens_per_minute: Some(100_000),
+            ..limits::ProviderLimits::default()
+        },
+    );
+
+    chat(&transport, &request(&server, "openai", MODEL)).await.unwrap();
+    chat_stream(&transport, &request(&server, "openai", MODEL), |_| {}).await.unwrap();
+
+    let status = queue(&transport.limiter, "openai");
+    assert_eq!(status.requests_last_minute, 2);
+    assert_eq!(status.tokens_last_minute, 2 * (PROMPT_TOKENS + COMPLETION_TOKENS));
+    assert_eq!(status.in_flight, 0);
+}
+
+#[test]
+fn stored_keys_never_follow_a_caller_base_url() {
+    let stored = |looked_up: &std::cell::Cell<bool>| {
+        looked_up.set(true);
+        Ok(Some("vault-key".to_string()))
+    };
+    let looked_up = std::cell::Cell::new(false);
+
+    let mut api_key = None;
+    let result = attach_stored_key("openai", &mut api_key, Some("https://elsewhere.example"), || stored(&looked_up));
+    assert!(matches!(result, Err(ProviderError::BadRequest { .. })), "{:?}", result);
+    assert_eq!(api_key, None);
+    assert!(!looked_up.get(), "the vault was read for a redirected call");
+
+    // An explicit key may go anywhere; the vault is not consulted
+    let mut api_key = Some("caller-key".to_string());
+    attach_stored_key("openai", &mut api_key, Some("https://elsewhere.example"), || stored(&looked_up)).unwrap();
+    assert_eq!(api_key.as_deref(), Some("caller-key"));
+    assert!(!looked_up.get());
+
+    for base_url in [None, Some(""), Some("  ")] {
+        let mut api_key = None;
+        attach_stored_key("openai", &mut api_key, base_url, || stored(&looked_up)).unwrap();
+        assert_eq!(api_key.as_deref(), Some("vault-key"), "{:?}", base_url);
+    }
+}
+
+#[tokio::test]
+async fn every_retry_counts_against_the_limits() {
+    let server = MockServer::start().await;
+    let mut transport = transport(3);
+    transport.limiter = limited(
+        "openai",
+        limits::ProviderLimits {
+            requests_per_minute: Some(100),
+            ..limits::ProviderLimits::default()
+        },
+    );
+
+    chat(&transport, &request(&server, "openai", "rate-limited")).await.unwrap_err();
+
+    let status = queue(&transport.limiter, "openai");
+    assert_eq!(status.requests_last_minute, 3);
+    assert_eq!(status.tokens_last_minute, 0);
+    assert_eq!(status.in_flight, 0);
+}
+
+#[test]
+fn retry_after_accepts_seconds_and_http_dates() {
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    // Wed, 21 Oct 2015 07:28:00 GMT
+    let now = UNIX_EPOCH + Duration::from_secs(1_445_412_480);
+    assert_eq!(retry::parse_retry_after(" 120 ", now), Some(120));
+    assert_eq!(retry::parse_retry_after("Wed, 21 Oct 2015 07:28:30 GMT", now), Some(30));
+    assert_eq!(retry::parse_retry_after("Thu, 22 Oct 2015 07:28:00 GMT", now), Some(86_400));
+    // Across a leap day
+    let leap = UNIX_EPOCH + Duration::from_secs(951_782_400); // Tue, 29 Feb 2000 00:00:00 GMT
+    assert_eq!(retry::parse_retry_after("Wed, 01 Mar 2000 00:00:00 GMT", leap), Some(86_400));
+    // Already past: go right away
+    assert_eq!(retry::parse_retry_after("Tue, 20 Oct 2015 07:28:00 GMT", now), Some(0));
+
+    for value in [
+        "",
+        "soon",
+        "-5",
+        "Wed, 21 Oct 2015 07:28:00 UTC",
+        "Wednesday, 21-Oct-15 07:28:00 GMT",
+        "Wed, 21 Foo 2015 07:28:00 GMT",
+        "Wed, 21 Oct 2015 25:28:00 GMT",
+    ] {
+        assert_eq!(retry::parse_retry_after(value, SystemTime::now()), None, "{:?}", value);
+    }
+}
+
+#[tokio::test]
+async fn gemini_keys_stay_out_of_urls_and_errors() {
+    let server = MockServer::start().await;
+    chat(&transport(1), &request(&server, "gemini", MODEL)).await.unwrap();
+    let recorded = server.last_request();
+    assert!(!recorded.path.contains("test-key"), "{}", recorded.path);
+    assert_eq!(recorded.headers.get("x-goog-api-key").map(String::as_str), Some("test-key"));
+
+    // Nothing listens on the port a dropped listener had
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    drop(listener);
+
+    let mut unreachable = request(&server, "gemini", MODEL);
+    unreachable.base_url = Some(format!("http://{}/v1beta", address));
+    let error = chat(&transport(1), &unreachable).await.unwrap_err();
+    assert!(matches!(error, ProviderError::Network { .. }), "{:?}", error);
+    assert!(!error.message().contains("test-key"), "{}", error);
+    assert!(!error.message().contains(&address.to_string()), "{}", error);
+}
@@ -0,0 +1,49 @@
+// Provider-neutral tool (function calling) types; each provider translates
+// them to and from its native wire format.
+
+use serde::{Deserialize, Serialize};
+
+/// A function the model may call, described by a JSON Schema for its
+/// arguments.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolDefinition {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// JSON Schema (`{"type": "object", "properties": ...}`).
+    #[serde(default = "empty_schema")]
+    pub parameters: serde_json::Value,
+}
+
+/// A call requested by the assistant. Providers without call ids (Gemini,
+/// Ollama) get a synthetic one so results can still be matched up.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolCall {
+    pub id: String,
+    pub name: String,
+    #[serde(default = "empty_object")]
+    pub arguments: serde_json::Value,
+}
+
+fn empty_schema() -> serde_json::Value {
+    serde_json::json!({ "type": "object", "properties": {} })
+}
+
+fn empty_object() -> serde_json::Value {
+    serde_json::json!({})
+}
+
+/// OpenAI-style providers send arguments as a JSON string; keep the raw
+/// string if the model produced invalid JSON so the caller can report it.
+pub fn parse_arguments(raw: &serde_json::Value) -> serde_json::Value {
+    match raw.as_str() {
+        Some(text) if text.trim().is_empty() => empty_object(),
+        Some(text) => serde_json::from_str(text).unwrap_or_else(|_| raw.clone()),
+        None if raw.is_null() => empty_object(),
+        None => raw.clone(),
+    }
+}
+
+pub fn synthetic_id(name: &str, index: usize) -> String {
+    format!("call_{}_{}", name, index)
+}
@@ -0,0 +1,122 @@
+// In-flight AI requests, tracked by id so the frontend can cancel them.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use tokio::task::AbortHandle;
+
+use crate::providers::ProviderError;
+
+#[derive(Default)]
+pub struct RequestRegistry {
+    /// Each entry carries the task's number, so a finished task only ever
+    /// removes its own entry and never one that reused its id.
+    tasks: Mutex<HashMap<String, (u64, AbortHandle)>>,
+    next_id: AtomicU64,
+    next_task: AtomicU64,
+}
+
+impl RequestRegistry {
+    /// Id for callers that did not supply their own.
+    pub fn next_id(&self) -> String {
+        format!("req-{}", self.next_id.fetch_add(1, Ordering::Relaxed))
+    }
+
+    /// Runs `task` on its own tokio task registered under `request_id`.
+    /// Aborting the task drops the pending reqwest future with it, which
+    /// closes the connection instead of waiting for the HTTP timeout. An id
+    /// that is still in flight is rejected, so `cancel` always reaches the
+    /// request it names.
+    pub async fn run<T, F>(&self, request_id: String, task: F) -> Result<T, ProviderError>
+    where
+        T: Send + 'static,
+        F: Future<Output = Result<T, ProviderError>> + Send + 'static,
+    {
+        let task_number = self.next_task.fetch_add(1, Ordering::Relaxed);
+        let handle = {
+            let mut tasks = self.tasks.lock().unwrap();
+            if tasks.contains_key(&request_id) {
+                return Err(ProviderError::bad_request(format!(
+                    "A request with id {} is already running",
+                    request_id
+                )));
+            }
+            let handle = tokio::spawn(task);
+            tasks.insert(request_id.clone(), (task_number, handle.abort_handle()));
+            handle
+        };
+
+        let result = handle.await;
+        let mut tasks = self.tasks.lock().unwrap();
+        if tasks.get(&request_id).is_some_and(|(number, _)| *number == task_number) {
+            tasks.remove(&request_id);
+        }
+        drop(tasks);
+
+        match result {
+            Ok(result) => result,
+            Err(e) if e.is_cancelled() => Err(ProviderError::cancelled()),
+            Err(e) => Err(ProviderError::Server {
+                status: None,
+                message: format!("Request task failed: {}", e),
+            }),
+        }
+    }
+
+    /// Returns `false` when no request with that id is in flight.
+    pub fn cancel(&self, request_id: &str) -> bool {
+        match self.tasks.lock().unwrap().remove(request_id) {
+            Some((_, handle)) => {
+                handle.abort();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    // A request that runs until cancelled
+    fn pending(registry: &Arc<RequestRegistry>, request_id: &str) -> tokio::task::JoinHandle<Result<(), ProviderError>> {
+        let registry = registry.clone();
+        let request_id = request_id.to_string();
+        tokio::spawn(async move { registry.run(request_id, std::future::pending()).await })
+    }
+
+    #[tokio::test]
+    async fn ids_in_flight_are_rejected() {
+        let registry = Arc::new(RequestRegistry::default());
+        let first = pending(&registry, "chat-1");
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        let error = registry.run("chat-1".to_string(), async { Ok(()) }).await.unwrap_err();
+        assert!(matches!(error, ProviderError::BadRequest { .. }), "{:?}", error);
+
+        assert!(registry.cancel("chat-1"));
+        let error = first.await.unwrap().unwrap_err();
+        assert!(matches!(error, ProviderError::Cancelled { .. }), "{:?}", error);
+    }
+
+    #[tokio::test]
+    async fn a_finished_run_leaves_a_reused_id_cancellable() {
+        let registry = Arc::new(RequestRegistry::default());
+        let first = pending(&registry, "chat-1");
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(registry.cancel("chat-1"));
+
+        // Reused before the cancelled task has been cleaned up
+        let second = pending(&registry, "chat-1");
+        first.await.unwrap().unwrap_err();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        assert!(registry.cancel("chat-1"));
+        second.await.unwrap().unwrap_err();
+        assert!(!registry.cancel("chat-1"));
+    }
+}
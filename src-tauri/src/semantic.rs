@@ -0,0 +1,581 @@
+// Semantic index of the open project: source files are split into
+// overlapping line windows, embedded, and kept on disk so the snippets most
+// relevant to a question can be retrieved without re-reading the tree.
+//
+// Each project gets two files named after a hash of its root: the chunk
+// metadata as JSON and the unit-length vectors as raw little-endian f32s.
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::UNIX_EPOCH;
+
+use crate::providers::{self, Credentials, EmbedPurpose, ProviderError, Transport};
+use crate::storage;
+
+const INDEX_DIR: &str = "semantic_index";
+
+const CHUNK_LINES: usize = 60;
+/// Lines shared by consecutive chunks, so code at a boundary stays findable.
+const CHUNK_OVERLAP: usize = 10;
+/// Minified or generated lines can be huge; longer chunks are cut.
+const MAX_CHUNK_CHARS: usize = 4_000;
+const MAX_FILE_BYTES: u64 = 512 * 1024;
+const MAX_FILES: usize = 5_000;
+/// Chunks per `embed` call; progress is reported after each.
+const EMBED_BATCH: usize = 64;
+pub const DEFAULT_RESULTS: usize = 5;
+const MAX_RESULTS: usize = 50;
+
+/// Dependency, build and cache directories; hidden entries are skipped too.
+const SKIPPED_DIRS: &[&str] = &[
+    "node_modules",
+    "target",
+    "dist",
+    "build",
+    "out",
+    "vendor",
+    "venv",
+    "__pycache__",
+];
+/// Generated files that would only crowd out real code.
+const SKIPPED_FILES: &[&str] = &["package-lock.json", "yarn.lock", "pnpm-lock.yaml", "Cargo.lock"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Chunk {
+    /// Relative to the project root, `/`-separated.
+    path: String,
+    /// 1-based and inclusive.
+    start_line: usize,
+    end_line: usize,
+    text: String,
+}
+
+/// Used to tell which files changed since the last run.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct FileStamp {
+    modified: u64,
+    bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct IndexMeta {
+    root: String,
+    provider: String,
+    model: String,
+    dimensions: usize,
+    updated_at: i64,
+    truncated: bool,
+    files: BTreeMap<String, FileStamp>,
+    chunks: Vec<Chunk>,
+}
+
+struct ProjectIndex {
+    meta: IndexMeta,
+    /// `chunks.len() * dimensions` values, each vector scaled to unit length.
+    vectors: Vec<f32>,
+}
+
+impl ProjectIndex {
+    fn vector(&self, i: usize) -> &[f32] {
+        let dims = self.meta.dimensions;
+        &self.vectors[i * dims..(i + 1) * dims]
+    }
+
+    fn status(&self, embedded: usize) -> IndexStatus {
+        IndexStatus {
+            root: self.meta.root.clone(),
+            provider: self.meta.provider.clone(),
+            model: self.meta.model.clone(),
+            files: self.meta.files.len(),
+            chunks: self.meta.chunks.len(),
+            updated_at: self.meta.updated_at,
+            truncated: self.meta.truncated,
+            embedded,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct IndexStatus {
+    pub root: String,
+    pub provider: String,
+    pub model: String,
+    pub files: usize,
+    pub chunks: usize,
+    pub updated_at: i64,
+    /// The project has more than `MAX_FILES` files; the rest were left out.
+    pub truncated: bool,
+    /// Chunks embedded by the run that produced this status; unchanged
+    /// files reuse their vectors.
+    pub embedded: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchResult {
+    pub path: String,
+    pub start_line: usize,
+    pub end_line: usize,
+    pub text: String,
+    /// Cosine similarity to the query.
+    pub score: f32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct IndexProgress {
+    pub root: String,
+    pub embedded: usize,
+    pub total: usize,
+}
+
+pub struct SemanticIndex {
+    /// `None` when the app data directory is unavailable.
+    dir: Option<PathBuf>,
+    /// The project searches run against.
+    current: RwLock<Option<Arc<ProjectIndex>>>,
+}
+
+impl SemanticIndex {
+    pub fn load(app_handle: &tauri::AppHandle) -> Self {
+        let dir = storage::app_data_file(app_handle, INDEX_DIR)
+            .ok()
+            .filter(|dir| fs::create_dir_all(dir).is_ok());
+
+        Self {
+            dir,
+            current: RwLock::new(None),
+        }
+    }
+
+    /// Provider and model the current project was indexed with.
+    pub fn embedding_model(&self) -> Option<(String, String)> {
+        let current = self.current.read().unwrap();
+        current.as_ref().map(|index| (index.meta.provider.clone(), index.meta.model.clone()))
+    }
+
+    /// Makes the saved index of `root` current, if there is one.
+    pub fn open(&self, root: &str) -> Result<Option<IndexStatus>, String> {
+        let root = canonical_root(root)?;
+        let Some(index) = self.read(&root) else {
+            return Ok(None);
+        };
+        let status = index.status(0);
+        *self.current.write().unwrap() = Some(Arc::new(index));
+        Ok(Some(status))
+    }
+
+    /// Indexes `root`, re-embedding only files changed since the last run
+    /// with the same model, and makes it the current project.
+    pub async fn index<F>(
+        &self,
+        transport: &Transport,
+        credentials: &Credentials,
+        model: &str,
+        root: &str,
+        mut on_progress: F,
+    ) -> Result<IndexStatus, ProviderError>
+    where
+        F: FnMut(IndexProgress),
+    {
+        let root = canonical_root(root).map_err(ProviderError::bad_request)?;
+        let (files, truncated) = project_files(&root);
+        let previous = self
+            .read(&root)
+            .filter(|index| index.meta.provider == credentials.provider && index.meta.model == model);
+
+        let mut meta = IndexMeta {
+            root: root.to_string_lossy().to_string(),
+            provider: credentials.provider.clone(),
+            model: model.to_string(),
+            dimensions: previous.as_ref().map_or(0, |index| index.meta.dimensions),
+            updated_at: Utc::now().timestamp(),
+            truncated,
+            files: BTreeMap::new(),
+            chunks: Vec::new(),
+        };
+        let mut previous_chunks: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+        if let Some(index) = &previous {
+            for (i, chunk) in index.meta.chunks.iter().enumerate() {
+                previous_chunks.entry(chunk.path.as_str()).or_default().push(i);
+            }
+        }
+
+        let mut vectors = Vec::new();
+        let mut pending = Vec::new();
+        for (path, stamp) in files {
+            let unchanged = previous
+                .as_ref()
+                .filter(|index| index.meta.files.get(&path) == Some(&stamp));
+            match unchanged {
+                Some(index) => {
+                    for &i in previous_chunks.get(path.as_str()).into_iter().flatten() {
+                        meta.chunks.push(index.meta.chunks[i].clone());
+                        vectors.extend_from_slice(index.vector(i));
+                    }
+                }
+                None => pending.extend(chunk_file(&root, &path)),
+            }
+            meta.files.insert(path, stamp);
+        }
+
+        let total = pending.len();
+        on_progress(IndexProgress {
+            root: meta.root.clone(),
+            embedded: 0,
+            total,
+        });
+        for (done, batch) in pending.chunks(EMBED_BATCH).enumerate() {
+            // The path gives the model context the snippet itself may lack
+            let texts: Vec<String> = batch
+                .iter()
+                .map(|chunk| format!("{}\n{}", chunk.path, chunk.text))
+                .collect();
+            let embedded =
+                providers::embed(transport, credentials, model, &texts, EmbedPurpose::Document).await?;
+
+            for (chunk, mut vector) in batch.iter().zip(embedded) {
+                if meta.dimensions == 0 {
+                    meta.dimensions = vector.len();
+                }
+                if vector.len() != meta.dimensions {
+                    return Err(ProviderError::parse(format!(
+                        "{} returned {}-dimensional embeddings, expected {}",
+                        model,
+                        vector.len(),
+                        meta.dimensions
+                    )));
+                }
+                normalize(&mut vector);
+                meta.chunks.push(chunk.clone());
+                vectors.extend(vector);
+            }
+            on_progress(IndexProgress {
+                root: meta.root.clone(),
+                embedded: (done * EMBED_BATCH + batch.len()).min(total),
+                total,
+            });
+        }
+
+        let index = ProjectIndex { meta, vectors };
+        self.write(&index).map_err(ProviderError::storage)?;
+        let status = index.status(total);
+        *self.current.write().unwrap() = Some(Arc::new(index));
+        Ok(status)
+    }
+
+    /// The `k` chunks of the current project closest to `query`, best first.
+    pub fn search(&self, query: &[f32], k: usize) -> Result<Vec<SearchResult>, ProviderError> {
+        let index = self
+            .current
+            .read()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| ProviderError::bad_request("No project has been indexed yet"))?;
+        if query.len() != index.meta.dimensions {
+            return Err(ProviderError::bad_request(
+                "The query embedding does not match the index; re-index the project",
+            ));
+        }
+
+        let mut query = query.to_vec();
+        normalize(&mut query);
+        let mut scored: Vec<(usize, f32)> = (0..index.meta.chunks.len())
+            .map(|i| (i, dot(&query, index.vector(i))))
+            .collect();
+        scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+        let root = Path::new(&index.meta.root);
+        Ok(scored
+            .into_iter()
+            .take(k.clamp(1, MAX_RESULTS))
+            .map(|(i, score)| {
+                let chunk = &index.meta.chunks[i];
+                SearchResult {
+                    path: root.join(&chunk.path).to_string_lossy().to_string(),
+                    start_line: chunk.start_line,
+                    end_line: chunk.end_line,
+                    text: chunk.text.clone(),
+                    score,
+                }
+            })
+            .collect())
+    }
+
+    pub fn clear(&self, root: &str) -> Result<(), String> {
+        let root = canonical_root(root)?;
+        let (meta_file, vector_file) = self.files(&root)?;
+        for file in [meta_file, vector_file] {
+            if file.exists() {
+                fs::remove_file(file).map_err(|e| format!("Failed to delete project index: {}", e))?;
+            }
+        }
+
+        let mut current = self.current.write().unwrap();
+        if current.as_ref().is_some_and(|index| Path::new(&index.meta.root) == root) {
+            *current = None;
+        }
+        Ok(())
+    }
+
+    fn read(&self, root: &Path) -> Option<ProjectIndex> {
+        let (meta_file, vector_file) = self.files(root).ok()?;
+        let meta: IndexMeta = serde_json::from_str(&fs::read_to_string(meta_file).ok()?).ok()?;
+        let vectors: Vec<f32> = fs::read(vector_file)
+            .ok()?
+            .chunks_exact(4)
+            .map(|bytes| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+            .collect();
+
+        // A half-written pair is worth less than a fresh run
+        (vectors.len() == meta.chunks.len() * meta.dimensions).then_some(ProjectIndex { meta, vectors })
+    }
+
+    fn write(&self, index: &ProjectIndex) -> Result<(), String> {
+        let (meta_file, vector_file) = self.files(Path::new(&index.meta.root))?;
+        let bytes: Vec<u8> = index.vectors.iter().flat_map(|v| v.to_le_bytes()).collect();
+        let text =
+            serde_json::to_string(&index.meta).map_err(|e| format!("Failed to serialize project index: {}", e))?;
+
+        fs::write(vector_file, bytes).map_err(|e| format!("Failed to save project index: {}", e))?;
+        fs::write(meta_file, text).map_err(|e| format!("Failed to save project index: {}", e))
+    }
+
+    fn files(&self, root: &Path) -> Result<(PathBuf, PathBuf), String> {
+        let dir = self.dir.as_ref().ok_or("Project index storage is unavailable")?;
+        let hash: String = Sha256::digest(root.to_string_lossy().as_bytes())
+            .iter()
+            .take(16)
+            .map(|b| format!("{:02x}", b))
+            .collect();
+        Ok((dir.join(format!("{}.json", hash)), dir.join(format!("{}.vec", hash))))
+    }
+}
+
+fn canonical_root(root: &str) -> Result<PathBuf, String> {
+    let root = fs::canonicalize(root).map_err(|e| format!("Cannot open project {}: {}", root, e))?;
+    if !root.is_dir() {
+        return Err(format!("Not a folder: {}", root.display()));
+    }
+    Ok(root)
+}
+
+// Text files worth indexing, by relative path; symlinks are not followed
+fn project_files(root: &Path) -> (Vec<(String, FileStamp)>, bool) {
+    let mut files = Vec::new();
+    let mut pending = vec![root.to_path_buf()];
+
+    while let Some(dir) = pending.pop() {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        let mut entries: Vec<_> = entries.flatten().collect();
+        entries.sort_by_key(|entry| entry.file_name());
+
+        for entry in entries {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            if name.starts_with('.') {
+                continue;
+            }
+            if file_type.is_dir() {
+                if !SKIPPED_DIRS.contains(&name.as_str()) {
+                    pending.push(entry.path());
+                }
+                continue;
+            }
+            if !file_type.is_file() || SKIPPED_FILES.contains(&name.as_str()) {
+                continue;
+            }
+
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            if metadata.len() == 0 || metadata.len() > MAX_FILE_BYTES {
+                continue;
+            }
+            if files.len() == MAX_FILES {
+                return (files, true);
+            }
+
+            let path = entry.path();
+            let Ok(relative) = path.strip_prefix(root) else {
+                continue;
+            };
+            let modified = metadata
+                .modified()
+                .ok()
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                .map_or(0, |age| age.as_secs());
+            files.push((
+                relative.to_string_lossy().replace('\\', "/"),
+                FileStamp {
+                    modified,
+                    bytes: metadata.len(),
+                },
+            ));
+        }
+    }
+    (files, false)
+}
+
+// Overlapping line windows; binary and non-UTF-8 files yield nothing
+fn chunk_file(root: &Path, path: &str) -> Vec<Chunk> {
+    let Ok(text) = fs::read_to_string(root.join(path)) else {
+        return Vec::new();
+    };
+    if text.contains('\0') {
+        return Vec::new();
+    }
+
+    let lines: Vec<&str> = text.lines().collect();
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    while start < lines.len() {
+        let end = (start + CHUNK_LINES).min(lines.len());
+        let window = lines[start..end].join("\n");
+        if !window.trim().is_empty() {
+            chunks.push(Chunk {
+                path: path.to_string(),
+                start_line: start + 1,
+                end_line: end,
+                text: window.chars().take(MAX_CHUNK_CHARS).collect(),
+            });
+        }
+        if end == lines.len() {
+            break;
+        }
+        start = end - CHUNK_OVERLAP;
+    }
+    chunks
+}
+
+fn normalize(vector: &mut [f32]) {
+    let norm = dot(vector, vector).sqrt();
+    if norm > 0.0 {
+        vector.iter_mut().for_each(|v| *v /= norm);
+    }
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(path: &str, line: usize) -> Chunk {
+        Chunk {
+            path: path.to_string(),
+            start_line: line,
+            end_line: line,
+            text: format!("{}:{}", path, line),
+        }
+    }
+
+    fn project(root: &Path, vectors: &[[f32; 2]]) -> ProjectIndex {
+        ProjectIndex {
+            meta: IndexMeta {
+                root: root.to_string_lossy().to_string(),
+                provider: "openai".to_string(),
+                model: "text-embedding-3-small".to_string(),
+                dimensions: 2,
+                updated_at: 0,
+                truncated: false,
+                files: BTreeMap::new(),
+                chunks: (0..vectors.len()).map(|i| chunk("lib.rs", i + 1)).collect(),
+            },
+            vectors: vectors.iter().flatten().copied().collect(),
+        }
+    }
+
+    fn temp_index() -> (tempfile::TempDir, SemanticIndex) {
+        let dir = tempfile::tempdir().unwrap();
+        let index = SemanticIndex {
+            dir: Some(dir.path().to_path_buf()),
+            current: RwLock::new(None),
+        };
+        (dir, index)
+    }
+
+    #[test]
+    fn files_are_split_into_overlapping_line_windows() {
+        let dir = tempfile::tempdir().unwrap();
+        let text: Vec<String> = (1..=130).map(|i| format!("line {}", i)).collect();
+        fs::write(dir.path().join("main.rs"), text.join("\n")).unwrap();
+
+        let chunks = chunk_file(dir.path(), "main.rs");
+
+        let lines: Vec<(usize, usize)> = chunks.iter().map(|c| (c.start_line, c.end_line)).collect();
+        assert_eq!(lines, [(1, 60), (51, 110), (101, 130)]);
+        assert!(chunks[1].text.starts_with("line 51\n"));
+        assert!(chunks[2].text.ends_with("line 130"));
+    }
+
+    #[test]
+    fn blank_binary_and_oversized_content_is_handled() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("blank.txt"), "\n  \n\n").unwrap();
+        fs::write(dir.path().join("image.png"), b"PNG\0\x01").unwrap();
+        fs::write(dir.path().join("bundle.js"), "x".repeat(MAX_CHUNK_CHARS + 100)).unwrap();
+
+        assert!(chunk_file(dir.path(), "blank.txt").is_empty());
+        assert!(chunk_file(dir.path(), "image.png").is_empty());
+        assert!(chunk_file(dir.path(), "missing.rs").is_empty());
+        let chunks = chunk_file(dir.path(), "bundle.js");
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].text.len(), MAX_CHUNK_CHARS);
+    }
+
+    #[test]
+    fn vectors_are_scaled_to_unit_length() {
+        let mut vector = [3.0, 4.0];
+        normalize(&mut vector);
+        assert_eq!(vector, [0.6, 0.8]);
+
+        let mut zero = [0.0, 0.0];
+        normalize(&mut zero);
+        assert_eq!(zero, [0.0, 0.0]);
+    }
+
+    #[test]
+    fn vectors_are_stored_as_little_endian_f32s() {
+        let (_dir, index) = temp_index();
+        let root = tempfile::tempdir().unwrap();
+        index.write(&project(root.path(), &[[1.0, -2.5]])).unwrap();
+
+        let (_, vector_file) = index.files(root.path()).unwrap();
+        assert_eq!(fs::read(&vector_file).unwrap(), [0, 0, 0x80, 0x3f, 0, 0, 0x20, 0xc0]);
+        assert_eq!(index.read(root.path()).unwrap().vectors, [1.0, -2.5]);
+
+        // A vector file that does not match the chunks is not trusted
+        fs::write(&vector_file, [0, 0, 0x80, 0x3f]).unwrap();
+        assert!(index.read(root.path()).is_none());
+    }
+
+    #[test]
+    fn searches_return_the_closest_chunks_first() {
+        let (_dir, index) = temp_index();
+        let root = tempfile::tempdir().unwrap();
+        let project = project(root.path(), &[[0.0, 1.0], [1.0, 0.0], [0.6, 0.8]]);
+        *index.current.write().unwrap() = Some(Arc::new(project));
+
+        let results = index.search(&[2.0, 0.0], 2).unwrap();
+        let lines: Vec<usize> = results.iter().map(|r| r.start_line).collect();
+        assert_eq!(lines, [2, 3]);
+        assert!((results[0].score - 1.0).abs() < 1e-6);
+        assert!((results[1].score - 0.6).abs() < 1e-6);
+
+        assert_eq!(index.search(&[0.0, 1.0], 0).unwrap().len(), 1);
+        assert!(matches!(
+            index.search(&[1.0, 0.0, 0.0], 2),
+            Err(ProviderError::BadRequest { .. })
+        ));
+    }
+}
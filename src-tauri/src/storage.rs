@@ -0,0 +1,34 @@
+// JSON files in the app data directory backing persisted backend settings.
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fs;
+use std::path::PathBuf;
+use tauri::api::path;
+
+pub fn app_data_file(app_handle: &tauri::AppHandle, name: &str) -> Result<PathBuf, String> {
+    let app_dir = path::app_data_dir(&app_handle.config())
+        .ok_or("Failed to get app data directory")?;
+
+    fs::create_dir_all(&app_dir).map_err(|e| format!("Failed to create app directory: {}", e))?;
+
+    Ok(app_dir.join(name))
+}
+
+/// Reads `name` from the app data directory, falling back to the default
+/// when the file does not exist yet or no longer parses.
+pub fn load_json<T: DeserializeOwned + Default>(app_handle: &tauri::AppHandle, name: &str) -> T {
+    app_data_file(app_handle, name)
+        .ok()
+        .and_then(|file| fs::read_to_string(file).ok())
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_json<T: Serialize>(app_handle: &tauri::AppHandle, name: &str, value: &T) -> Result<(), String> {
+    let file = app_data_file(app_handle, name)?;
+    let text = serde_json::to_string_pretty(value)
+        .map_err(|e| format!("Failed to serialize {}: {}", name, e))?;
+
+    fs::write(file, text).map_err(|e| format!("Failed to save {}: {}", name, e))
+}
@@ -0,0 +1,381 @@
+// Token usage ledger - persisted per provider/model/day, with cost estimates
+// from an editable price table.
+
+use chrono::{Duration, Local};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::{Mutex, RwLock};
+
+use crate::providers::Usage;
+use crate::storage;
+
+const USAGE_FILE: &str = "usage.json";
+const PRICES_FILE: &str = "prices.json";
+
+/// Providers that run on the user's machine and never bill.
+const LOCAL_PROVIDERS: &[&str] = &["ollama"];
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UsageEntry {
+    pub date: String,
+    pub provider: String,
+    pub model: String,
+    pub requests: u64,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+}
+
+/// USD per million tokens.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelPrice {
+    pub prompt: f64,
+    pub completion: f64,
+}
+
+/// Keys are model ids matched by longest prefix, so `gpt-4o` also prices
+/// `gpt-4o-2024-08-06`. A `provider:` prefix restricts a key to one provider.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PriceTable(pub BTreeMap<String, ModelPrice>);
+
+impl Default for PriceTable {
+    fn default() -> Self {
+        let prices = [
+            ("gpt-4o", 2.5, 10.0),
+            ("gpt-4o-mini", 0.15, 0.6),
+            ("gpt-4-turbo", 10.0, 30.0),
+            ("gpt-4", 30.0, 60.0),
+            ("gpt-3.5-turbo", 0.5, 1.5),
+            ("claude-3-opus", 15.0, 75.0),
+            ("claude-3-sonnet", 3.0, 15.0),
+            ("claude-3-5-sonnet", 3.0, 15.0),
+            ("claude-3-haiku", 0.25, 1.25),
+            ("gemini-pro", 0.5, 1.5),
+            ("gemini-1.5-pro", 1.25, 5.0),
+            ("gemini-1.5-flash", 0.075, 0.3),
+            ("mistral-large", 2.0, 6.0),
+            ("mistral-medium", 2.7, 8.1),
+            ("mistral-small", 0.2, 0.6),
+            ("open-mistral-7b", 0.25, 0.25),
+            ("open-mixtral-8x7b", 0.7, 0.7),
+            ("open-mixtral-8x22b", 2.0, 6.0),
+            ("command-r-plus", 2.5, 10.0),
+            ("command-r", 0.15, 0.6),
+            ("command", 1.0, 2.0),
+            ("command-light", 0.3, 0.6),
+        ];
+
+        PriceTable(
+            prices
+                .iter()
+                .map(|(model, prompt, completion)| {
+                    (
+                        model.to_string(),
+                        ModelPrice {
+                            prompt: *prompt,
+                            completion: *completion,
+                        },
+                    )
+                })
+                .collect(),
+        )
+    }
+}
+
+impl PriceTable {
+    /// Estimated cost in USD, or `None` when the model has no known price.
+    pub fn cost(&self, provider: &str, model: &str, prompt: u64, completion: u64) -> Option<f64> {
+        if LOCAL_PROVIDERS.contains(&provider) {
+            return Some(0.0);
+        }
+
+        let price = self.price(provider, model)?;
+        Some((prompt as f64 * price.prompt + completion as f64 * price.completion) / 1_000_000.0)
+    }
+
+    /// The table entry with the longest key matching the model id.
+    pub fn price(&self, provider: &str, model: &str) -> Option<&ModelPrice> {
+        // OpenRouter ids look like `openai/gpt-4`; fall back to the bare model
+        let bare = model.rsplit('/').next().unwrap_or(model);
+
+        [model, bare].iter().find_map(|candidate| {
+            self.0
+                .iter()
+                .filter_map(|(key, price)| {
+                    let key = match key.split_once(':') {
+                        Some((p, k)) if p == provider => k,
+                        Some(_) => return None,
+                        None => key.as_str(),
+                    };
+                    candidate.starts_with(key).then_some((key.len(), price))
+                })
+                .max_by_key(|(len, _)| *len)
+                .map(|(_, price)| price)
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct UsageTotals {
+    pub requests: u64,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub cost: f64,
+    /// Requests whose model is missing from the price table.
+    pub unpriced_requests: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UsageReportEntry {
+    #[serde(flatten)]
+    pub entry: UsageEntry,
+    pub cost: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UsageReport {
+    pub entries: Vec<UsageReportEntry>,
+    pub total: UsageTotals,
+    /// Usage since the app was started.
+    pub session: UsageTotals,
+}
+
+pub struct UsageLedger {
+    entries: Mutex<Vec<UsageEntry>>,
+    session: Mutex<Vec<UsageEntry>>,
+    prices: RwLock<PriceTable>,
+}
+
+impl UsageLedger {
+    pub fn load(app_handle: &tauri::AppHandle) -> Self {
+        let entries: Vec<UsageEntry> = storage::load_json(app_handle, USAGE_FILE);
+        let prices: Option<PriceTable> = storage::load_json(app_handle, PRICES_FILE);
+
+        Self {
+            entries: Mutex::new(entries),
+            session: Mutex::new(Vec::new()),
+            prices: RwLock::new(prices.unwrap_or_default()),
+        }
+    }
+
+    /// Adds one request to today's totals and returns its estimated cost.
+    pub fn record(
+        &self,
+        app_handle: &tauri::AppHandle,
+        provider: &str,
+        model: &str,
+        usage: Option<&Usage>,
+    ) -> Result<Option<f64>, String> {
+        let today = Local::now().format("%Y-%m-%d").to_string();
+        let usage = usage.cloned().unwrap_or_default();
+
+        add_usage(&mut self.session.lock().unwrap(), &today, provider, model, &usage);
+
+        let mut entries = self.entries.lock().unwrap();
+        add_usage(&mut entries, &today, provider, model, &usage);
+        storage::save_json(app_handle, USAGE_FILE, &*entries)?;
+
+        Ok(self.prices.read().unwrap().cost(
+            provider,
+            model,
+            usage.prompt_tokens,
+            usage.completion_tokens,
+        ))
+    }
+
+    /// Usage for the last `days` days (all history when `None`).
+    pub fn report(&self, days: Option<u32>) -> UsageReport {
+        let prices = self.prices.read().unwrap();
+        let since = days.map(|d| {
+            (Local::now() - Duration::days(i64::from(d.saturating_sub(1))))
+                .format("%Y-%m-%d")
+                .to_string()
+        });
+
+        let mut total = UsageTotals::default();
+        let entries = self
+            .entries
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|e| since.as_ref().is_none_or(|since| e.date >= *since))
+            .map(|entry| {
+                let cost = price_entry(&prices, entry, &mut total);
+                UsageReportEntry {
+                    entry: entry.clone(),
+                    cost,
+                }
+            })
+            .collect();
+
+        let mut session = UsageTotals::default();
+        for entry in self.session.lock().unwrap().iter() {
+            price_entry(&prices, entry, &mut session);
+        }
+
+        UsageReport {
+            entries,
+            total,
+            session,
+        }
+    }
+
+    pub fn prices(&self) -> PriceTable {
+        self.prices.read().unwrap().clone()
+    }
+
+    pub fn set_prices(&self, app_handle: &tauri::AppHandle, prices: PriceTable) -> Result<(), String> {
+        storage::save_json(app_handle, PRICES_FILE, &prices)?;
+        *self.prices.write().unwrap() = prices;
+        Ok(())
+    }
+}
+
+fn add_usage(entries: &mut Vec<UsageEntry>, date: &str, provider: &str, model: &str, usage: &Usage) {
+    let index = match entries
+        .iter()
+        .position(|e| e.date == date && e.provider == provider && e.model == model)
+    {
+        Some(index) => index,
+        None => {
+            entries.push(UsageEntry {
+                date: date.to_string(),
+                provider: provider.to_string(),
+                model: model.to_string(),
+                ..UsageEntry::default()
+            });
+            entries.len() - 1
+        }
+    };
+
+    let entry = &mut entries[index];
+    entry.requests += 1;
+    entry.prompt_tokens += usage.prompt_tokens;
+    entry.completion_tokens += usage.completion_tokens;
+}
+
+fn price_entry(prices: &PriceTable, entry: &UsageEntry, totals: &mut UsageTotals) -> Option<f64> {
+    let cost = prices.cost(
+        &entry.provider,
+        &entry.model,
+        entry.prompt_tokens,
+        entry.completion_tokens,
+    );
+
+    totals.requests += entry.requests;
+    totals.prompt_tokens += entry.prompt_tokens;
+    totals.completion_tokens += entry.completion_tokens;
+    match cost {
+        Some(cost) => totals.cost += cost,
+        None => totals.unpriced_requests += entry.requests,
+    }
+
+    cost
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usage(prompt_tokens: u64, completion_tokens: u64) -> Usage {
+        Usage {
+            prompt_tokens,
+            completion_tokens,
+        }
+    }
+
+    fn ledger(entries: Vec<UsageEntry>, session: Vec<UsageEntry>) -> UsageLedger {
+        UsageLedger {
+            entries: Mutex::new(entries),
+            session: Mutex::new(session),
+            prices: RwLock::new(PriceTable::default()),
+        }
+    }
+
+    #[test]
+    fn prices_match_the_longest_model_prefix() {
+        let prices = PriceTable::default();
+        let prompt = |provider, model| prices.price(provider, model).map(|price| price.prompt);
+
+        assert_eq!(prompt("openai", "gpt-4o-2024-08-06"), Some(2.5));
+        assert_eq!(prompt("openai", "gpt-4o-mini-2024-07-18"), Some(0.15));
+        assert_eq!(prompt("openai", "gpt-4-0613"), Some(30.0));
+        assert_eq!(prompt("cohere", "command-light-nightly"), Some(0.3));
+        // OpenRouter ids carry the vendor
+        assert_eq!(prompt("openrouter", "openai/gpt-4o"), Some(2.5));
+    }
+
+    #[test]
+    fn provider_scoped_prices_only_apply_to_that_provider() {
+        let mut prices = PriceTable::default();
+        prices.0.insert(
+            "openrouter:gpt-4o".to_string(),
+            ModelPrice {
+                prompt: 3.0,
+                completion: 12.0,
+            },
+        );
+
+        assert_eq!(prices.price("openrouter", "openai/gpt-4o").unwrap().prompt, 3.0);
+        assert_eq!(prices.price("openai", "gpt-4o").unwrap().prompt, 2.5);
+    }
+
+    #[test]
+    fn unknown_models_have_no_cost_but_local_ones_are_free() {
+        let prices = PriceTable::default();
+
+        assert!(prices.price("openai", "my-finetune").is_none());
+        assert_eq!(prices.cost("openai", "my-finetune", 1000, 1000), None);
+        assert_eq!(prices.cost("ollama", "llama3", 1000, 1000), Some(0.0));
+
+        let cost = prices.cost("openai", "gpt-4o", 1_000_000, 500_000).unwrap();
+        assert!((cost - 7.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn usage_adds_up_per_day_provider_and_model() {
+        let mut entries = Vec::new();
+        add_usage(&mut entries, "2024-05-01", "openai", "gpt-4o", &usage(100, 10));
+        add_usage(&mut entries, "2024-05-01", "openai", "gpt-4o", &usage(50, 5));
+        add_usage(&mut entries, "2024-05-01", "openai", "gpt-4o-mini", &usage(1, 1));
+        add_usage(&mut entries, "2024-05-02", "openai", "gpt-4o", &usage(1, 1));
+
+        assert_eq!(entries.len(), 3);
+        assert_eq!(
+            (entries[0].requests, entries[0].prompt_tokens, entries[0].completion_tokens),
+            (2, 150, 15)
+        );
+    }
+
+    #[test]
+    fn session_totals_price_known_models_and_count_the_rest() {
+        let mut session = Vec::new();
+        add_usage(&mut session, "2024-05-01", "openai", "gpt-4o", &usage(1_000_000, 0));
+        add_usage(&mut session, "2024-05-01", "openai", "gpt-4o", &usage(0, 100_000));
+        add_usage(&mut session, "2024-05-01", "openai", "my-finetune", &usage(10, 10));
+        add_usage(&mut session, "2024-05-01", "ollama", "llama3", &usage(10, 10));
+
+        let report = ledger(Vec::new(), session).report(None);
+        let totals = report.session;
+
+        assert_eq!(totals.requests, 4);
+        assert_eq!((totals.prompt_tokens, totals.completion_tokens), (1_000_020, 100_020));
+        assert!((totals.cost - 3.5).abs() < 1e-9);
+        assert_eq!(totals.unpriced_requests, 1);
+        assert!(report.entries.is_empty());
+    }
+
+    #[test]
+    fn reports_only_cover_the_requested_days() {
+        let today = Local::now().format("%Y-%m-%d").to_string();
+        let mut entries = Vec::new();
+        add_usage(&mut entries, "2000-01-01", "openai", "gpt-4o", &usage(1, 1));
+        add_usage(&mut entries, &today, "openai", "gpt-4o", &usage(1, 1));
+        let ledger = ledger(entries, Vec::new());
+
+        let recent = ledger.report(Some(7));
+        assert_eq!(recent.entries.len(), 1);
+        assert_eq!(recent.entries[0].entry.date, today);
+        assert_eq!(recent.total.requests, 1);
+        assert_eq!(ledger.report(None).total.requests, 2);
+    }
+}
@@ -0,0 +1,525 @@
+// Encrypted API key vault - every key is sealed with AES-256-GCM under a key
+// derived (Argon2id) from either a master password or this machine's id.
+
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use argon2::Argon2;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::Mutex;
+use zeroize::Zeroizing;
+
+use crate::storage;
+
+const VAULT_FILE: &str = "vault.json";
+const LEGACY_KEY_SUFFIX: &str = "_api_key.txt";
+/// Encrypted under the vault key so unlocking can tell a wrong password
+/// apart from a corrupted entry.
+const CHECK_PLAINTEXT: &[u8] = b"bonzo-devassist-vault";
+const CHECK_AAD: &[u8] = b"check";
+/// Mixed into the machine id so the derived key is specific to this app.
+const MACHINE_CONTEXT: &str = "bonzo-devassist:api-key-vault";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VaultMode {
+    /// Key derived from the OS machine id; unlocks automatically on start.
+    Machine,
+    /// Key derived from a master password the user has to enter.
+    Password,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Sealed {
+    nonce: String,
+    ciphertext: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct VaultFile {
+    version: u32,
+    mode: VaultMode,
+    salt: String,
+    check: Sealed,
+    entries: BTreeMap<String, Sealed>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct VaultStatus {
+    pub exists: bool,
+    pub unlocked: bool,
+    pub mode: Option<VaultMode>,
+    /// Why the last unlock failed, e.g. the automatic one at startup;
+    /// cleared once an unlock succeeds.
+    pub error: Option<String>,
+}
+
+/// What the frontend may learn about a stored key without seeing it.
+#[derive(Debug, Clone, Serialize)]
+pub struct ApiKeyStatus {
+    pub provider: String,
+    pub configured: bool,
+    /// e.g. `sk-…3f9a`; `None` while the vault is locked.
+    pub masked: Option<String>,
+}
+
+type VaultKey = Zeroizing<[u8; 32]>;
+
+#[derive(Default)]
+pub struct Vault {
+    file: Mutex<Option<VaultFile>>,
+    key: Mutex<Option<VaultKey>>,
+    /// Set when the vault file exists but could not be read; the vault then
+    /// stays locked instead of being replaced by an empty one.
+    load_error: Option<String>,
+    unlock_error: Mutex<Option<String>>,
+}
+
+impl Vault {
+    pub fn load(app_handle: &tauri::AppHandle) -> Self {
+        let loaded = storage::app_data_file(app_handle, VAULT_FILE).and_then(|path| read_vault_file(&path));
+        let (file, load_error) = match loaded {
+            Ok(file) => (file, None),
+            Err(e) => (None, Some(e)),
+        };
+        Self {
+            file: Mutex::new(file),
+            key: Mutex::new(None),
+            load_error,
+            unlock_error: Mutex::new(None),
+        }
+    }
+
+    pub fn status(&self) -> VaultStatus {
+        let file = self.file.lock().unwrap();
+        VaultStatus {
+            exists: file.is_some(),
+            unlocked: self.key.lock().unwrap().is_some(),
+            mode: file.as_ref().map(|f| f.mode),
+            error: self.unlock_error.lock().unwrap().clone().or_else(|| self.load_error.clone()),
+        }
+    }
+
+    /// Unlocks the vault, creating it on first use (password mode when a
+    /// password is given, machine mode otherwise) and migrating any legacy
+    /// plaintext key files into it. A failure is also kept for `status`.
+    pub fn unlock(&self, app_handle: &tauri::AppHandle, password: Option<&str>) -> Result<(), String> {
+        let result = self.try_unlock(app_handle, password);
+        *self.unlock_error.lock().unwrap() = result.as_ref().err().cloned();
+        result
+    }
+
+    fn try_unlock(&self, app_handle: &tauri::AppHandle, password: Option<&str>) -> Result<(), String> {
+        let mut file = self.file.lock().unwrap();
+
+        let key = match file.as_ref() {
+            Some(existing) => {
+                let secret = secret_for(existing.mode, password)?;
+                unlock_key(existing, &secret)?
+            }
+            None => {
+                // Never create a vault over one that exists but did not load
+                if storage::app_data_file(app_handle, VAULT_FILE)?.exists() {
+                    return Err(self.load_error.clone().unwrap_or_else(|| {
+                        format!("{} changed on disk; restart the app to open it", VAULT_FILE)
+                    }));
+                }
+                let mode = if password.is_some() {
+                    VaultMode::Password
+                } else {
+                    VaultMode::Machine
+                };
+                let secret = secret_for(mode, password)?;
+                let (new_file, key) = new_vault(mode, &secret)?;
+                *file = Some(new_file);
+                key
+            }
+        };
+
+        if let Some(file) = file.as_mut() {
+            let app_dir = storage::app_data_file(app_handle, "")?;
+            migrate_legacy_keys(&app_dir, file, &key, |file| storage::save_json(app_handle, VAULT_FILE, file))?;
+            storage::save_json(app_handle, VAULT_FILE, file)?;
+        }
+
+        *self.key.lock().unwrap() = Some(key);
+        Ok(())
+    }
+
+    pub fn lock(&self) {
+        *self.key.lock().unwrap() = None;
+    }
+
+    /// Switches between password and machine mode, re-encrypting every key.
+    pub fn change_password(&self, app_handle: &tauri::AppHandle, password: Option<&str>) -> Result<(), String> {
+        let mut file = self.file.lock().unwrap();
+        let mut key = self.key.lock().unwrap();
+        let (current, old_key) = match (file.as_ref(), key.as_ref()) {
+            (Some(current), Some(old_key)) => (current, old_key),
+            _ => return Err("Vault is locked".to_string()),
+        };
+
+        let mode = if password.is_some() {
+            VaultMode::Password
+        } else {
+            VaultMode::Machine
+        };
+        let secret = secret_for(mode, password)?;
+        let (mut new_file, new_key) = new_vault(mode, &secret)?;
+        for (provider, sealed) in &current.entries {
+            let plaintext = Zeroizing::new(open(old_key, sealed, provider.as_bytes())?);
+            new_file
+                .entries
+                .insert(provider.clone(), seal(&new_key, &plaintext, provider.as_bytes())?);
+        }
+
+        storage::save_json(app_handle, VAULT_FILE, &new_file)?;
+        *file = Some(new_file);
+        *key = Some(new_key);
+        Ok(())
+    }
+
+    pub fn set(&self, app_handle: &tauri::AppHandle, provider: &str, secret: &str) -> Result<(), String> {
+        let mut file = self.file.lock().unwrap();
+        let key = self.key.lock().unwrap();
+        let (file, key) = unlocked(&mut file, &key)?;
+
+        let sealed = seal(key, secret.as_bytes(), provider.as_bytes())?;
+        file.entries.insert(provider.to_string(), sealed);
+        storage::save_json(app_handle, VAULT_FILE, file)
+    }
+
+    pub fn get(&self, provider: &str) -> Result<Option<String>, String> {
+        let mut file = self.file.lock().unwrap();
+        let key = self.key.lock().unwrap();
+        let (file, key) = unlocked(&mut file, &key)?;
+
+        match file.entries.get(provider) {
+            Some(sealed) => {
+                let plaintext = open(key, sealed, provider.as_bytes())?;
+                String::from_utf8(plaintext)
+                    .map(Some)
+                    .map_err(|_| "Stored key is not valid UTF-8".to_string())
+            }
+            None => Ok(None),
+        }
+    }
+
+    pub fn delete(&self, app_handle: &tauri::AppHandle, provider: &str) -> Result<(), String> {
+        let mut file = self.file.lock().unwrap();
+        let key = self.key.lock().unwrap();
+        let (file, _) = unlocked(&mut file, &key)?;
+
+        file.entries.remove(provider);
+        storage::save_json(app_handle, VAULT_FILE, file)
+    }
+
+    pub fn key_status(&self, provider: &str) -> ApiKeyStatus {
+        let configured = self
+            .file
+            .lock()
+            .unwrap()
+            .as_ref()
+            .is_some_and(|f| f.entries.contains_key(provider));
+        let masked = match self.get(provider) {
+            Ok(Some(secret)) => Some(mask(&secret)),
+            _ => None,
+        };
+
+        ApiKeyStatus {
+            provider: provider.to_string(),
+            configured,
+            masked,
+        }
+    }
+
+    /// Provider names are not secret, so this works while locked.
+    pub fn providers(&self) -> Vec<String> {
+        self.file
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|f| f.entries.keys().cloned().collect())
+            .unwrap_or_default()
+    }
+}
+
+/// Keeps a short prefix (which identifies the key type) and the last four
+/// characters; short keys are masked entirely.
+fn mask(secret: &str) -> String {
+    let chars: Vec<char> = secret.chars().collect();
+    if chars.len() < 12 {
+        return "••••".to_string();
+    }
+    let prefix: String = chars[..3].iter().collect();
+    let suffix: String = chars[chars.len() - 4..].iter().collect();
+    format!("{}…{}", prefix, suffix)
+}
+
+// `None` when there is no vault yet, an error when one exists but cannot be
+// read or parsed
+fn read_vault_file(path: &Path) -> Result<Option<VaultFile>, String> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("Failed to read {}: {}", VAULT_FILE, e)),
+    };
+    serde_json::from_str(&text).map(Some).map_err(|e| {
+        format!(
+            "{} is corrupted ({}); your keys were left untouched. Restore a backup or move the file aside to start a new vault",
+            VAULT_FILE, e
+        )
+    })
+}
+
+fn unlocked<'a>(
+    file: &'a mut Option<VaultFile>,
+    key: &'a Option<VaultKey>,
+) -> Result<(&'a mut VaultFile, &'a VaultKey), String> {
+    match (file.as_mut(), key.as_ref()) {
+        (Some(file), Some(key)) => Ok((file, key)),
+        _ => Err("Vault is locked".to_string()),
+    }
+}
+
+fn secret_for(mode: VaultMode, password: Option<&str>) -> Result<Zeroizing<Vec<u8>>, String> {
+    match mode {
+        VaultMode::Password => match password {
+            Some(password) if !password.is_empty() => Ok(Zeroizing::new(password.as_bytes().to_vec())),
+            _ => Err("Vault password required".to_string()),
+        },
+        VaultMode::Machine => {
+            let machine_id = machine_uid::get().map_err(|e| format!("Failed to read machine id: {}", e))?;
+            Ok(Zeroizing::new(
+                format!("{}:{}", MACHINE_CONTEXT, machine_id.trim()).into_bytes(),
+            ))
+        }
+    }
+}
+
+fn derive_key(secret: &[u8], salt: &[u8]) -> Result<VaultKey, String> {
+    let mut key = Zeroizing::new([0u8; 32]);
+    Argon2::default()
+        .hash_password_into(secret, salt, key.as_mut())
+        .map_err(|e| format!("Failed to derive vault key: {}", e))?;
+    Ok(key)
+}
+
+// Derives the key of an existing vault and checks it against the sealed
+// check value
+fn unlock_key(file: &VaultFile, secret: &[u8]) -> Result<VaultKey, String> {
+    let salt = BASE64
+        .decode(&file.salt)
+        .map_err(|_| "Vault file is corrupted".to_string())?;
+    let key = derive_key(secret, &salt)?;
+    open(&key, &file.check, CHECK_AAD).map_err(|_| match file.mode {
+        VaultMode::Password => "Wrong vault password".to_string(),
+        VaultMode::Machine => "Vault was created on another machine".to_string(),
+    })?;
+    Ok(key)
+}
+
+fn new_vault(mode: VaultMode, secret: &[u8]) -> Result<(VaultFile, VaultKey), String> {
+    let mut salt = [0u8; 16];
+    rand::thread_rng().fill_bytes(&mut salt);
+
+    let key = derive_key(secret, &salt)?;
+
+    let file = VaultFile {
+        version: 1,
+        mode,
+        salt: BASE64.encode(salt),
+        check: seal(&key, CHECK_PLAINTEXT, CHECK_AAD)?,
+        entries: BTreeMap::new(),
+    };
+    Ok((file, key))
+}
+
+/// The provider name is bound as associated data, so a sealed key cannot be
+/// moved to another provider's slot in the file.
+fn seal(key: &VaultKey, plaintext: &[u8], aad: &[u8]) -> Result<Sealed, String> {
+    let cipher = Aes256Gcm::new_from_slice(key.as_ref()).map_err(|e| e.to_string())?;
+    let mut nonce = [0u8; 12];
+    rand::thread_rng().fill_bytes(&mut nonce);
+
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce), Payload { msg: plaintext, aad })
+        .map_err(|_| "Failed to encrypt".to_string())?;
+
+    Ok(Sealed {
+        nonce: BASE64.encode(nonce),
+        ciphertext: BASE64.encode(ciphertext),
+    })
+}
+
+fn open(key: &VaultKey, sealed: &Sealed, aad: &[u8]) -> Result<Vec<u8>, String> {
+    let cipher = Aes256Gcm::new_from_slice(key.as_ref()).map_err(|e| e.to_string())?;
+    let nonce = BASE64.decode(&sealed.nonce).map_err(|_| "Vault file is corrupted".to_string())?;
+    let ciphertext = BASE64
+        .decode(&sealed.ciphertext)
+        .map_err(|_| "Vault file is corrupted".to_string())?;
+    if nonce.len() != 12 {
+        return Err("Vault file is corrupted".to_string());
+    }
+
+    cipher
+        .decrypt(Nonce::from_slice(&nonce), Payload { msg: &ciphertext, aad })
+        .map_err(|_| "Failed to decrypt vault entry".to_string())
+}
+
+/// Moves `<provider>_api_key.txt` files written by older versions into the
+/// vault and deletes the plaintext copies; `save` persists the vault file.
+fn migrate_legacy_keys(
+    app_dir: &Path,
+    file: &mut VaultFile,
+    key: &VaultKey,
+    save: impl Fn(&VaultFile) -> Result<(), String>,
+) -> Result<(), String> {
+    let Ok(entries) = fs::read_dir(app_dir) else {
+        return Ok(());
+    };
+
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        let Some(provider) = name.strip_suffix(LEGACY_KEY_SUFFIX) else {
+            continue;
+        };
+
+        let secret = Zeroizing::new(
+            fs::read_to_string(entry.path()).map_err(|e| format!("Failed to read {}: {}", name, e))?,
+        );
+        if !secret.trim().is_empty() && !file.entries.contains_key(provider) {
+            let sealed = seal(key, secret.trim().as_bytes(), provider.as_bytes())?;
+            file.entries.insert(provider.to_string(), sealed);
+        }
+        // Persist before deleting so a crash never loses a key
+        save(file)?;
+        fs::remove_file(entry.path()).map_err(|e| format!("Failed to remove {}: {}", name, e))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    #[test]
+    fn a_new_vault_reopens_with_the_same_secret() {
+        let (mut file, key) = new_vault(VaultMode::Password, b"hunter2").unwrap();
+        file.entries
+            .insert("openai".to_string(), seal(&key, b"sk-secret", b"openai").unwrap());
+
+        // Through the file format, as it would be after a restart
+        let file: VaultFile = serde_json::from_str(&serde_json::to_string(&file).unwrap()).unwrap();
+        let reopened = unlock_key(&file, b"hunter2").unwrap();
+        assert_eq!(*reopened, *key);
+        assert_eq!(open(&reopened, &file.entries["openai"], b"openai").unwrap(), b"sk-secret");
+    }
+
+    #[test]
+    fn derived_keys_depend_on_secret_and_salt() {
+        let key = derive_key(b"secret", b"salt-0123456789").unwrap();
+        assert_eq!(*derive_key(b"secret", b"salt-0123456789").unwrap(), *key);
+        assert_ne!(*derive_key(b"other", b"salt-0123456789").unwrap(), *key);
+        assert_ne!(*derive_key(b"secret", b"salt-9876543210").unwrap(), *key);
+    }
+
+    #[test]
+    fn sealed_entries_are_bound_to_key_and_provider() {
+        let key = Zeroizing::new([7u8; 32]);
+        let sealed = seal(&key, b"sk-secret", b"openai").unwrap();
+
+        assert_eq!(open(&key, &sealed, b"openai").unwrap(), b"sk-secret");
+        // A fresh nonce every time
+        assert_ne!(seal(&key, b"sk-secret", b"openai").unwrap().nonce, sealed.nonce);
+        // Moved to another provider's slot, or opened with another key
+        assert!(open(&key, &sealed, b"anthropic").is_err());
+        assert!(open(&Zeroizing::new([8u8; 32]), &sealed, b"openai").is_err());
+    }
+
+    #[test]
+    fn a_wrong_secret_says_why_the_vault_did_not_open() {
+        let (file, _) = new_vault(VaultMode::Password, b"hunter2").unwrap();
+        assert_eq!(unlock_key(&file, b"hunter3").unwrap_err(), "Wrong vault password");
+
+        let (file, _) = new_vault(VaultMode::Machine, b"machine-a").unwrap();
+        assert_eq!(
+            unlock_key(&file, b"machine-b").unwrap_err(),
+            "Vault was created on another machine"
+        );
+    }
+
+    #[test]
+    fn password_mode_requires_a_password() {
+        for password in [None, Some("")] {
+            assert_eq!(
+                secret_for(VaultMode::Password, password).unwrap_err(),
+                "Vault password required"
+            );
+        }
+    }
+
+    #[test]
+    fn a_missing_vault_file_is_not_an_error_but_a_corrupt_one_is() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(VAULT_FILE);
+        assert!(read_vault_file(&path).unwrap().is_none());
+
+        fs::write(&path, "{ not json").unwrap();
+        let error = read_vault_file(&path).unwrap_err();
+        assert!(error.contains("corrupted"), "{}", error);
+    }
+
+    #[test]
+    fn legacy_key_files_move_into_the_vault() {
+        let dir = tempfile::tempdir().unwrap();
+        let (mut file, key) = new_vault(VaultMode::Password, b"hunter2").unwrap();
+        file.entries
+            .insert("gemini".to_string(), seal(&key, b"vault-key", b"gemini").unwrap());
+
+        fs::write(dir.path().join("openai_api_key.txt"), " sk-legacy \n").unwrap();
+        fs::write(dir.path().join("gemini_api_key.txt"), "old-key").unwrap();
+        fs::write(dir.path().join("mistral_api_key.txt"), "  ").unwrap();
+        fs::write(dir.path().join("notes.txt"), "keep me").unwrap();
+
+        let saves = Cell::new(0);
+        migrate_legacy_keys(dir.path(), &mut file, &key, |_| {
+            saves.set(saves.get() + 1);
+            Ok(())
+        })
+        .unwrap();
+
+        assert_eq!(saves.get(), 3);
+        assert_eq!(open(&key, &file.entries["openai"], b"openai").unwrap(), b"sk-legacy");
+        // Keys already in the vault win; empty files are dropped
+        assert_eq!(open(&key, &file.entries["gemini"], b"gemini").unwrap(), b"vault-key");
+        assert!(!file.entries.contains_key("mistral"));
+
+        let mut left: Vec<String> = fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        left.sort();
+        assert_eq!(left, ["notes.txt"]);
+    }
+
+    #[test]
+    fn legacy_files_stay_when_the_vault_cannot_be_saved() {
+        let dir = tempfile::tempdir().unwrap();
+        let (mut file, key) = new_vault(VaultMode::Password, b"hunter2").unwrap();
+        fs::write(dir.path().join("openai_api_key.txt"), "sk-legacy").unwrap();
+
+        let result = migrate_legacy_keys(dir.path(), &mut file, &key, |_| Err("disk full".to_string()));
+
+        assert_eq!(result.unwrap_err(), "disk full");
+        assert!(dir.path().join("openai_api_key.txt").exists());
+    }
+}
@@ -1,8 +1,10 @@
 import React, { useState, useRef, useEffect } from 'react';
-import { FiSend, FiZap, FiCheckCircle } from 'react-icons/fi';
+import { FiSend, FiZap, FiCheckCircle, FiSquare, FiX, FiClock, FiRefreshCw } from 'react-icons/fi';
 import useStore from '../store/useStore';
-import { callAI } from '../utils/aiClient';
-import { runMOA, isMOAConfigured, MOA_STRATEGIES } from '../utils/moa/moaEngine';
+import {
+  streamAI, runAgent, indexProject, awaitMOARun, cancelAIRequest, isCancelled, describeAIError,
+} from '../utils/aiClient';
+import { runMOA, isMOAConfigured, toStrategyResult, MOA_STRATEGIES } from '../utils/moa/moaEngine';
 import { OPENROUTER_MODELS } from '../utils/providers/openrouter';
 import { GEMINI_MODELS } from '../utils/providers/gemini';
 import { MISTRAL_MODELS } from '../utils/providers/mistral';
@@ -10,12 +12,42 @@ import { COHERE_MODELS } from '../utils/providers/cohere';
 import { DEFAULT_OLLAMA_MODELS } from '../utils/providers/ollama';
 import { BUILTIN_SKILLS, getSkillById } from '../utils/skills/builtinSkills';
 import { loadCustomSkills } from '../utils/skills/skillsStorage';
+import {
+  listEndpointProfiles,
+  listModels,
+  createConversation,
+  loadConversation,
+  appendConversationMessages,
+  openProjectIndex,
+  semanticSearch,
+  getRateLimitStatus,
+} from '../utils/tauriCommands';
+import ConversationHistory from './ConversationHistory';
 import './AIAssistant.css';
 
+// MOA run still in flight, so a reopened or reloaded panel can collect it
+const PENDING_MOA_KEY = 'moa-pending-run';
+
+const loadPendingMOA = () => {
+  try {
+    return JSON.parse(localStorage.getItem(PENDING_MOA_KEY));
+  } catch {
+    return null;
+  }
+};
+
+// True for the first caller only, so a run finished by both the panel that
+// started it and a reopened one is saved once
+const claimPendingMOA = (runId) => {
+  if (loadPendingMOA()?.runId !== runId) return false;
+  localStorage.removeItem(PENDING_MOA_KEY);
+  return true;
+};
+
 const AIAssistant = () => {
   const { 
     openaiKey, claudeKey, openrouterKey, geminiKey, mistralKey, cohereKey, ollamaUrl,
-    toggleSettings, currentFileContent,
+    toggleSettings, currentFileContent, workspaceRoot,
     moaEnabled, moaConfig, setMOAEnabled
   } = useStore();
 
@@ -25,10 +57,30 @@ const AIAssistant = () => {
   const [input, setInput] = useState('');
   const [isLoading, setIsLoading] = useState(false);
   const [error, setError] = useState(null);
+  // Informational line, e.g. when older messages were trimmed to fit the model
+  const [notice, setNotice] = useState(null);
   const messagesEndRef = useRef(null);
+  const activeRequestRef = useRef(null);
   const [skills, setSkills] = useState([]);
   const [showSkillsMenu, setShowSkillsMenu] = useState(false);
   const [useMOA, setUseMOA] = useState(false);
+  const [useAgent, setUseAgent] = useState(false);
+  // Skill whose prompt is sent as the system prompt until cleared
+  const [activeSkill, setActiveSkill] = useState(null);
+  // Pasted images as data URLs, sent with the next message
+  const [attachments, setAttachments] = useState([]);
+  // Custom OpenAI-compatible endpoints, selectable as `custom:<id>`
+  const [customEndpoints, setCustomEndpoints] = useState([]);
+  const [customModels, setCustomModels] = useState([]);
+  // Saved conversation the current messages belong to; created on first send
+  const [conversationId, setConversationId] = useState(null);
+  const [showHistory, setShowHistory] = useState(false);
+  const [historyVersion, setHistoryVersion] = useState(0);
+  const [useProjectContext, setUseProjectContext] = useState(false);
+  const [indexStatus, setIndexStatus] = useState(null);
+  const [indexProgress, setIndexProgress] = useState(null);
+  // Requests held back by the per-provider rate limits while one is running
+  const [queuedRequests, setQueuedRequests] = useState(0);
 
   const scrollToBottom = () => {
     messagesEndRef.current?.scrollIntoView({ behavior: 'smooth' });
@@ -41,6 +93,63 @@ const AIAssistant = () => {
   useEffect(() => {
     const customSkills = loadCustomSkills();
     setSkills([...BUILTIN_SKILLS, ...customSkills]);
+    listEndpointProfiles()
+      .then(setCustomEndpoints)
+      .catch(() => setCustomEndpoints([]));
+  }, []);
+
+  // Reuse the saved index of the opened folder, if it has one
+  useEffect(() => {
+    setIndexStatus(null);
+    if (!workspaceRoot) return;
+    openProjectIndex(workspaceRoot)
+      .then(setIndexStatus)
+      .catch(() => setIndexStatus(null));
+  }, [workspaceRoot]);
+
+  // Show when a request is waiting in a rate-limit queue rather than stuck
+  useEffect(() => {
+    setQueuedRequests(0);
+    if (!isLoading || window.__TAURI_IPC__ === undefined) return;
+    const timer = setInterval(() => {
+      getRateLimitStatus()
+        .then((status) => setQueuedRequests(status.reduce((total, s) => total + s.queued, 0)))
+        .catch(() => setQueuedRequests(0));
+    }, 1000);
+    return () => clearInterval(timer);
+  }, [isLoading]);
+
+  // Pick up a MOA run that was still going when the panel closed or reloaded
+  useEffect(() => {
+    const pending = loadPendingMOA();
+    if (!pending) return;
+
+    setMessages((prev) => [...prev, { role: 'user', content: pending.prompt }]);
+    activeRequestRef.current = pending.runId;
+    setIsLoading(true);
+    awaitMOARun(pending.runId)
+      .then(async (result) => {
+        if (!result) return;
+        const moaResult = toStrategyResult(result);
+        const content = formatMOAResponse(moaResult, pending.strategy);
+        setMessages((prev) => [...prev, { role: 'assistant', content, moaResult, isMOA: true }]);
+        if (claimPendingMOA(pending.runId)) {
+          await saveTurn(
+            [{ role: 'user', content: pending.prompt }, { role: 'assistant', content }],
+            { provider: 'moa', model: pending.strategy }
+          );
+        }
+      })
+      .catch((err) => {
+        if (!isCancelled(err)) {
+          setError('Failed to get response: ' + describeAIError(err));
+        }
+      })
+      .finally(() => {
+        claimPendingMOA(pending.runId);
+        activeRequestRef.current = null;
+        setIsLoading(false);
+      });
   }, []);
 
   // Model options for each provider
@@ -69,7 +178,7 @@ const AIAssistant = () => {
       case 'ollama':
         return DEFAULT_OLLAMA_MODELS;
       default:
-        return [];
+        return customModels;
     }
   };
 
@@ -91,7 +200,8 @@ const AIAssistant = () => {
       case 'ollama':
         return true; // Ollama doesn't need an API key
       default:
-        return null;
+        // Custom endpoints use their saved key, if the server needs one
+        return provider.startsWith('custom:');
     }
   };
 
@@ -108,8 +218,24 @@ const AIAssistant = () => {
       cohere: 'command',
       ollama: 'llama2',
     };
-    setModel(defaultModels[provider] || '');
-  }, [provider]);
+    if (!provider.startsWith('custom:')) {
+      setModel(defaultModels[provider] || '');
+      return;
+    }
+
+    const endpoint = customEndpoints.find((e) => `custom:${e.id}` === provider);
+    setModel(endpoint?.default_model || '');
+    listModels(provider)
+      .then((catalog) => {
+        setCustomModels(catalog.models);
+        if (!endpoint?.default_model && catalog.models.length) {
+          setModel(catalog.models[0].id);
+        }
+      })
+      .catch(() => setCustomModels(endpoint?.default_model
+        ? [{ id: endpoint.default_model, name: endpoint.default_model }]
+        : []));
+  }, [provider, customEndpoints]);
 
   const handleSend = async () => {
     if (!input.trim()) return;
@@ -119,57 +245,245 @@ const AIAssistant = () => {
     
     if (!shouldUseMOA && !hasApiKey) return;
 
+    // With images attached the content becomes a list of parts
     const userMessage = {
       role: 'user',
-      content: input.trim(),
+      content: attachments.length && !shouldUseMOA
+        ? [
+            { type: 'text', text: input.trim() },
+            ...attachments.map((data) => ({ type: 'image', data })),
+          ]
+        : input.trim(),
     };
 
     setMessages((prev) => [...prev, userMessage]);
     setInput('');
+    setAttachments([]);
     setIsLoading(true);
     setError(null);
+    setNotice(null);
 
     try {
       if (shouldUseMOA) {
-        // Use MOA
-        const moaResult = await runMOA(input.trim(), moaConfig);
-        
-        const assistantMessage = {
-          role: 'assistant',
-          content: formatMOAResponse(moaResult, moaConfig.strategy),
-          moaResult,
-          isMOA: true,
-        };
-
-        setMessages((prev) => [...prev, assistantMessage]);
+        const moaResult = await handleMOARun(input.trim());
+        const content = formatMOAResponse(moaResult.result, moaConfig.strategy);
+
+        setMessages((prev) => [...prev, { role: 'assistant', content, moaResult: moaResult.result, isMOA: true }]);
+        if (moaResult.owned) {
+          await saveTurn(
+            [userMessage, { role: 'assistant', content }],
+            { provider: 'moa', model: moaConfig.strategy }
+          );
+        }
+      } else if (useAgent && workspaceRoot) {
+        const result = await handleAgentRun([...chatHistory(messages), userMessage]);
+        await saveTurn([userMessage, { role: 'assistant', content: result.content }], { usage: result.usage });
       } else {
         // Use regular AI
-        const chatMessages = [...messages, userMessage];
-        const currentApiKey = getCurrentApiKey();
-        
-        const response = await callAI(
+        const chatMessages = [...chatHistory(messages), userMessage];
+        const requestId = crypto.randomUUID();
+        activeRequestRef.current = requestId;
+
+        // Append an empty reply and grow it as deltas stream in
+        setMessages((prev) => [...prev, { role: 'assistant', content: '' }]);
+        const appendToReply = (delta) => {
+          setMessages((prev) => {
+            const last = prev[prev.length - 1];
+            return [...prev.slice(0, -1), { ...last, content: last.content + delta }];
+          });
+        };
+
+        // The backend supplies the saved key for the provider
+        const summary = await streamAI(
           provider,
-          currentApiKey,
+          null,
           model,
           chatMessages,
-          provider === 'ollama' ? ollamaUrl : null
+          provider === 'ollama' ? ollamaUrl : null,
+          appendToReply,
+          requestId,
+          generationOptions(await projectContext(input.trim()))
         );
-
-        const assistantMessage = {
-          role: 'assistant',
-          content: response,
-        };
-
-        setMessages((prev) => [...prev, assistantMessage]);
+        if (summary.meta?.context) {
+          const {
+            dropped_messages: dropped,
+            summarized_messages: summarized,
+            summary_error: summaryError,
+          } = summary.meta.context;
+          setNotice(summarized
+            ? `Conversation too long for ${model}: ${summarized} earlier messages were summarized.`
+            : `Conversation too long for ${model}: ${dropped} earlier messages were left out`
+              + (summaryError ? ` (summary failed: ${describeAIError(summaryError)}).` : '.'));
+        }
+        await saveTurn([userMessage, { role: 'assistant', content: summary.content }], { usage: summary.usage });
       }
     } catch (err) {
-      console.error('AI API Error:', err);
-      setError('Failed to get response: ' + err.toString());
+      // Drop the placeholder reply if the stream failed before any text arrived
+      setMessages((prev) => {
+        const last = prev[prev.length - 1];
+        return last?.role === 'assistant' && !last.content ? prev.slice(0, -1) : prev;
+      });
+      if (!isCancelled(err)) {
+        console.error('AI API Error:', err);
+        setError('Failed to get response: ' + describeAIError(err));
+      }
     } finally {
+      activeRequestRef.current = null;
       setIsLoading(false);
     }
   };
 
+  // Stores a completed exchange; a failure here must not interrupt the chat
+  const saveTurn = async (turn, { provider: turnProvider = provider, model: turnModel = model, usage = null } = {}) => {
+    try {
+      let id = conversationId;
+      if (!id) {
+        id = (await createConversation(turnProvider, turnModel)).id;
+        setConversationId(id);
+      }
+      await appendConversationMessages(id, turn, { provider: turnProvider, model: turnModel, usage });
+      setHistoryVersion((v) => v + 1);
+    } catch (err) {
+      console.error('Failed to save conversation:', err);
+    }
+  };
+
+  const handleSelectConversation = async (id) => {
+    try {
+      const conversation = await loadConversation(id);
+      setMessages(conversation.messages.map(({ role, content }) => ({ role, content })));
+      setConversationId(id);
+      setError(null);
+      setShowHistory(false);
+    } catch (err) {
+      setError(`Failed to load conversation: ${err}`);
+    }
+  };
+
+  const handleNewConversation = () => {
+    setMessages([]);
+    setConversationId(null);
+    setError(null);
+  };
+
+  // Agent transcript entries are shown but not sent back as chat history;
+  // the backend keeps the tool round-trips for the duration of a run.
+  const chatHistory = (msgs) => msgs.filter((msg) => !msg.isAgentStep);
+
+  const generationOptions = (context = null) => {
+    const system = [activeSkill?.systemPrompt, context].filter(Boolean).join('\n\n');
+    return system ? { system } : {};
+  };
+
+  // Snippets of the indexed project related to the question; retrieval
+  // failures fall back to a plain chat
+  const projectContext = async (question) => {
+    if (!useProjectContext || !indexStatus) return null;
+    try {
+      const results = await semanticSearch(question, 5);
+      if (!results.length) return null;
+      const snippets = results.map((result) =>
+        `${result.path} (lines ${result.start_line}-${result.end_line}):\n\`\`\`\n${result.text}\n\`\`\``);
+      return `Excerpts from the user's project that may be relevant:\n\n${snippets.join('\n\n')}`;
+    } catch (err) {
+      console.error('Failed to retrieve project context:', err);
+      return null;
+    }
+  };
+
+  // Embeds with the provider the index was built with, or the current one
+  const handleIndexProject = async () => {
+    const indexProvider = indexStatus?.provider || provider;
+    setError(null);
+    setIndexProgress({ embedded: 0, total: 0 });
+    try {
+      const status = await indexProject(workspaceRoot, indexProvider, {
+        model: indexStatus?.provider === indexProvider ? indexStatus.model : null,
+        onProgress: setIndexProgress,
+      });
+      setIndexStatus(status);
+    } catch (err) {
+      if (!isCancelled(err)) {
+        setError('Failed to index project: ' + describeAIError(err));
+      }
+    } finally {
+      setIndexProgress(null);
+    }
+  };
+
+  // The run lives in the backend; if this panel closes first, the reopened
+  // one collects the result. `owned` is false when that already happened.
+  const handleMOARun = async (prompt) => {
+    const runId = crypto.randomUUID();
+    activeRequestRef.current = runId;
+    localStorage.setItem(PENDING_MOA_KEY, JSON.stringify({ runId, prompt, strategy: moaConfig.strategy }));
+
+    const reportMember = (member) => {
+      const who = member.role || `${member.provider} - ${member.model}`;
+      setNotice(member.response != null
+        ? `MOA: ${who} answered in ${(member.elapsed_ms / 1000).toFixed(1)}s`
+        : `MOA: ${who} failed: ${describeAIError(member.error)}`);
+    };
+
+    try {
+      const result = await runMOA(prompt, moaConfig, { runId, onMember: reportMember });
+      setNotice(null);
+      return { result, owned: claimPendingMOA(runId) };
+    } catch (err) {
+      claimPendingMOA(runId);
+      throw err;
+    }
+  };
+
+  const handleAgentRun = async (chatMessages) => {
+    const runId = crypto.randomUUID();
+    activeRequestRef.current = runId;
+
+    const addStep = (step) => {
+      if (step.type === 'assistant') {
+        const calls = step.tool_calls.map((call) => `→ ${call.name}(${JSON.stringify(call.arguments)})`);
+        setMessages((prev) => [
+          ...prev,
+          {
+            role: 'assistant',
+            content: [step.content, ...calls].filter(Boolean).join('\n'),
+            isAgentStep: step.tool_calls.length > 0,
+          },
+        ]);
+      } else {
+        setMessages((prev) => [
+          ...prev,
+          { role: step.is_error ? 'tool error' : 'tool', content: step.output, isAgentStep: true },
+        ]);
+      }
+    };
+
+    const confirmToolCall = async (request) => {
+      const detail = request.name === 'write_file'
+        ? `write ${request.arguments.path}`
+        : `run "${request.arguments.command}"`;
+      return window.confirm(`The assistant wants to ${detail} in ${workspaceRoot}. Allow?`);
+    };
+
+    const result = await runAgent(provider, model, chatMessages, workspaceRoot, {
+      baseUrl: provider === 'ollama' ? ollamaUrl : null,
+      options: generationOptions(),
+      onStep: addStep,
+      onApproval: confirmToolCall,
+      runId,
+    });
+    if (!result.finished) {
+      setError('The agent stopped after reaching its step limit.');
+    }
+    return result;
+  };
+
+  const handleStop = async () => {
+    if (activeRequestRef.current) {
+      await cancelAIRequest(activeRequestRef.current);
+    }
+  };
+
   const formatMOAResponse = (result, strategy) => {
     if (strategy === MOA_STRATEGIES.VOTING) {
       if (result.aggregatedResponse) {
@@ -230,17 +544,40 @@ const AIAssistant = () => {
   };
 
   const handleUseSkill = (skill) => {
-    let prompt = skill.systemPrompt + '\n\n';
+    // The skill prompt goes out as the system prompt; the input only
+    // carries the context it asks for
+    setActiveSkill(skill);
     
     // Add current file content if required
     if (skill.requiredContext && skill.requiredContext.includes('currentFile') && currentFileContent) {
-      prompt += `Code to analyze:\n\`\`\`\n${currentFileContent}\n\`\`\``;
+      setInput(`Code to analyze:\n\`\`\`\n${currentFileContent}\n\`\`\``);
     }
     
-    setInput(prompt);
     setShowSkillsMenu(false);
   };
 
+  const handlePaste = (e) => {
+    const images = Array.from(e.clipboardData?.items || [])
+      .filter((item) => item.type.startsWith('image/'))
+      .map((item) => item.getAsFile());
+    if (!images.length) return;
+
+    e.preventDefault();
+    images.forEach((file) => {
+      const reader = new FileReader();
+      reader.onload = () => setAttachments((prev) => [...prev, reader.result]);
+      reader.readAsDataURL(file);
+    });
+  };
+
+  const messageText = (content) =>
+    Array.isArray(content)
+      ? content.filter((part) => part.type === 'text').map((part) => part.text).join('\n')
+      : content;
+
+  const messageImages = (content) =>
+    Array.isArray(content) ? content.filter((part) => part.type === 'image').map((part) => part.data) : [];
+
   const handleKeyPress = (e) => {
     if (e.key === 'Enter' && !e.shiftKey) {
       e.preventDefault();
@@ -293,7 +630,23 @@ const AIAssistant = () => {
         <h2>
           <FiZap />
           AI Assistant
+          <button
+            className={`history-toggle ${showHistory ? 'active' : ''}`}
+            onClick={() => setShowHistory(!showHistory)}
+            title="Conversation history"
+          >
+            <FiClock />
+          </button>
         </h2>
+
+        {showHistory && (
+          <ConversationHistory
+            activeId={conversationId}
+            onSelect={handleSelectConversation}
+            onNew={handleNewConversation}
+            refreshKey={historyVersion}
+          />
+        )}
         
         {/* MOA Toggle */}
         {canUseMOA && (
@@ -310,6 +663,52 @@ const AIAssistant = () => {
           </div>
         )}
 
+        {/* Agent Toggle */}
+        {workspaceRoot && !(useMOA && canUseMOA) && (
+          <div className="moa-toggle-wrapper">
+            <label className="moa-toggle-label" title="Let the assistant read files and, with your approval, write files and run commands">
+              <input
+                type="checkbox"
+                checked={useAgent}
+                onChange={(e) => setUseAgent(e.target.checked)}
+                className="moa-checkbox"
+              />
+              <span className="moa-toggle-text">Agent Mode</span>
+            </label>
+          </div>
+        )}
+
+        {/* Project Context Toggle */}
+        {workspaceRoot && !(useMOA && canUseMOA) && (
+          <div className="moa-toggle-wrapper project-context">
+            <label className="moa-toggle-label" title="Add the most relevant snippets of the indexed project to each question">
+              <input
+                type="checkbox"
+                checked={useProjectContext}
+                onChange={(e) => setUseProjectContext(e.target.checked)}
+                disabled={!indexStatus}
+                className="moa-checkbox"
+              />
+              <span className="moa-toggle-text">Project Context</span>
+            </label>
+            <span className="index-status">
+              {indexProgress
+                ? `Indexing ${indexProgress.embedded}/${indexProgress.total}`
+                : indexStatus
+                  ? `${indexStatus.files} files · ${indexStatus.chunks} chunks`
+                  : 'Not indexed'}
+            </span>
+            <button
+              className="history-toggle"
+              onClick={handleIndexProject}
+              disabled={!!indexProgress}
+              title={indexStatus ? `Re-index with ${indexStatus.provider}/${indexStatus.model}` : `Index with ${provider}`}
+            >
+              <FiRefreshCw />
+            </button>
+          </div>
+        )}
+
         {useMOA && canUseMOA ? (
           <div className="moa-active-indicator">
             <FiCheckCircle />
@@ -329,6 +728,9 @@ const AIAssistant = () => {
               <option value="mistral" disabled={!mistralKey}>Mistral AI</option>
               <option value="cohere" disabled={!cohereKey}>Cohere</option>
               <option value="ollama">Ollama (Local)</option>
+              {customEndpoints.map((endpoint) => (
+                <option key={endpoint.id} value={`custom:${endpoint.id}`}>{endpoint.name}</option>
+              ))}
             </select>
             <select 
               className="model-select"
@@ -359,7 +761,12 @@ const AIAssistant = () => {
               {msg.role}
               {msg.isMOA && <span className="moa-badge">MOA</span>}
             </div>
-            <div className="message-content">{msg.content}</div>
+            <div className="message-content">
+              {messageText(msg.content)}
+              {messageImages(msg.content).map((src, i) => (
+                <img key={i} className="message-image" src={src} alt="Attachment" />
+              ))}
+            </div>
           </div>
         ))}
 
@@ -371,6 +778,18 @@ const AIAssistant = () => {
           </div>
         )}
 
+        {isLoading && queuedRequests > 0 && (
+          <div className="notice-message">
+            {queuedRequests === 1 ? '1 request' : `${queuedRequests} requests`} waiting for a rate limit
+          </div>
+        )}
+
+        {notice && (
+          <div className="notice-message">
+            {notice}
+          </div>
+        )}
+
         {error && (
           <div className="error-message">
             {error}
@@ -420,6 +839,13 @@ const AIAssistant = () => {
           </button>
         </div>
         
+        {activeSkill && (
+          <div className="active-skill">
+            <span>{activeSkill.icon || '⚡'} {activeSkill.name}</span>
+            <button onClick={() => setActiveSkill(null)} title="Stop using this skill">×</button>
+          </div>
+        )}
+
         {showSkillsMenu && (
           <div className="skills-dropdown">
             {skills.slice(0, 9).map((skill) => (
@@ -437,21 +863,43 @@ const AIAssistant = () => {
       </div>
 
       <div className="ai-input-area">
+        {attachments.length > 0 && (
+          <div className="ai-attachments">
+            {attachments.map((src, i) => (
+              <div key={i} className="ai-attachment">
+                <img src={src} alt="Attachment" />
+                <button
+                  onClick={() => setAttachments((prev) => prev.filter((_, j) => j !== i))}
+                  title="Remove image"
+                >
+                  <FiX />
+                </button>
+              </div>
+            ))}
+          </div>
+        )}
         <div className="ai-input-wrapper">
           <textarea
             className="ai-input"
             value={input}
             onChange={(e) => setInput(e.target.value)}
             onKeyPress={handleKeyPress}
-            placeholder="Ask me anything..."
+            onPaste={handlePaste}
+            placeholder="Ask me anything... (paste a screenshot to attach it)"
           />
-          <button
-            className="send-btn"
-            onClick={handleSend}
-            disabled={!input.trim() || isLoading || (!hasApiKey && !useMOA)}
-          >
-            <FiSend />
-          </button>
+          {isLoading && activeRequestRef.current ? (
+            <button className="send-btn" onClick={handleStop} title="Stop generating">
+              <FiSquare />
+            </button>
+          ) : (
+            <button
+              className="send-btn"
+              onClick={handleSend}
+              disabled={!input.trim() || isLoading || (!hasApiKey && !useMOA)}
+            >
+              <FiSend />
+            </button>
+          )}
         </div>
       </div>
     </div>
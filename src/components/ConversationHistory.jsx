@@ -0,0 +1,137 @@
+import React, { useState, useEffect } from 'react';
+import { FiPlus, FiEdit2, FiTrash2, FiDownload } from 'react-icons/fi';
+import { save } from '@tauri-apps/api/dialog';
+import {
+  listConversations,
+  searchConversations,
+  renameConversation,
+  deleteConversation,
+  exportConversation,
+} from '../utils/tauriCommands';
+import './ConversationHistory.css';
+
+const formatDate = (secs) => new Date(secs * 1000).toLocaleString();
+
+// Saved conversations with search; picking one hands its id to onSelect
+const ConversationHistory = ({ activeId, onSelect, onNew, refreshKey }) => {
+  const [conversations, setConversations] = useState([]);
+  const [query, setQuery] = useState('');
+  const [hits, setHits] = useState(null);
+
+  useEffect(() => {
+    listConversations()
+      .then(setConversations)
+      .catch(() => setConversations([]));
+  }, [refreshKey]);
+
+  useEffect(() => {
+    if (!query.trim()) {
+      setHits(null);
+      return;
+    }
+    const timer = setTimeout(() => {
+      searchConversations(query)
+        .then(setHits)
+        .catch(() => setHits([]));
+    }, 250);
+    return () => clearTimeout(timer);
+  }, [query, refreshKey]);
+
+  const reload = async () => {
+    setConversations(await listConversations());
+  };
+
+  const handleRename = async (conversation) => {
+    const title = window.prompt('Rename conversation', conversation.title);
+    if (!title?.trim()) return;
+    try {
+      await renameConversation(conversation.id, title);
+      await reload();
+    } catch (error) {
+      alert(`Failed to rename conversation: ${error}`);
+    }
+  };
+
+  const handleDelete = async (conversation) => {
+    if (!window.confirm(`Delete "${conversation.title}"?`)) return;
+    try {
+      await deleteConversation(conversation.id);
+      if (conversation.id === activeId) onNew();
+      await reload();
+    } catch (error) {
+      alert(`Failed to delete conversation: ${error}`);
+    }
+  };
+
+  const handleExport = async (conversation) => {
+    const path = await save({
+      defaultPath: `${conversation.title.replace(/[\\/:*?"<>|]/g, '_')}.md`,
+      filters: [
+        { name: 'Markdown', extensions: ['md'] },
+        { name: 'JSON', extensions: ['json'] },
+      ],
+    });
+    if (!path) return;
+    try {
+      await exportConversation(conversation.id, path.endsWith('.json') ? 'json' : 'markdown', path);
+    } catch (error) {
+      alert(`Failed to export conversation: ${error}`);
+    }
+  };
+
+  const entries = hits
+    ? hits.map((hit) => ({ ...hit.conversation, snippet: hit.snippet }))
+    : conversations;
+
+  return (
+    <div className="conversation-history">
+      <div className="conversation-history-bar">
+        <input
+          type="text"
+          value={query}
+          onChange={(e) => setQuery(e.target.value)}
+          placeholder="Search conversations..."
+        />
+        <button className="conversation-btn" onClick={onNew} title="New conversation">
+          <FiPlus />
+        </button>
+      </div>
+
+      <div className="conversation-list">
+        {entries.length === 0 && (
+          <div className="conversation-empty">
+            {hits ? 'No matches' : 'No saved conversations yet'}
+          </div>
+        )}
+        {entries.map((conversation) => (
+          <div
+            key={conversation.id}
+            className={`conversation-item ${conversation.id === activeId ? 'active' : ''}`}
+            onClick={() => onSelect(conversation.id)}
+          >
+            <div className="conversation-info">
+              <span className="conversation-title">{conversation.title}</span>
+              <span className="conversation-meta">
+                {conversation.snippet
+                  || `${conversation.model} · ${conversation.message_count} messages · ${formatDate(conversation.updated_at)}`}
+              </span>
+            </div>
+            <div className="conversation-actions" onClick={(e) => e.stopPropagation()}>
+              <button className="conversation-btn" onClick={() => handleRename(conversation)} title="Rename">
+                <FiEdit2 />
+              </button>
+              <button className="conversation-btn" onClick={() => handleExport(conversation)} title="Export">
+                <FiDownload />
+              </button>
+              <button className="conversation-btn" onClick={() => handleDelete(conversation)} title="Delete">
+                <FiTrash2 />
+              </button>
+            </div>
+          </div>
+        ))}
+      </div>
+    </div>
+  );
+};
+
+export default ConversationHistory;
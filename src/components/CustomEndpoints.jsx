@@ -0,0 +1,206 @@
+import React, { useState, useEffect } from 'react';
+import { FiServer, FiTrash2, FiEdit2, FiCheck, FiAlertCircle } from 'react-icons/fi';
+import {
+  listEndpointProfiles,
+  saveEndpointProfile,
+  deleteEndpointProfile,
+  saveAPIKey,
+  validateProviderKey,
+} from '../utils/tauriCommands';
+import { describeAIError } from '../utils/aiClient';
+
+const EMPTY_FORM = { id: '', name: '', base_url: '', default_model: '', headers: '', key: '' };
+
+// "Name: value" per line <-> { Name: value }
+const parseHeaders = (text) => Object.fromEntries(
+  text
+    .split('\n')
+    .map((line) => line.split(':'))
+    .filter(([name, ...rest]) => name.trim() && rest.length)
+    .map(([name, ...rest]) => [name.trim(), rest.join(':').trim()])
+);
+
+const formatHeaders = (headers) => Object.entries(headers || {})
+  .map(([name, value]) => `${name}: ${value}`)
+  .join('\n');
+
+// Self-hosted OpenAI-compatible servers, each usable as provider `custom:<id>`
+const CustomEndpoints = () => {
+  const [profiles, setProfiles] = useState([]);
+  const [form, setForm] = useState(EMPTY_FORM);
+  const [status, setStatus] = useState(null);
+  const [testing, setTesting] = useState(null);
+
+  useEffect(() => {
+    loadProfiles();
+  }, []);
+
+  const loadProfiles = async () => {
+    try {
+      setProfiles(await listEndpointProfiles());
+    } catch (error) {
+      setProfiles([]);
+    }
+  };
+
+  const updateForm = (field, value) => {
+    setForm((prev) => ({ ...prev, [field]: value }));
+  };
+
+  const handleEdit = (profile) => {
+    setForm({
+      id: profile.id,
+      name: profile.name,
+      base_url: profile.base_url,
+      default_model: profile.default_model || '',
+      headers: formatHeaders(profile.headers),
+      key: '',
+    });
+    setStatus(null);
+  };
+
+  const handleSave = async () => {
+    try {
+      const saved = await saveEndpointProfile({
+        id: form.id,
+        name: form.name,
+        base_url: form.base_url,
+        default_model: form.default_model.trim() || null,
+        headers: parseHeaders(form.headers),
+      });
+      if (form.key.trim()) {
+        await saveAPIKey(`custom:${saved.id}`, form.key.trim());
+      }
+      setForm(EMPTY_FORM);
+      setStatus({ success: true, message: `Saved ${saved.name}` });
+      await loadProfiles();
+    } catch (error) {
+      setStatus({ success: false, message: String(error) });
+    }
+  };
+
+  const handleDelete = async (profile) => {
+    if (!window.confirm(`Delete custom endpoint "${profile.name}"?`)) return;
+    try {
+      await deleteEndpointProfile(profile.id);
+      if (form.id === profile.id) setForm(EMPTY_FORM);
+      await loadProfiles();
+    } catch (error) {
+      setStatus({ success: false, message: String(error) });
+    }
+  };
+
+  const handleTest = async (profile) => {
+    setTesting(profile.id);
+    const result = await validateProviderKey(`custom:${profile.id}`);
+    setStatus(result.valid
+      ? { success: true, message: `${profile.name}: ${result.models.length} models available.` }
+      : { success: false, message: `${profile.name}: ${describeAIError(result.error)}` });
+    setTesting(null);
+  };
+
+  return (
+    <div className="settings-section">
+      <h3><FiServer /> Custom Endpoints</h3>
+      <p className="provider-info">
+        OpenAI-compatible servers such as vLLM, LM Studio, llama.cpp or LocalAI
+      </p>
+
+      {profiles.map((profile) => (
+        <div key={profile.id} className="custom-endpoint">
+          <div className="custom-endpoint-info">
+            <strong>{profile.name}</strong>
+            <span>{profile.base_url}</span>
+          </div>
+          <button
+            className="test-connection-btn"
+            onClick={() => handleTest(profile)}
+            disabled={testing === profile.id}
+          >
+            {testing === profile.id ? 'Testing...' : 'Test'}
+          </button>
+          <button className="custom-endpoint-btn" onClick={() => handleEdit(profile)} title="Edit">
+            <FiEdit2 />
+          </button>
+          <button className="custom-endpoint-btn" onClick={() => handleDelete(profile)} title="Delete">
+            <FiTrash2 />
+          </button>
+        </div>
+      ))}
+
+      <div className="form-group">
+        <label>Name</label>
+        <input
+          type="text"
+          value={form.name}
+          onChange={(e) => updateForm('name', e.target.value)}
+          placeholder="LM Studio"
+        />
+      </div>
+
+      <div className="form-group">
+        <label>Base URL</label>
+        <input
+          type="text"
+          value={form.base_url}
+          onChange={(e) => updateForm('base_url', e.target.value)}
+          placeholder="http://localhost:1234/v1"
+        />
+      </div>
+
+      <div className="form-group">
+        <label>API Key (Optional)</label>
+        <input
+          type="password"
+          value={form.key}
+          onChange={(e) => updateForm('key', e.target.value)}
+          placeholder={form.id ? 'Leave empty to keep the saved key' : 'Only if the server requires one'}
+        />
+      </div>
+
+      <div className="form-group">
+        <label>Default Model (Optional)</label>
+        <input
+          type="text"
+          value={form.default_model}
+          onChange={(e) => updateForm('default_model', e.target.value)}
+          placeholder="llama-3-8b-instruct"
+        />
+      </div>
+
+      <div className="form-group">
+        <label>Extra Headers (Optional)</label>
+        <textarea
+          value={form.headers}
+          onChange={(e) => updateForm('headers', e.target.value)}
+          placeholder="X-Api-Version: 2"
+          rows={3}
+        />
+      </div>
+
+      <div className="input-wrapper">
+        <button
+          className="test-connection-btn"
+          onClick={handleSave}
+          disabled={!form.name.trim() || !form.base_url.trim()}
+        >
+          {form.id ? 'Update Endpoint' : 'Add Endpoint'}
+        </button>
+        {form.id && (
+          <button className="test-connection-btn" onClick={() => setForm(EMPTY_FORM)}>
+            Cancel
+          </button>
+        )}
+      </div>
+
+      {status && (
+        <div className={`connection-status ${status.success ? 'success' : 'error'}`}>
+          {status.success ? <FiCheck /> : <FiAlertCircle />}
+          {status.message}
+        </div>
+      )}
+    </div>
+  );
+};
+
+export default CustomEndpoints;
@@ -1,11 +1,21 @@
-import React from 'react';
-import { FiFolder, FiGitCommit, FiCode, FiZap, FiPlus } from 'react-icons/fi';
+import React, { useState, useEffect } from 'react';
+import { FiFolder, FiGitCommit, FiCode, FiZap, FiPlus, FiDollarSign } from 'react-icons/fi';
 import { open } from '@tauri-apps/api/dialog';
 import useStore from '../store/useStore';
+import { getUsageReport } from '../utils/tauriCommands';
 import './Dashboard.css';
 
 const Dashboard = () => {
   const { stats, projects, addProject, setCurrentView, setWorkspaceRoot, setProjectHandle } = useStore();
+  const [usage, setUsage] = useState(null);
+
+  useEffect(() => {
+    if (window.__TAURI_IPC__ === undefined) return;
+    // AI spend over the last 30 days
+    getUsageReport(30)
+      .then(setUsage)
+      .catch(() => setUsage(null));
+  }, []);
 
   const handleNewProject = async () => {
     // Check if we are in Tauri environment
@@ -70,6 +80,17 @@ const Dashboard = () => {
     { label: 'Git Commits', value: stats.gitCommits.toLocaleString(), icon: FiGitCommit, color: 'purple' },
   ];
 
+  if (usage) {
+    const tokens = usage.total.prompt_tokens + usage.total.completion_tokens;
+    statCards.push({
+      label: 'AI Spend (30d)',
+      value: `$${usage.total.cost.toFixed(2)}`,
+      icon: FiDollarSign,
+      color: 'green',
+      change: `${tokens.toLocaleString()} tokens • $${usage.session.cost.toFixed(4)} this session`,
+    });
+  }
+
   return (
     <div className="dashboard">
       <div className="dashboard-welcome">
@@ -89,7 +110,7 @@ const Dashboard = () => {
                 <Icon className="stat-icon" />
               </div>
               <div className="stat-value">{stat.value}</div>
-              <div className="stat-change">↑ 12% from last week</div>
+              <div className="stat-change">{stat.change || '↑ 12% from last week'}</div>
             </div>
           );
         })}
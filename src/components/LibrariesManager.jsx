@@ -50,10 +50,10 @@ const LibrariesManager = () => {
   ];
 
   const getAIProvider = () => {
-    if (openaiKey) return { provider: 'openai', apiKey: openaiKey, model: 'gpt-4' };
-    if (claudeKey) return { provider: 'claude', apiKey: claudeKey, model: 'claude-3-sonnet-20240229' };
-    if (geminiKey) return { provider: 'gemini', apiKey: geminiKey, model: 'gemini-pro' };
-    if (mistralKey) return { provider: 'mistral', apiKey: mistralKey, model: 'mistral-large-latest' };
+    if (openaiKey) return { provider: 'openai', model: 'gpt-4' };
+    if (claudeKey) return { provider: 'claude', model: 'claude-3-sonnet-20240229' };
+    if (geminiKey) return { provider: 'gemini', model: 'gemini-pro' };
+    if (mistralKey) return { provider: 'mistral', model: 'mistral-large-latest' };
     return null;
   };
 
@@ -100,7 +100,7 @@ Only return the JSON array, no other text.`
 
       const response = await callAI(
         aiConfig.provider,
-        aiConfig.apiKey,
+        null,
         aiConfig.model,
         messages
       );
@@ -39,19 +39,6 @@ const MOAPanel = () => {
     }
   }, []);
 
-  const getApiKeyForProvider = (provider) => {
-    switch (provider) {
-      case 'openai': return openaiKey;
-      case 'claude': return claudeKey;
-      case 'openrouter': return openrouterKey;
-      case 'gemini': return geminiKey;
-      case 'mistral': return mistralKey;
-      case 'cohere': return cohereKey;
-      case 'ollama': return true;
-      default: return null;
-    }
-  };
-
   const getModelOptions = (provider) => {
     switch (provider) {
       case 'openai':
@@ -86,7 +73,7 @@ const MOAPanel = () => {
       id: Date.now(),
       provider: 'openai',
       model: 'gpt-3.5-turbo',
-      apiKey: openaiKey || '',
+      apiKey: '',
       baseUrl: null,
     };
 
@@ -102,9 +89,9 @@ const MOAPanel = () => {
       if (m.id === id) {
         const updated = { ...m, [field]: value };
         
-        // Auto-fill API key when provider changes
+        // Reset the key override (empty uses the saved key) when provider changes
         if (field === 'provider') {
-          updated.apiKey = getApiKeyForProvider(value) || '';
+          updated.apiKey = '';
           updated.baseUrl = value === 'ollama' ? ollamaUrl : null;
           
           // Set default model for provider
@@ -186,7 +173,7 @@ const MOAPanel = () => {
       [role]: {
         provider: 'openai',
         model: 'gpt-3.5-turbo',
-        apiKey: openaiKey || '',
+        apiKey: '',
         systemPrompt: rolePrompts[role] || '',
       },
     });
@@ -326,7 +313,7 @@ const MOAPanel = () => {
                 onChange={(e) => setAggregatorConfig({
                   ...aggregatorConfig,
                   provider: e.target.value,
-                  apiKey: getApiKeyForProvider(e.target.value) || '',
+                  apiKey: '',
                   model: getModelOptions(e.target.value)[0]?.id || '',
                 })}
               >
@@ -428,7 +415,7 @@ const MOAPanel = () => {
                   onChange={(e) => {
                     const newProvider = e.target.value;
                     updateAgentRole(role, 'provider', newProvider);
-                    updateAgentRole(role, 'apiKey', getApiKeyForProvider(newProvider) || '');
+                    updateAgentRole(role, 'apiKey', '');
                     updateAgentRole(role, 'model', getModelOptions(newProvider)[0]?.id || '');
                   }}
                 >
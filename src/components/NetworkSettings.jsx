@@ -7,8 +7,31 @@ import {
   stopTunnel,
   getTunnelStatus,
 } from '../utils/tunnel/cloudflareTunnel';
+import {
+  getRetryPolicy,
+  setRetryPolicy,
+  getNetworkConfig,
+  setNetworkConfig,
+  getContextPolicy,
+  setContextPolicy,
+  getCacheSettings,
+  setCacheSettings,
+  getAICacheStats,
+  clearAICache,
+  getRateLimits,
+  setRateLimits,
+  getRateLimitStatus,
+} from '../utils/tauriCommands';
 import './NetworkSettings.css';
 
+const LIMITED_PROVIDERS = ['openai', 'claude', 'openrouter', 'gemini', 'mistral', 'cohere', 'ollama'];
+
+const LIMIT_FIELDS = [
+  { field: 'requests_per_minute', label: 'Requests/min' },
+  { field: 'tokens_per_minute', label: 'Tokens/min' },
+  { field: 'max_concurrent', label: 'Concurrent' },
+];
+
 const NetworkSettings = () => {
   const [devPort, setDevPort] = useState(5173);
   const [backendPort, setBackendPort] = useState('');
@@ -16,6 +39,13 @@ const NetworkSettings = () => {
   const [cloudflaredPath, setCloudflaredPath] = useState('cloudflared');
   const [tunnelStatus, setTunnelStatus] = useState({ active: false, url: null });
   const [isStarting, setIsStarting] = useState(false);
+  const [retryPolicy, setRetryPolicyState] = useState(null);
+  const [httpConfig, setHttpConfig] = useState(null);
+  const [cacheSettings, setCacheSettingsState] = useState(null);
+  const [contextPolicy, setContextPolicyState] = useState(null);
+  const [cacheStats, setCacheStats] = useState(null);
+  const [rateLimits, setRateLimitsState] = useState(null);
+  const [rateStatus, setRateStatus] = useState([]);
 
   useEffect(() => {
     // Load saved configuration
@@ -30,9 +60,80 @@ const NetworkSettings = () => {
     // Load tunnel status
     const status = getTunnelStatus();
     setTunnelStatus(status);
+
+    // Load AI retry policy from the backend
+    getRetryPolicy()
+      .then(setRetryPolicyState)
+      .catch(() => setRetryPolicyState(null));
+
+    // Load HTTP client (proxy / TLS / timeouts) configuration
+    getNetworkConfig()
+      .then(setHttpConfig)
+      .catch(() => setHttpConfig(null));
+
+    // Load how over-long conversations are trimmed
+    getContextPolicy()
+      .then(setContextPolicyState)
+      .catch(() => setContextPolicyState(null));
+
+    // Load AI response cache settings and current size
+    getCacheSettings()
+      .then(setCacheSettingsState)
+      .catch(() => setCacheSettingsState(null));
+    getAICacheStats()
+      .then(setCacheStats)
+      .catch(() => setCacheStats(null));
+
+    // Load per-provider rate limits
+    getRateLimits()
+      .then(setRateLimitsState)
+      .catch(() => setRateLimitsState(null));
+  }, []);
+
+  // Queue depth changes as requests come and go, so keep it current
+  useEffect(() => {
+    const refresh = () => getRateLimitStatus()
+      .then(setRateStatus)
+      .catch(() => setRateStatus([]));
+    refresh();
+    const timer = setInterval(refresh, 2000);
+    return () => clearInterval(timer);
   }, []);
 
-  const handleSave = () => {
+  const updateRateLimit = (provider, field, value) => {
+    setRateLimitsState((prev) => ({
+      ...prev,
+      [provider]: { ...prev[provider], [field]: parseInt(value) || null },
+    }));
+  };
+
+  const updateContextPolicy = (field, value) => {
+    setContextPolicyState((prev) => ({ ...prev, [field]: value }));
+  };
+
+  const updateCacheSettings = (field, value) => {
+    setCacheSettingsState((prev) => ({ ...prev, [field]: value }));
+  };
+
+  const handleClearCache = async () => {
+    try {
+      const freed = await clearAICache();
+      setCacheStats({ entries: 0, bytes: 0 });
+      alert(`Removed ${freed.entries} cached responses`);
+    } catch (error) {
+      alert(`Failed to clear the response cache: ${error}`);
+    }
+  };
+
+  const updateHttpConfig = (field, value) => {
+    setHttpConfig((prev) => ({ ...prev, [field]: value }));
+  };
+
+  const updateRetryPolicy = (field, value) => {
+    setRetryPolicyState((prev) => ({ ...prev, [field]: value }));
+  };
+
+  const handleSave = async () => {
     const config = {
       devPort,
       backendPort,
@@ -40,7 +141,49 @@ const NetworkSettings = () => {
       cloudflaredPath,
     };
 
-    const success = saveTunnelConfig(config);
+    let success = saveTunnelConfig(config);
+    if (retryPolicy) {
+      try {
+        await setRetryPolicy(retryPolicy);
+      } catch (error) {
+        success = false;
+      }
+    }
+    if (contextPolicy) {
+      try {
+        await setContextPolicy(contextPolicy);
+      } catch (error) {
+        alert(`Invalid context settings: ${error}`);
+        return;
+      }
+    }
+    if (cacheSettings) {
+      try {
+        await setCacheSettings(cacheSettings);
+      } catch (error) {
+        success = false;
+      }
+    }
+    if (rateLimits) {
+      // Providers without any limit are left out
+      const limits = Object.fromEntries(
+        Object.entries(rateLimits).filter(([, values]) => Object.values(values).some(Boolean))
+      );
+      try {
+        await setRateLimits(limits);
+      } catch (error) {
+        alert(`Invalid rate limits: ${error}`);
+        return;
+      }
+    }
+    if (httpConfig) {
+      try {
+        await setNetworkConfig(httpConfig);
+      } catch (error) {
+        alert(`Invalid HTTP client settings: ${error}`);
+        return;
+      }
+    }
     if (success) {
       alert('Network settings saved successfully!');
     } else {
@@ -193,6 +336,289 @@ const NetworkSettings = () => {
         )}
       </div>
 
+      {httpConfig && (
+        <div className="network-section">
+          <h4>HTTP Client</h4>
+          <p className="section-description">
+            Proxy, certificates and timeouts used for all AI provider requests
+          </p>
+
+          <div className="form-group">
+            <label>Proxy URL</label>
+            <input
+              type="text"
+              value={httpConfig.proxy_url || ''}
+              onChange={(e) => updateHttpConfig('proxy_url', e.target.value || null)}
+              placeholder="http://proxy.corp:8080 or socks5://127.0.0.1:1080"
+            />
+            <span className="input-hint">Leave empty for a direct connection</span>
+          </div>
+
+          <div className="form-group">
+            <label>No Proxy</label>
+            <input
+              type="text"
+              value={httpConfig.no_proxy || ''}
+              onChange={(e) => updateHttpConfig('no_proxy', e.target.value || null)}
+              placeholder="localhost,127.0.0.1"
+            />
+            <span className="input-hint">Comma-separated hosts that bypass the proxy</span>
+          </div>
+
+          <div className="form-group">
+            <label>Custom CA Bundle</label>
+            <input
+              type="text"
+              value={httpConfig.ca_bundle_path || ''}
+              onChange={(e) => updateHttpConfig('ca_bundle_path', e.target.value || null)}
+              placeholder="C:\certs\corp-ca.pem"
+            />
+            <span className="input-hint">PEM file with extra root certificates</span>
+          </div>
+
+          <div className="form-group">
+            <label>Connect Timeout (s)</label>
+            <input
+              type="number"
+              value={httpConfig.connect_timeout_secs}
+              onChange={(e) => updateHttpConfig('connect_timeout_secs', parseInt(e.target.value) || 1)}
+              min="1"
+            />
+          </div>
+
+          <div className="form-group">
+            <label>Read Timeout (s)</label>
+            <input
+              type="number"
+              value={httpConfig.read_timeout_secs}
+              onChange={(e) => updateHttpConfig('read_timeout_secs', parseInt(e.target.value) || 1)}
+              min="1"
+            />
+            <span className="input-hint">Max wait for a response or the next streamed chunk</span>
+          </div>
+
+          <div className="form-group">
+            <label>User Agent</label>
+            <input
+              type="text"
+              value={httpConfig.user_agent}
+              onChange={(e) => updateHttpConfig('user_agent', e.target.value)}
+            />
+          </div>
+        </div>
+      )}
+
+      {retryPolicy && (
+        <div className="network-section">
+          <h4>AI Request Retries</h4>
+          <p className="section-description">
+            Retry rate-limited (429) and failed (5xx) provider requests with exponential backoff
+          </p>
+
+          <div className="form-group">
+            <label>Max Attempts</label>
+            <input
+              type="number"
+              value={retryPolicy.max_attempts}
+              onChange={(e) => updateRetryPolicy('max_attempts', parseInt(e.target.value) || 1)}
+              min="1"
+              max="10"
+            />
+            <span className="input-hint">Including the first request; 1 disables retries</span>
+          </div>
+
+          <div className="form-group">
+            <label>Base Delay (ms)</label>
+            <input
+              type="number"
+              value={retryPolicy.base_delay_ms}
+              onChange={(e) => updateRetryPolicy('base_delay_ms', parseInt(e.target.value) || 0)}
+              min="0"
+            />
+          </div>
+
+          <div className="form-group">
+            <label>Max Delay (ms)</label>
+            <input
+              type="number"
+              value={retryPolicy.max_delay_ms}
+              onChange={(e) => updateRetryPolicy('max_delay_ms', parseInt(e.target.value) || 0)}
+              min="0"
+            />
+            <span className="input-hint">Longer Retry-After waits are reported instead of retried</span>
+          </div>
+
+          <div className="form-group checkbox-group">
+            <label className="checkbox-label">
+              <input
+                type="checkbox"
+                checked={retryPolicy.jitter}
+                onChange={(e) => updateRetryPolicy('jitter', e.target.checked)}
+              />
+              <span>Randomize backoff (jitter)</span>
+            </label>
+          </div>
+        </div>
+      )}
+
+      {rateLimits && (
+        <div className="network-section">
+          <h4>AI Rate Limits</h4>
+          <p className="section-description">
+            Queue requests to a provider instead of exceeding its limits; leave a field empty for no limit
+          </p>
+
+          {[...new Set([...LIMITED_PROVIDERS, ...Object.keys(rateLimits)])].map((provider) => {
+            const status = rateStatus.find((s) => s.provider === provider);
+            return (
+              <div className="form-group" key={provider}>
+                <label>{provider}</label>
+                <div className="rate-limit-row">
+                  {LIMIT_FIELDS.map(({ field, label }) => (
+                    <input
+                      key={field}
+                      type="number"
+                      value={rateLimits[provider]?.[field] || ''}
+                      onChange={(e) => updateRateLimit(provider, field, e.target.value)}
+                      placeholder={label}
+                      title={label}
+                      min="1"
+                    />
+                  ))}
+                </div>
+                {status && (status.queued > 0 || status.in_flight > 0 || status.requests_last_minute > 0) && (
+                  <span className="input-hint">
+                    {status.queued} queued, {status.in_flight} in flight, {status.requests_last_minute} requests
+                    and {status.tokens_last_minute} tokens in the last minute
+                  </span>
+                )}
+              </div>
+            );
+          })}
+        </div>
+      )}
+
+      {contextPolicy && (
+        <div className="network-section">
+          <h4>Context Window</h4>
+          <p className="section-description">
+            What to do when a conversation no longer fits the model's context window
+          </p>
+
+          <div className="form-group">
+            <label>Strategy</label>
+            <select
+              value={contextPolicy.strategy}
+              onChange={(e) => updateContextPolicy('strategy', e.target.value)}
+            >
+              <option value="drop_oldest">Drop oldest turns</option>
+              <option value="keep_last">Keep system prompt and last messages</option>
+              <option value="summarize">Summarize earlier turns</option>
+              <option value="off">Off (send as is)</option>
+            </select>
+          </div>
+
+          {(contextPolicy.strategy === 'keep_last' || contextPolicy.strategy === 'summarize') && (
+            <div className="form-group">
+              <label>Messages Kept Verbatim</label>
+              <input
+                type="number"
+                value={contextPolicy.keep_last}
+                onChange={(e) => updateContextPolicy('keep_last', parseInt(e.target.value) || 0)}
+                min="0"
+              />
+            </div>
+          )}
+
+          <div className="form-group">
+            <label>Reserved for Reply (tokens)</label>
+            <input
+              type="number"
+              value={contextPolicy.reserve_tokens}
+              onChange={(e) => updateContextPolicy('reserve_tokens', parseInt(e.target.value) || 0)}
+              min="0"
+            />
+            <span className="input-hint">Used when a request does not set max tokens</span>
+          </div>
+
+          {contextPolicy.strategy === 'summarize' && (
+            <>
+              <div className="form-group">
+                <label>Summary Provider</label>
+                <input
+                  type="text"
+                  value={contextPolicy.summary_provider || ''}
+                  onChange={(e) => updateContextPolicy('summary_provider', e.target.value || null)}
+                  placeholder="Same as the chat"
+                />
+              </div>
+              <div className="form-group">
+                <label>Summary Model</label>
+                <input
+                  type="text"
+                  value={contextPolicy.summary_model || ''}
+                  onChange={(e) => updateContextPolicy('summary_model', e.target.value || null)}
+                  placeholder="e.g. gpt-4o-mini"
+                />
+                <span className="input-hint">A cheap model is enough for summaries</span>
+              </div>
+            </>
+          )}
+        </div>
+      )}
+
+      {cacheSettings && (
+        <div className="network-section">
+          <h4>AI Response Cache</h4>
+          <p className="section-description">
+            Answer identical prompts to the same model from disk instead of calling the provider again
+          </p>
+
+          <div className="form-group checkbox-group">
+            <label className="checkbox-label">
+              <input
+                type="checkbox"
+                checked={cacheSettings.enabled}
+                onChange={(e) => updateCacheSettings('enabled', e.target.checked)}
+              />
+              <span>Cache responses</span>
+            </label>
+          </div>
+
+          <div className="form-group">
+            <label>Time to Live (hours)</label>
+            <input
+              type="number"
+              value={Math.round(cacheSettings.ttl_secs / 3600)}
+              onChange={(e) => updateCacheSettings('ttl_secs', (parseInt(e.target.value) || 1) * 3600)}
+              min="1"
+            />
+          </div>
+
+          <div className="form-group">
+            <label>Max Size (MB)</label>
+            <input
+              type="number"
+              value={Math.round(cacheSettings.max_bytes / (1024 * 1024))}
+              onChange={(e) => updateCacheSettings('max_bytes', (parseInt(e.target.value) || 0) * 1024 * 1024)}
+              min="0"
+            />
+            <span className="input-hint">The oldest responses are evicted first</span>
+          </div>
+
+          <div className="form-group">
+            <button className="save-btn" onClick={handleClearCache}>
+              Clear Cache
+            </button>
+            {cacheStats && (
+              <span className="input-hint">
+                {cacheStats.entries} responses, {(cacheStats.bytes / (1024 * 1024)).toFixed(1)} MB
+              </span>
+            )}
+          </div>
+        </div>
+      )}
+
       <div className="network-actions">
         <button className="save-btn" onClick={handleSave}>
           Save Network Settings
@@ -0,0 +1,168 @@
+import React, { useState, useEffect } from 'react';
+import { FiInfo, FiTrash2, FiRefreshCw, FiX, FiCheck, FiAlertCircle } from 'react-icons/fi';
+import {
+  getOllamaModels,
+  pullOllamaModel,
+  deleteOllamaModel,
+  showOllamaModel,
+  listRunningOllamaModels,
+} from '../utils/providers/ollama';
+import { cancelAIRequest, isCancelled, describeAIError } from '../utils/aiClient';
+
+const formatBytes = (bytes) => `${(bytes / 1024 ** 3).toFixed(1)} GB`;
+
+const describeProgress = ({ status, total, completed }) => (
+  total ? `${status} ${Math.round(((completed || 0) / total) * 100)}%` : status
+);
+
+// Installed and loaded Ollama models, with pull/delete and per-model details
+const OllamaModels = ({ baseUrl }) => {
+  const [installed, setInstalled] = useState([]);
+  const [running, setRunning] = useState([]);
+  const [details, setDetails] = useState(null);
+  const [pullName, setPullName] = useState('');
+  const [pull, setPull] = useState(null);
+  const [status, setStatus] = useState(null);
+
+  useEffect(() => {
+    refresh();
+  }, [baseUrl]);
+
+  const refresh = async () => {
+    setInstalled(await getOllamaModels(baseUrl));
+    try {
+      setRunning(await listRunningOllamaModels(baseUrl));
+    } catch (error) {
+      setRunning([]);
+    }
+  };
+
+  const handlePull = async () => {
+    const name = pullName.trim();
+    const requestId = crypto.randomUUID();
+    setPull({ requestId, name, progress: null });
+    setStatus(null);
+    try {
+      await pullOllamaModel(name, {
+        baseUrl,
+        requestId,
+        onProgress: (progress) => setPull((prev) => prev && { ...prev, progress }),
+      });
+      setStatus({ success: true, message: `Pulled ${name}` });
+      setPullName('');
+      await refresh();
+    } catch (error) {
+      setStatus(isCancelled(error)
+        ? { success: false, message: `Pull of ${name} cancelled` }
+        : { success: false, message: `Failed to pull ${name}: ${describeAIError(error)}` });
+    } finally {
+      setPull(null);
+    }
+  };
+
+  const handleDelete = async (name) => {
+    if (!window.confirm(`Delete ${name} from Ollama?`)) return;
+    try {
+      await deleteOllamaModel(name, baseUrl);
+      if (details?.name === name) setDetails(null);
+      await refresh();
+    } catch (error) {
+      setStatus({ success: false, message: `Failed to delete ${name}: ${describeAIError(error)}` });
+    }
+  };
+
+  const handleDetails = async (name) => {
+    if (details?.name === name) {
+      setDetails(null);
+      return;
+    }
+    try {
+      setDetails(await showOllamaModel(name, baseUrl));
+    } catch (error) {
+      setStatus({ success: false, message: `Failed to load ${name}: ${describeAIError(error)}` });
+    }
+  };
+
+  return (
+    <div className="ollama-models">
+      <div className="form-group">
+        <label>
+          Installed Models
+          <button className="custom-endpoint-btn" onClick={refresh} title="Refresh">
+            <FiRefreshCw />
+          </button>
+        </label>
+        {installed.length === 0 && <p className="provider-info">No models found</p>}
+        {installed.map(({ id }) => {
+          const loaded = running.find((model) => model.name === id);
+          return (
+            <div key={id}>
+              <div className="custom-endpoint">
+                <div className="custom-endpoint-info">
+                  <strong>{id}</strong>
+                  {loaded && (
+                    <span>Loaded · {formatBytes(loaded.size)} ({formatBytes(loaded.size_vram)} in VRAM)</span>
+                  )}
+                </div>
+                <button className="custom-endpoint-btn" onClick={() => handleDetails(id)} title="Details">
+                  <FiInfo />
+                </button>
+                <button className="custom-endpoint-btn" onClick={() => handleDelete(id)} title="Delete">
+                  <FiTrash2 />
+                </button>
+              </div>
+              {details?.name === id && (
+                <div className="ollama-model-details">
+                  <span>
+                    {[details.family, details.parameter_size, details.quantization_level]
+                      .filter(Boolean)
+                      .join(' · ')}
+                  </span>
+                  {details.context_length && <span>Context length: {details.context_length}</span>}
+                  {Object.entries(details.parameters).map(([name, values]) => (
+                    <span key={name}>{name}: {values.join(', ')}</span>
+                  ))}
+                  {details.template && <pre>{details.template}</pre>}
+                </div>
+              )}
+            </div>
+          );
+        })}
+      </div>
+
+      <div className="form-group">
+        <label>Pull Model</label>
+        <div className="input-wrapper">
+          <input
+            type="text"
+            value={pullName}
+            onChange={(e) => setPullName(e.target.value)}
+            placeholder="llama3:8b"
+            disabled={!!pull}
+          />
+          {pull ? (
+            <button className="test-connection-btn" onClick={() => cancelAIRequest(pull.requestId)}>
+              <FiX /> Cancel
+            </button>
+          ) : (
+            <button className="test-connection-btn" onClick={handlePull} disabled={!pullName.trim()}>
+              Pull
+            </button>
+          )}
+        </div>
+        {pull && (
+          <p className="provider-info">{pull.progress ? describeProgress(pull.progress) : `Starting ${pull.name}...`}</p>
+        )}
+      </div>
+
+      {status && (
+        <div className={`connection-status ${status.success ? 'success' : 'error'}`}>
+          {status.success ? <FiCheck /> : <FiAlertCircle />}
+          {status.message}
+        </div>
+      )}
+    </div>
+  );
+};
+
+export default OllamaModels;
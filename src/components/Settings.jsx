@@ -1,24 +1,45 @@
 import React, { useState, useEffect } from 'react';
-import { FiSettings, FiX, FiEye, FiEyeOff, FiCheck, FiAlertCircle, FiUpload, FiRotateCcw } from 'react-icons/fi';
+import { FiSettings, FiX, FiEye, FiEyeOff, FiCheck, FiAlertCircle, FiUpload, FiRotateCcw, FiLock, FiUnlock } from 'react-icons/fi';
 import useStore from '../store/useStore';
-import { saveAPIKey, getAPIKey } from '../utils/tauriCommands';
-import { testOpenAIConnection, testClaudeConnection } from '../utils/aiClient';
+import { saveAPIKey, getAPIKeyStatus, listModels, getVaultStatus, unlockVault, lockVault, setVaultPassword, getOllamaBaseUrl, setOllamaBaseUrl } from '../utils/tauriCommands';
+import { testOpenAIConnection, testClaudeConnection, describeAIError } from '../utils/aiClient';
 import { OPENROUTER_MODELS, testOpenRouterConnection } from '../utils/providers/openrouter';
 import { GEMINI_MODELS, testGeminiConnection } from '../utils/providers/gemini';
 import { MISTRAL_MODELS, testMistralConnection } from '../utils/providers/mistral';
 import { COHERE_MODELS, testCohereConnection } from '../utils/providers/cohere';
-import { DEFAULT_OLLAMA_MODELS, testOllamaConnection, getOllamaModels } from '../utils/providers/ollama';
+import { DEFAULT_OLLAMA_MODELS, getOllamaStatus } from '../utils/providers/ollama';
 import { loadMCPConfig, saveMCPConfig } from '../utils/mcp/mcpClient';
 import { loadCustomLogo, saveCustomLogo, resetLogo, validateLogoFile, fileToDataURL } from '../utils/branding';
 import SkillsManager from './SkillsManager';
 import MOAPanel from './MOAPanel';
 import ContextSelector from './ContextSelector';
 import NetworkSettings from './NetworkSettings';
+import CustomEndpoints from './CustomEndpoints';
+import OllamaModels from './OllamaModels';
 import ThemeSelector from './ThemeSelector';
 import './Settings.css';
 
+const OPENAI_MODELS = [
+  { id: 'gpt-4', name: 'GPT-4' },
+  { id: 'gpt-4-turbo-preview', name: 'GPT-4 Turbo' },
+  { id: 'gpt-3.5-turbo', name: 'GPT-3.5 Turbo' },
+];
+
+const CLAUDE_MODELS = [
+  { id: 'claude-3-opus-20240229', name: 'Claude 3 Opus' },
+  { id: 'claude-3-sonnet-20240229', name: 'Claude 3 Sonnet' },
+  { id: 'claude-3-haiku-20240307', name: 'Claude 3 Haiku' },
+];
+
+// Turns a validate_provider_key result into a connection-status line
+const keyTestStatus = (result) => (
+  result.valid
+    ? { success: true, message: `Connection successful! ${result.models.length} models available.` }
+    : { success: false, message: `Connection failed: ${describeAIError(result.error)}` }
+);
+
 const Settings = () => {
-  const { settingsOpen, toggleSettings, setOpenAIKey, setClaudeKey, setOpenRouterKey, setGeminiKey, setMistralKey, setCohereKey, setOllamaUrl } = useStore();
+  const { settingsOpen, toggleSettings, setOpenAIKey, setClaudeKey, setOpenRouterKey, setGeminiKey, setMistralKey, setCohereKey, ollamaUrl, setOllamaUrl } = useStore();
   const [activeTab, setActiveTab] = useState('ai');
   
   // OpenAI
@@ -70,6 +91,17 @@ const Settings = () => {
   const [testingOllama, setTestingOllama] = useState(false);
   const [ollamaModels, setOllamaModels] = useState(DEFAULT_OLLAMA_MODELS);
 
+  // Saved keys never reach the UI - only whether one exists and a masked hint
+  const [keyStatus, setKeyStatus] = useState({});
+
+  // Model lists fetched from the providers, replacing the built-in defaults
+  const [catalogs, setCatalogs] = useState({});
+
+  // API key vault
+  const [vaultStatus, setVaultStatus] = useState(null);
+  const [vaultPassword, setVaultPasswordInput] = useState('');
+  const [vaultMessage, setVaultMessage] = useState(null);
+
   // MCP
   const [mcpEnabled, setMcpEnabled] = useState(false);
   const [mcpServerUrl, setMcpServerUrl] = useState('');
@@ -79,49 +111,98 @@ const Settings = () => {
   const [logoPreview, setLogoPreview] = useState(null);
   useEffect(() => {
     if (settingsOpen) {
-      loadApiKeys();
+      loadVault();
       loadMCPSettings();
       loadBrandingSettings();
     }
   }, [settingsOpen]);
 
-  const loadApiKeys = async () => {
+  const loadVault = async () => {
     try {
-      const openaiKey = await getAPIKey('openai');
-      const claudeKey = await getAPIKey('claude');
-      const openrouterKey = await getAPIKey('openrouter');
-      const geminiKey = await getAPIKey('gemini');
-      const mistralKey = await getAPIKey('mistral');
-      const cohereKey = await getAPIKey('cohere');
-      const ollamaUrl = await getAPIKey('ollama');
-      
-      if (openaiKey) {
-        setOpenaiKeyInput(openaiKey);
-        setOpenAIKey(openaiKey);
-      }
-      if (claudeKey) {
-        setClaudeKeyInput(claudeKey);
-        setClaudeKey(claudeKey);
-      }
-      if (openrouterKey) {
-        setOpenrouterKeyInput(openrouterKey);
-        setOpenRouterKey(openrouterKey);
-      }
-      if (geminiKey) {
-        setGeminiKeyInput(geminiKey);
-        setGeminiKey(geminiKey);
+      const status = await getVaultStatus();
+      setVaultStatus(status);
+      if (!status.unlocked && status.error) {
+        setVaultMessage({ success: false, message: `Could not unlock the vault: ${status.error}` });
       }
-      if (mistralKey) {
-        setMistralKeyInput(mistralKey);
-        setMistralKey(mistralKey);
-      }
-      if (cohereKey) {
-        setCohereKeyInput(cohereKey);
-        setCohereKey(cohereKey);
+      await loadApiKeys();
+    } catch (error) {
+      console.error('Failed to load vault:', error);
+    }
+  };
+
+  const handleUnlockVault = async () => {
+    try {
+      const status = await unlockVault(vaultPassword || null);
+      setVaultStatus(status);
+      setVaultPasswordInput('');
+      setVaultMessage(null);
+      await loadApiKeys();
+    } catch (error) {
+      setVaultMessage({ success: false, message: String(error) });
+    }
+  };
+
+  const handleLockVault = async () => {
+    try {
+      setVaultStatus(await lockVault());
+      setVaultMessage(null);
+      await loadApiKeys();
+    } catch (error) {
+      setVaultMessage({ success: false, message: String(error) });
+    }
+  };
+
+  const handleSetVaultPassword = async () => {
+    try {
+      const status = await setVaultPassword(vaultPassword || null);
+      setVaultStatus(status);
+      setVaultPasswordInput('');
+      setVaultMessage({
+        success: true,
+        message: status.mode === 'password'
+          ? 'Master password set. Keys will need unlocking after restart.'
+          : 'Master password removed. Keys are bound to this machine.',
+      });
+    } catch (error) {
+      setVaultMessage({ success: false, message: String(error) });
+    }
+  };
+
+  const loadCatalogs = async (providers) => {
+    const results = await Promise.allSettled(providers.map((provider) => listModels(provider)));
+    const loaded = {};
+    results.forEach((result) => {
+      if (result.status === 'fulfilled' && result.value.models.length > 0) {
+        loaded[result.value.provider] = result.value.models;
       }
-      if (ollamaUrl) {
-        setOllamaUrlInput(ollamaUrl);
-        setOllamaUrl(ollamaUrl);
+    });
+    setCatalogs((prev) => ({ ...prev, ...loaded }));
+  };
+
+  const modelsFor = (provider, defaults) => catalogs[provider] || defaults;
+
+  const loadApiKeys = async () => {
+    try {
+      const providers = ['openai', 'claude', 'openrouter', 'gemini', 'mistral', 'cohere'];
+      const statuses = await Promise.all(providers.map((provider) => getAPIKeyStatus(provider)));
+      const byProvider = Object.fromEntries(statuses.map((status) => [status.provider, status]));
+      setKeyStatus(byProvider);
+      const savedOllamaUrl = await getOllamaBaseUrl();
+      loadCatalogs([
+        ...providers.filter((provider) => byProvider[provider].configured),
+        ...(savedOllamaUrl ? ['ollama'] : []),
+      ]);
+
+      // The store only needs to know which providers are usable
+      setOpenAIKey(byProvider.openai.masked || '');
+      setClaudeKey(byProvider.claude.masked || '');
+      setOpenRouterKey(byProvider.openrouter.masked || '');
+      setGeminiKey(byProvider.gemini.masked || '');
+      setMistralKey(byProvider.mistral.masked || '');
+      setCohereKey(byProvider.cohere.masked || '');
+      if (savedOllamaUrl) {
+        setOllamaUrlInput(savedOllamaUrl);
+        setOllamaUrl(savedOllamaUrl);
       }
     } catch (error) {
       console.error('Failed to load API keys:', error);
@@ -189,7 +270,7 @@ const Settings = () => {
   };
 
   const handleTestOpenAI = async () => {
-    if (!openaiKeyInput.trim()) {
+    if (!openaiKeyInput.trim() && !keyStatus.openai?.configured) {
       setOpenaiTestStatus({ success: false, message: 'Please enter an API key' });
       return;
     }
@@ -198,12 +279,8 @@ const Settings = () => {
     setOpenaiTestStatus(null);
 
     try {
-      const success = await testOpenAIConnection(openaiKeyInput);
-      if (success) {
-        setOpenaiTestStatus({ success: true, message: 'Connection successful!' });
-      } else {
-        setOpenaiTestStatus({ success: false, message: 'Connection failed. Check your API key.' });
-      }
+      const result = await testOpenAIConnection(openaiKeyInput.trim() || null);
+      setOpenaiTestStatus(keyTestStatus(result));
     } catch (error) {
       setOpenaiTestStatus({ success: false, message: 'Connection failed: ' + error.message });
     } finally {
@@ -212,7 +289,7 @@ const Settings = () => {
   };
 
   const handleTestClaude = async () => {
-    if (!claudeKeyInput.trim()) {
+    if (!claudeKeyInput.trim() && !keyStatus.claude?.configured) {
       setClaudeTestStatus({ success: false, message: 'Please enter an API key' });
       return;
     }
@@ -221,12 +298,8 @@ const Settings = () => {
     setClaudeTestStatus(null);
 
     try {
-      const success = await testClaudeConnection(claudeKeyInput);
-      if (success) {
-        setClaudeTestStatus({ success: true, message: 'Connection successful!' });
-      } else {
-        setClaudeTestStatus({ success: false, message: 'Connection failed. Check your API key.' });
-      }
+      const result = await testClaudeConnection(claudeKeyInput.trim() || null);
+      setClaudeTestStatus(keyTestStatus(result));
     } catch (error) {
       setClaudeTestStatus({ success: false, message: 'Connection failed: ' + error.message });
     } finally {
@@ -235,7 +308,7 @@ const Settings = () => {
   };
 
   const handleTestOpenRouter = async () => {
-    if (!openrouterKeyInput.trim()) {
+    if (!openrouterKeyInput.trim() && !keyStatus.openrouter?.configured) {
       setOpenrouterTestStatus({ success: false, message: 'Please enter an API key' });
       return;
     }
@@ -244,12 +317,8 @@ const Settings = () => {
     setOpenrouterTestStatus(null);
 
     try {
-      const success = await testOpenRouterConnection(openrouterKeyInput);
-      if (success) {
-        setOpenrouterTestStatus({ success: true, message: 'Connection successful!' });
-      } else {
-        setOpenrouterTestStatus({ success: false, message: 'Connection failed. Check your API key.' });
-      }
+      const result = await testOpenRouterConnection(openrouterKeyInput.trim() || null);
+      setOpenrouterTestStatus(keyTestStatus(result));
     } catch (error) {
       setOpenrouterTestStatus({ success: false, message: 'Connection failed: ' + error.message });
     } finally {
@@ -258,7 +327,7 @@ const Settings = () => {
   };
 
   const handleTestGemini = async () => {
-    if (!geminiKeyInput.trim()) {
+    if (!geminiKeyInput.trim() && !keyStatus.gemini?.configured) {
       setGeminiTestStatus({ success: false, message: 'Please enter an API key' });
       return;
     }
@@ -267,12 +336,8 @@ const Settings = () => {
     setGeminiTestStatus(null);
 
     try {
-      const success = await testGeminiConnection(geminiKeyInput);
-      if (success) {
-        setGeminiTestStatus({ success: true, message: 'Connection successful!' });
-      } else {
-        setGeminiTestStatus({ success: false, message: 'Connection failed. Check your API key.' });
-      }
+      const result = await testGeminiConnection(geminiKeyInput.trim() || null);
+      setGeminiTestStatus(keyTestStatus(result));
     } catch (error) {
       setGeminiTestStatus({ success: false, message: 'Connection failed: ' + error.message });
     } finally {
@@ -281,7 +346,7 @@ const Settings = () => {
   };
 
   const handleTestMistral = async () => {
-    if (!mistralKeyInput.trim()) {
+    if (!mistralKeyInput.trim() && !keyStatus.mistral?.configured) {
       setMistralTestStatus({ success: false, message: 'Please enter an API key' });
       return;
     }
@@ -290,12 +355,8 @@ const Settings = () => {
     setMistralTestStatus(null);
 
     try {
-      const success = await testMistralConnection(mistralKeyInput);
-      if (success) {
-        setMistralTestStatus({ success: true, message: 'Connection successful!' });
-      } else {
-        setMistralTestStatus({ success: false, message: 'Connection failed. Check your API key.' });
-      }
+      const result = await testMistralConnection(mistralKeyInput.trim() || null);
+      setMistralTestStatus(keyTestStatus(result));
     } catch (error) {
       setMistralTestStatus({ success: false, message: 'Connection failed: ' + error.message });
     } finally {
@@ -304,7 +365,7 @@ const Settings = () => {
   };
 
   const handleTestCohere = async () => {
-    if (!cohereKeyInput.trim()) {
+    if (!cohereKeyInput.trim() && !keyStatus.cohere?.configured) {
       setCohereTestStatus({ success: false, message: 'Please enter an API key' });
       return;
     }
@@ -313,12 +374,8 @@ const Settings = () => {
     setCohereTestStatus(null);
 
     try {
-      const success = await testCohereConnection(cohereKeyInput);
-      if (success) {
-        setCohereTestStatus({ success: true, message: 'Connection successful!' });
-      } else {
-        setCohereTestStatus({ success: false, message: 'Connection failed. Check your API key.' });
-      }
+      const result = await testCohereConnection(cohereKeyInput.trim() || null);
+      setCohereTestStatus(keyTestStatus(result));
     } catch (error) {
       setCohereTestStatus({ success: false, message: 'Connection failed: ' + error.message });
     } finally {
@@ -336,19 +393,18 @@ const Settings = () => {
     setOllamaTestStatus(null);
 
     try {
-      const success = await testOllamaConnection(ollamaUrlInput);
-      if (success) {
-        setOllamaTestStatus({ success: true, message: 'Connection successful!' });
-        try {
-          const models = await getOllamaModels(ollamaUrlInput);
-          if (models && models.length > 0) {
-            setOllamaModels(models);
-          }
-        } catch (error) {
-          console.error('Failed to fetch Ollama models:', error);
-        }
-      } else {
-        setOllamaTestStatus({ success: false, message: 'Connection failed. Check your base URL.' });
+      const status = await getOllamaStatus(ollamaUrlInput, ollamaModel);
+      if (!status.reachable) {
+        setOllamaTestStatus({ success: false, message: `Connection failed: ${describeAIError(status.error)}` });
+        return;
+      }
+
+      setOllamaUrlInput(status.base_url);
+      setOllamaTestStatus(status.model_available === false
+        ? { success: false, message: `Ollama ${status.version} is running, but ${ollamaModel} is not installed.` }
+        : { success: true, message: `Ollama ${status.version} is running with ${status.models.length} models.` });
+      if (status.models.length > 0) {
+        setOllamaModels(status.models.map((model) => ({ id: model, name: model })));
       }
     } catch (error) {
       setOllamaTestStatus({ success: false, message: 'Connection failed: ' + error.message });
@@ -358,34 +414,31 @@ const Settings = () => {
   };
   const handleSave = async () => {
     try {
-      if (openaiKeyInput.trim()) {
-        await saveAPIKey('openai', openaiKeyInput);
-        setOpenAIKey(openaiKeyInput);
-      }
-      if (claudeKeyInput.trim()) {
-        await saveAPIKey('claude', claudeKeyInput);
-        setClaudeKey(claudeKeyInput);
-      }
-      if (openrouterKeyInput.trim()) {
-        await saveAPIKey('openrouter', openrouterKeyInput);
-        setOpenRouterKey(openrouterKeyInput);
-      }
-      if (geminiKeyInput.trim()) {
-        await saveAPIKey('gemini', geminiKeyInput);
-        setGeminiKey(geminiKeyInput);
-      }
-      if (mistralKeyInput.trim()) {
-        await saveAPIKey('mistral', mistralKeyInput);
-        setMistralKey(mistralKeyInput);
-      }
-      if (cohereKeyInput.trim()) {
-        await saveAPIKey('cohere', cohereKeyInput);
-        setCohereKey(cohereKeyInput);
+      const keys = {
+        openai: openaiKeyInput,
+        claude: claudeKeyInput,
+        openrouter: openrouterKeyInput,
+        gemini: geminiKeyInput,
+        mistral: mistralKeyInput,
+        cohere: cohereKeyInput,
+      };
+      for (const [provider, key] of Object.entries(keys)) {
+        if (key.trim()) {
+          await saveAPIKey(provider, key);
+        }
       }
       if (ollamaUrlInput.trim()) {
-        await saveAPIKey('ollama', ollamaUrlInput);
-        setOllamaUrl(ollamaUrlInput);
+        await setOllamaBaseUrl(ollamaUrlInput);
       }
+
+      // Clear the typed secrets and show the saved hints instead
+      setOpenaiKeyInput('');
+      setClaudeKeyInput('');
+      setOpenrouterKeyInput('');
+      setGeminiKeyInput('');
+      setMistralKeyInput('');
+      setCohereKeyInput('');
+      await loadApiKeys();
       
       alert('Settings saved successfully!');
       toggleSettings();
@@ -458,6 +511,49 @@ const Settings = () => {
 
           {activeTab === 'ai' && (
             <div>
+              <div className="settings-section">
+                <h3>{vaultStatus?.unlocked ? <FiUnlock /> : <FiLock />} API Key Vault</h3>
+                <p className="provider-info">
+                  {vaultStatus?.unlocked
+                    ? (vaultStatus.mode === 'password'
+                      ? 'Unlocked. Keys are encrypted with your master password.'
+                      : 'Unlocked. Keys are encrypted with a key bound to this machine.')
+                    : 'Locked. Enter your master password to load saved API keys.'}
+                </p>
+                <div className="form-group">
+                  <label>{vaultStatus?.unlocked ? 'New Master Password' : 'Master Password'}</label>
+                  <div className="input-wrapper">
+                    <input
+                      type="password"
+                      value={vaultPassword}
+                      onChange={(e) => setVaultPasswordInput(e.target.value)}
+                      placeholder={vaultStatus?.unlocked ? 'Leave empty to use the machine key' : 'Master password'}
+                    />
+                    {vaultStatus?.unlocked ? (
+                      <>
+                        <button className="test-connection-btn" onClick={handleSetVaultPassword}>
+                          {vaultPassword ? 'Set Password' : 'Use Machine Key'}
+                        </button>
+                        <button className="test-connection-btn" onClick={handleLockVault}>
+                          Lock
+                        </button>
+                      </>
+                    ) : (
+                      <button className="test-connection-btn" onClick={handleUnlockVault}>
+                        Unlock
+                      </button>
+                    )}
+                  </div>
+                </div>
+
+                {vaultMessage && (
+                  <div className={`connection-status ${vaultMessage.success ? 'success' : 'error'}`}>
+                    {vaultMessage.success ? <FiCheck /> : <FiAlertCircle />}
+                    {vaultMessage.message}
+                  </div>
+                )}
+              </div>
+
               <div className="settings-section">
                 <h3>OpenAI Configuration</h3>
                 <div className="form-group">
@@ -467,7 +563,7 @@ const Settings = () => {
                       type={showOpenaiKey ? 'text' : 'password'}
                       value={openaiKeyInput}
                       onChange={(e) => setOpenaiKeyInput(e.target.value)}
-                      placeholder="sk-..."
+                      placeholder={keyStatus.openai?.masked ? `Saved: ${keyStatus.openai.masked}` : 'sk-...'}
                       style={{ paddingRight: '45px' }}
                     />
                     <button
@@ -487,7 +583,7 @@ const Settings = () => {
                   <button
                     className="test-connection-btn"
                     onClick={handleTestOpenAI}
-                    disabled={testingOpenai || !openaiKeyInput.trim()}
+                    disabled={testingOpenai || !(openaiKeyInput.trim() || keyStatus.openai?.configured)}
                   >
                     {testingOpenai ? 'Testing...' : 'Test Connection'}
                   </button>
@@ -503,9 +599,9 @@ const Settings = () => {
                 <div className="form-group model-select">
                   <label>Default Model</label>
                   <select value={openaiModel} onChange={(e) => setOpenaiModel(e.target.value)}>
-                    <option value="gpt-4">GPT-4</option>
-                    <option value="gpt-4-turbo-preview">GPT-4 Turbo</option>
-                    <option value="gpt-3.5-turbo">GPT-3.5 Turbo</option>
+                    {modelsFor('openai', OPENAI_MODELS).map((model) => (
+                      <option key={model.id} value={model.id}>{model.name}</option>
+                    ))}
                   </select>
                 </div>
               </div>
@@ -519,7 +615,7 @@ const Settings = () => {
                       type={showClaudeKey ? 'text' : 'password'}
                       value={claudeKeyInput}
                       onChange={(e) => setClaudeKeyInput(e.target.value)}
-                      placeholder="sk-ant-..."
+                      placeholder={keyStatus.claude?.masked ? `Saved: ${keyStatus.claude.masked}` : 'sk-ant-...'}
                       style={{ paddingRight: '45px' }}
                     />
                     <button
@@ -539,7 +635,7 @@ const Settings = () => {
                   <button
                     className="test-connection-btn"
                     onClick={handleTestClaude}
-                    disabled={testingClaude || !claudeKeyInput.trim()}
+                    disabled={testingClaude || !(claudeKeyInput.trim() || keyStatus.claude?.configured)}
                   >
                     {testingClaude ? 'Testing...' : 'Test Connection'}
                   </button>
@@ -555,9 +651,9 @@ const Settings = () => {
                 <div className="form-group model-select">
                   <label>Default Model</label>
                   <select value={claudeModel} onChange={(e) => setClaudeModel(e.target.value)}>
-                    <option value="claude-3-opus-20240229">Claude 3 Opus</option>
-                    <option value="claude-3-sonnet-20240229">Claude 3 Sonnet</option>
-                    <option value="claude-3-haiku-20240307">Claude 3 Haiku</option>
+                    {modelsFor('claude', CLAUDE_MODELS).map((model) => (
+                      <option key={model.id} value={model.id}>{model.name}</option>
+                    ))}
                   </select>
                 </div>
               </div>
@@ -571,7 +667,7 @@ const Settings = () => {
                       type={showOpenrouterKey ? 'text' : 'password'}
                       value={openrouterKeyInput}
                       onChange={(e) => setOpenrouterKeyInput(e.target.value)}
-                      placeholder="sk-or-..."
+                      placeholder={keyStatus.openrouter?.masked ? `Saved: ${keyStatus.openrouter.masked}` : 'sk-or-...'}
                       style={{ paddingRight: '45px' }}
                     />
                     <button
@@ -591,7 +687,7 @@ const Settings = () => {
                   <button
                     className="test-connection-btn"
                     onClick={handleTestOpenRouter}
-                    disabled={testingOpenrouter || !openrouterKeyInput.trim()}
+                    disabled={testingOpenrouter || !(openrouterKeyInput.trim() || keyStatus.openrouter?.configured)}
                   >
                     {testingOpenrouter ? 'Testing...' : 'Test Connection'}
                   </button>
@@ -607,7 +703,7 @@ const Settings = () => {
                 <div className="form-group model-select">
                   <label>Default Model</label>
                   <select value={openrouterModel} onChange={(e) => setOpenrouterModel(e.target.value)}>
-                    {OPENROUTER_MODELS.map((model) => (
+                    {modelsFor('openrouter', OPENROUTER_MODELS).map((model) => (
                       <option key={model.id} value={model.id}>{model.name}</option>
                     ))}
                   </select>
@@ -623,7 +719,7 @@ const Settings = () => {
                       type={showGeminiKey ? 'text' : 'password'}
                       value={geminiKeyInput}
                       onChange={(e) => setGeminiKeyInput(e.target.value)}
-                      placeholder="AIza..."
+                      placeholder={keyStatus.gemini?.masked ? `Saved: ${keyStatus.gemini.masked}` : 'AIza...'}
                       style={{ paddingRight: '45px' }}
                     />
                     <button
@@ -643,7 +739,7 @@ const Settings = () => {
                   <button
                     className="test-connection-btn"
                     onClick={handleTestGemini}
-                    disabled={testingGemini || !geminiKeyInput.trim()}
+                    disabled={testingGemini || !(geminiKeyInput.trim() || keyStatus.gemini?.configured)}
                   >
                     {testingGemini ? 'Testing...' : 'Test Connection'}
                   </button>
@@ -659,7 +755,7 @@ const Settings = () => {
                 <div className="form-group model-select">
                   <label>Default Model</label>
                   <select value={geminiModel} onChange={(e) => setGeminiModel(e.target.value)}>
-                    {GEMINI_MODELS.map((model) => (
+                    {modelsFor('gemini', GEMINI_MODELS).map((model) => (
                       <option key={model.id} value={model.id}>{model.name}</option>
                     ))}
                   </select>
@@ -675,7 +771,7 @@ const Settings = () => {
                       type={showMistralKey ? 'text' : 'password'}
                       value={mistralKeyInput}
                       onChange={(e) => setMistralKeyInput(e.target.value)}
-                      placeholder="Enter your Mistral API key"
+                      placeholder={keyStatus.mistral?.masked ? `Saved: ${keyStatus.mistral.masked}` : 'Enter your Mistral API key'}
                       style={{ paddingRight: '45px' }}
                     />
                     <button
@@ -695,7 +791,7 @@ const Settings = () => {
                   <button
                     className="test-connection-btn"
                     onClick={handleTestMistral}
-                    disabled={testingMistral || !mistralKeyInput.trim()}
+                    disabled={testingMistral || !(mistralKeyInput.trim() || keyStatus.mistral?.configured)}
                   >
                     {testingMistral ? 'Testing...' : 'Test Connection'}
                   </button>
@@ -711,7 +807,7 @@ const Settings = () => {
                 <div className="form-group model-select">
                   <label>Default Model</label>
                   <select value={mistralModel} onChange={(e) => setMistralModel(e.target.value)}>
-                    {MISTRAL_MODELS.map((model) => (
+                    {modelsFor('mistral', MISTRAL_MODELS).map((model) => (
                       <option key={model.id} value={model.id}>{model.name}</option>
                     ))}
                   </select>
@@ -727,7 +823,7 @@ const Settings = () => {
                       type={showCohereKey ? 'text' : 'password'}
                       value={cohereKeyInput}
                       onChange={(e) => setCohereKeyInput(e.target.value)}
-                      placeholder="Enter your Cohere API key"
+                      placeholder={keyStatus.cohere?.masked ? `Saved: ${keyStatus.cohere.masked}` : 'Enter your Cohere API key'}
                       style={{ paddingRight: '45px' }}
                     />
                     <button
@@ -747,7 +843,7 @@ const Settings = () => {
                   <button
                     className="test-connection-btn"
                     onClick={handleTestCohere}
-                    disabled={testingCohere || !cohereKeyInput.trim()}
+                    disabled={testingCohere || !(cohereKeyInput.trim() || keyStatus.cohere?.configured)}
                   >
                     {testingCohere ? 'Testing...' : 'Test Connection'}
                   </button>
@@ -763,7 +859,7 @@ const Settings = () => {
                 <div className="form-group model-select">
                   <label>Default Model</label>
                   <select value={cohereModel} onChange={(e) => setCohereModel(e.target.value)}>
-                    {COHERE_MODELS.map((model) => (
+                    {modelsFor('cohere', COHERE_MODELS).map((model) => (
                       <option key={model.id} value={model.id}>{model.name}</option>
                     ))}
                   </select>
@@ -809,7 +905,11 @@ const Settings = () => {
                     ))}
                   </select>
                 </div>
+
+                <OllamaModels baseUrl={ollamaUrl} />
               </div>
+
+              <CustomEndpoints />
             </div>
           )}
 
@@ -66,13 +66,13 @@ const ArticleEditor = ({ article, onSave, onCancel }) => {
       let enhancedContent;
       if (openaiKey) {
         enhancedContent = await callOpenAI(
-          openaiKey,
+          null,
           'gpt-3.5-turbo',
           [{ role: 'user', content: prompt }]
         );
       } else if (claudeKey) {
         enhancedContent = await callClaude(
-          claudeKey,
+          null,
           'claude-3-haiku-20240307',
           [{ role: 'user', content: prompt }]
         );
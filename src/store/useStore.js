@@ -27,7 +27,8 @@ const useStore = create((set) => ({
   setCurrentFileHandle: (handle) => set({ currentFileHandle: handle }),
   setCurrentFileContent: (content) => set({ currentFileContent: content }),
 
-  // AI Providers
+  // AI Providers - keys stay in the backend vault; these hold the masked
+  // hint (e.g. "sk-…3f9a") and are only used to tell which are configured
   openaiKey: '',
   claudeKey: '',
   openrouterKey: '',
@@ -1,4 +1,6 @@
 import { invoke } from '@tauri-apps/api/tauri';
+import { listen } from '@tauri-apps/api/event';
+import { validateProviderKey } from './tauriCommands';
 import { callOpenRouter, testOpenRouterConnection } from './providers/openrouter';
 import { callGemini, testGeminiConnection } from './providers/gemini';
 import { callMistral, testMistralConnection } from './providers/mistral';
@@ -7,10 +9,16 @@ import { callOllama, testOllamaConnection } from './providers/ollama';
 
 const isTauri = window.__TAURI_IPC__ !== undefined;
 
+// In the desktop app `apiKey` is only an override: when it is empty the
+// backend uses the key stored in its vault, so saved keys never reach JS.
+
 export const callOpenAI = async (apiKey, model, messages) => {
   try {
     if (isTauri) {
-      return await invoke('call_openai_api', { apiKey, model, messages });
+      const response = await invoke('chat', {
+        request: { provider: 'openai', model, messages, api_key: apiKey || undefined },
+      });
+      return response.content;
     } else {
       // Web Mode: Direct fetch
       const response = await fetch('https://api.openai.com/v1/chat/completions', {
@@ -34,7 +42,10 @@ export const callOpenAI = async (apiKey, model, messages) => {
 export const callClaude = async (apiKey, model, messages) => {
   try {
     if (isTauri) {
-      return await invoke('call_claude_api', { apiKey, model, messages });
+      const response = await invoke('chat', {
+        request: { provider: 'claude', model, messages, api_key: apiKey || undefined },
+      });
+      return response.content;
     } else {
       // Web Mode: Direct fetch (May hit CORS, warned user)
       const response = await fetch('https://api.anthropic.com/v1/messages', {
@@ -61,23 +72,11 @@ export const callClaude = async (apiKey, model, messages) => {
   }
 };
 
-export const testOpenAIConnection = async (apiKey) => {
-  try {
-    await callOpenAI(apiKey, 'gpt-3.5-turbo', [{ role: 'user', content: 'Hello' }]);
-    return true;
-  } catch (error) {
-    return false;
-  }
-};
+// Key checks list models instead of sending a chat, so they cost nothing.
+// Both resolve to { provider, valid, error, models }.
+export const testOpenAIConnection = (apiKey) => validateProviderKey('openai', apiKey);
 
-export const testClaudeConnection = async (apiKey) => {
-  try {
-    await callClaude(apiKey, 'claude-3-haiku-20240307', [{ role: 'user', content: 'Hello' }]);
-    return true;
-  } catch (error) {
-    return false;
-  }
-};
+export const testClaudeConnection = (apiKey) => validateProviderKey('claude', apiKey);
 
 // Export provider functions
 export { callOpenRouter, testOpenRouterConnection };
@@ -107,3 +106,245 @@ export const callAI = async (provider, apiKey, model, messages, baseUrl = null)
       throw new Error(`Unknown provider: ${provider}`);
   }
 };
+
+// Streaming AI call - onDelta receives each text fragment as it arrives.
+// Resolves with { content, finish_reason, usage } once the stream ends.
+// Pass a requestId to be able to abort the call with cancelAIRequest.
+// `options` are generation settings: { system, temperature, top_p,
+// max_tokens, stop, seed, json_mode }, all optional. With the response
+// cache enabled, bypassCache forces a fresh answer (which is then cached).
+export const streamAI = async (
+  provider,
+  apiKey,
+  model,
+  messages,
+  baseUrl = null,
+  onDelta = () => {},
+  requestId = crypto.randomUUID(),
+  options = {},
+  bypassCache = false
+) => {
+  if (!isTauri) {
+    const content = await callAI(provider, apiKey, model, messages, baseUrl);
+    onDelta(content);
+    return { content, finish_reason: null, usage: null };
+  }
+
+  const unlisten = await listen('chat-delta', (event) => {
+    if (event.payload.request_id === requestId) {
+      onDelta(event.payload.delta);
+    }
+  });
+
+  try {
+    return await invoke('chat_stream', {
+      requestId,
+      request: {
+        provider,
+        model,
+        messages,
+        options,
+        api_key: apiKey || undefined,
+        base_url: baseUrl || undefined,
+      },
+      bypassCache,
+    });
+  } catch (error) {
+    if (!isCancelled(error)) {
+      console.error('Streaming AI call failed:', error);
+    }
+    throw error;
+  } finally {
+    unlisten();
+  }
+};
+
+// Runs the workspace agent: the model may read and list files on its own,
+// while writes and commands wait for onApproval(request) to resolve true.
+// onStep receives each transcript entry ({ type: 'assistant' | 'tool_result', ... }).
+// Resolves with { content, finished, steps, usage, cost }; cancel with
+// cancelAIRequest(runId).
+export const runAgent = async (
+  provider,
+  model,
+  messages,
+  workspaceRoot,
+  {
+    baseUrl = null,
+    maxSteps = null,
+    options = {},
+    onStep = () => {},
+    onApproval = async () => false,
+    runId = crypto.randomUUID(),
+  } = {}
+) => {
+  if (!isTauri) {
+    throw new Error('Agent mode is only available in the desktop app');
+  }
+
+  const unlistenStep = await listen('agent-step', (event) => {
+    if (event.payload.run_id === runId) {
+      onStep(event.payload);
+    }
+  });
+  const unlistenApproval = await listen('agent-approval', async (event) => {
+    if (event.payload.run_id !== runId) return;
+    let approved = false;
+    try {
+      approved = await onApproval(event.payload);
+    } finally {
+      await respondAgentApproval(runId, event.payload.call_id, approved);
+    }
+  });
+
+  try {
+    return await invoke('run_agent', {
+      runId,
+      request: {
+        provider,
+        model,
+        messages,
+        options,
+        base_url: baseUrl || undefined,
+        workspace_root: workspaceRoot,
+        max_steps: maxSteps || undefined,
+      },
+    });
+  } catch (error) {
+    if (!isCancelled(error)) {
+      console.error('Agent run failed:', error);
+    }
+    throw error;
+  } finally {
+    unlistenStep();
+    unlistenApproval();
+  }
+};
+
+// Embeds the project's source files for semanticSearch; onProgress receives
+// { root, embedded, total }. Resolves with the index status; cancel with
+// cancelAIRequest(requestId).
+export const indexProject = async (
+  root,
+  provider,
+  { model = null, onProgress = () => {}, requestId = crypto.randomUUID() } = {}
+) => {
+  if (!isTauri) {
+    throw new Error('Project indexing is only available in the desktop app');
+  }
+
+  const unlisten = await listen('index-progress', (event) => {
+    if (event.payload.request_id === requestId) {
+      onProgress(event.payload);
+    }
+  });
+
+  try {
+    return await invoke('index_project', { requestId, root, provider, model });
+  } catch (error) {
+    if (!isCancelled(error)) {
+      console.error('Project indexing failed:', error);
+    }
+    throw error;
+  } finally {
+    unlisten();
+  }
+};
+
+// Runs a mixture-of-agents request in the backend: { strategy, prompt,
+// members: [{ provider, model, role, system_prompt, base_url }], aggregator,
+// timeout_secs }. onMember receives each member's result as it completes.
+// Resolves with { strategy, members, aggregated, aggregation_error, cost };
+// cancel with cancelAIRequest(runId). The run outlives the page that
+// started it - collect it again with awaitMOARun(runId).
+export const runMOAOnBackend = async (
+  request,
+  { onMember = () => {}, runId = crypto.randomUUID() } = {}
+) => {
+  if (!isTauri) {
+    throw new Error('Backend MOA runs are only available in the desktop app');
+  }
+
+  const unlisten = await listen('moa-member', (event) => {
+    if (event.payload.run_id === runId) {
+      onMember(event.payload);
+    }
+  });
+
+  try {
+    return await invoke('run_moa', { runId, request });
+  } catch (error) {
+    if (!isCancelled(error)) {
+      console.error('MOA run failed:', error);
+    }
+    throw error;
+  } finally {
+    unlisten();
+  }
+};
+
+// Result of a run started with runMOAOnBackend, waiting for it if it is
+// still going. Resolves with null when the backend no longer knows the run.
+export const awaitMOARun = async (runId) => {
+  if (!isTauri) return null;
+
+  let resolveDone;
+  const done = new Promise((resolve) => {
+    resolveDone = resolve;
+  });
+  const unlisten = await listen('moa-done', (event) => {
+    if (event.payload.run_id === runId) {
+      resolveDone(event.payload);
+    }
+  });
+
+  try {
+    const run = await invoke('get_moa_run', { runId });
+    if (!run) return null;
+    const finished = run.finished ? run : await done;
+    if (finished.error) throw finished.error;
+    return finished.result;
+  } finally {
+    unlisten();
+  }
+};
+
+export const respondAgentApproval = async (runId, callId, approved) => {
+  return await invoke('respond_agent_approval', { runId, callId, approved });
+};
+
+// Aborts an in-flight chat; the pending call rejects with a cancelled error
+export const cancelAIRequest = async (requestId) => {
+  if (!isTauri) return false;
+  return await invoke('cancel_request', { requestId });
+};
+
+export const isCancelled = (error) => error?.kind === 'cancelled';
+
+// Backend errors are { kind, message, ... } objects; turn them into a
+// sentence the user can act on.
+export const describeAIError = (error) => {
+  if (!error?.kind) {
+    return error?.message || String(error);
+  }
+  switch (error.kind) {
+    case 'auth':
+      return `Invalid or missing API key: ${error.message}`;
+    case 'rate_limited':
+      return error.retry_after
+        ? `Rate limited, try again in ${error.retry_after}s: ${error.message}`
+        : `Rate limited: ${error.message}`;
+    case 'quota':
+      return `Quota or credits exhausted: ${error.message}`;
+    case 'context_too_long':
+      return `Conversation is too long for this model: ${error.message}`;
+    case 'network':
+      return `Could not reach the provider: ${error.message}`;
+    case 'server':
+      return `Provider error: ${error.message}`;
+    case 'storage':
+      return `Could not save to the app data folder: ${error.message}`;
+    default:
+      return error.message;
+  }
+};
@@ -1,6 +1,8 @@
 // MOA Engine - Orchestrate multiple AI models for better results
 
-import { callAI } from '../aiClient';
+import { callAI, runMOAOnBackend, describeAIError } from '../aiClient';
+
+const isTauri = window.__TAURI_IPC__ !== undefined;
 
 // MOA Strategies
 export const MOA_STRATEGIES = {
@@ -190,9 +192,72 @@ export const runSpecializedMOA = async (prompt, agentRoles) => {
   return results;
 };
 
-// Main MOA runner
-export const runMOA = async (prompt, config) => {
+// Panel member config -> run_moa member. The backend supplies the saved
+// key unless one was typed into the panel.
+const toBackendMember = ({ provider, model, apiKey, baseUrl, systemPrompt }, role = null) => ({
+  provider,
+  model,
+  role,
+  system_prompt: systemPrompt || undefined,
+  api_key: apiKey || undefined,
+  base_url: baseUrl || undefined,
+});
+
+export const toBackendRequest = (prompt, config) => {
+  const { strategy, models = [], aggregatorConfig, agentRoles = {}, timeoutSecs } = config;
+  return {
+    strategy,
+    prompt,
+    members: strategy === MOA_STRATEGIES.SPECIALIZED
+      ? Object.entries(agentRoles).map(([role, roleConfig]) => toBackendMember(roleConfig, role))
+      : models.map((modelConfig) => toBackendMember(modelConfig)),
+    aggregator: aggregatorConfig ? toBackendMember(aggregatorConfig) : undefined,
+    timeout_secs: timeoutSecs || undefined,
+  };
+};
+
+// A backend member result in the shape the strategies above return
+export const toMemberResult = (member) => ({
+  provider: member.provider,
+  model: member.model,
+  response: member.response,
+  success: member.response != null,
+  error: member.error ? describeAIError(member.error) : undefined,
+  timestamp: new Date().toISOString(),
+});
+
+// A run_moa result in the shape the matching strategy above returns
+export const toStrategyResult = (result) => {
+  const members = result.members.map(toMemberResult);
+
+  switch (result.strategy) {
+    case MOA_STRATEGIES.VOTING:
+      return {
+        originalResponses: members,
+        aggregatedResponse: result.aggregated,
+        aggregationError: result.aggregation_error ? describeAIError(result.aggregation_error) : undefined,
+        strategy: 'voting',
+        timestamp: new Date().toISOString(),
+      };
+
+    case MOA_STRATEGIES.SPECIALIZED:
+      return Object.fromEntries(result.members.map((member, i) => [member.role, members[i]]));
+
+    default:
+      return members;
+  }
+};
+
+// Main MOA runner. In the desktop app the backend runs the strategy, so it
+// finishes even if the panel is closed; onMember then receives each
+// member's result as it completes and runId allows cancelling the run.
+export const runMOA = async (prompt, config, { onMember, runId } = {}) => {
   const { strategy, models, aggregatorConfig, agentRoles } = config;
+
+  if (isTauri) {
+    const result = await runMOAOnBackend(toBackendRequest(prompt, config), { onMember, runId });
+    return toStrategyResult(result);
+  }
   
   switch (strategy) {
     case MOA_STRATEGIES.PARALLEL:
@@ -1,4 +1,5 @@
 import { invoke } from '@tauri-apps/api/tauri';
+import { validateProviderKey } from '../tauriCommands';
 
 // Available Cohere models
 export const COHERE_MODELS = [
@@ -10,26 +11,15 @@ export const COHERE_MODELS = [
 
 export const callCohere = async (apiKey, model, messages) => {
   try {
-    const response = await invoke('call_cohere_api', {
-      apiKey,
-      model,
-      messages,
+    const response = await invoke('chat', {
+      request: { provider: 'cohere', model, messages, api_key: apiKey || undefined },
     });
-    return response;
+    return response.content;
   } catch (error) {
     console.error('Cohere API call failed:', error);
     throw error;
   }
 };
 
-export const testCohereConnection = async (apiKey) => {
-  try {
-    const messages = [
-      { role: 'user', content: 'Hello' }
-    ];
-    await callCohere(apiKey, 'command', messages);
-    return true;
-  } catch (error) {
-    return false;
-  }
-};
+// Lists models with the key instead of sending a chat, so the check is free
+export const testCohereConnection = (apiKey) => validateProviderKey('cohere', apiKey);
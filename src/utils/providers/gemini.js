@@ -1,4 +1,5 @@
 import { invoke } from '@tauri-apps/api/tauri';
+import { validateProviderKey } from '../tauriCommands';
 
 // Available Gemini models
 export const GEMINI_MODELS = [
@@ -10,26 +11,15 @@ export const GEMINI_MODELS = [
 
 export const callGemini = async (apiKey, model, messages) => {
   try {
-    const response = await invoke('call_gemini_api', {
-      apiKey,
-      model,
-      messages,
+    const response = await invoke('chat', {
+      request: { provider: 'gemini', model, messages, api_key: apiKey || undefined },
     });
-    return response;
+    return response.content;
   } catch (error) {
     console.error('Gemini API call failed:', error);
     throw error;
   }
 };
 
-export const testGeminiConnection = async (apiKey) => {
-  try {
-    const messages = [
-      { role: 'user', content: 'Hello' }
-    ];
-    await callGemini(apiKey, 'gemini-pro', messages);
-    return true;
-  } catch (error) {
-    return false;
-  }
-};
+// Lists models with the key instead of sending a chat, so the check is free
+export const testGeminiConnection = (apiKey) => validateProviderKey('gemini', apiKey);
@@ -1,4 +1,5 @@
 import { invoke } from '@tauri-apps/api/tauri';
+import { validateProviderKey } from '../tauriCommands';
 
 // Available Mistral models
 export const MISTRAL_MODELS = [
@@ -12,26 +13,15 @@ export const MISTRAL_MODELS = [
 
 export const callMistral = async (apiKey, model, messages) => {
   try {
-    const response = await invoke('call_mistral_api', {
-      apiKey,
-      model,
-      messages,
+    const response = await invoke('chat', {
+      request: { provider: 'mistral', model, messages, api_key: apiKey || undefined },
     });
-    return response;
+    return response.content;
   } catch (error) {
     console.error('Mistral API call failed:', error);
     throw error;
   }
 };
 
-export const testMistralConnection = async (apiKey) => {
-  try {
-    const messages = [
-      { role: 'user', content: 'Hello' }
-    ];
-    await callMistral(apiKey, 'mistral-small-latest', messages);
-    return true;
-  } catch (error) {
-    return false;
-  }
-};
+// Lists models with the key instead of sending a chat, so the check is free
+export const testMistralConnection = (apiKey) => validateProviderKey('mistral', apiKey);
@@ -1,4 +1,6 @@
 import { invoke } from '@tauri-apps/api/tauri';
+import { listen } from '@tauri-apps/api/event';
+import { validateProviderKey } from '../tauriCommands';
 
 // Default Ollama models (can be auto-detected)
 export const DEFAULT_OLLAMA_MODELS = [
@@ -14,12 +16,10 @@ export const DEFAULT_OLLAMA_MODELS = [
 
 export const callOllama = async (baseUrl, model, messages) => {
   try {
-    const response = await invoke('call_ollama_api', {
-      baseUrl,
-      model,
-      messages,
+    const response = await invoke('chat', {
+      request: { provider: 'ollama', model, messages, base_url: baseUrl },
     });
-    return response;
+    return response.content;
   } catch (error) {
     console.error('Ollama API call failed:', error);
     throw error;
@@ -41,11 +41,67 @@ export const getOllamaModels = async (baseUrl) => {
   }
 };
 
-export const testOllamaConnection = async (baseUrl) => {
+export const testOllamaConnection = (baseUrl) => validateProviderKey('ollama', null, baseUrl);
+
+// Resolves to { base_url, reachable, version, models, model_available, error };
+// base_url comes back normalized (scheme, port, no trailing slash)
+export const getOllamaStatus = async (baseUrl = null, model = null) => {
+  try {
+    return await invoke('ollama_status', { baseUrl, model });
+  } catch (error) {
+    console.error('Failed to check Ollama status:', error);
+    throw error;
+  }
+};
+
+// Downloads a model; onProgress receives { status, digest, total, completed }
+// per status line. Cancel with cancelAIRequest(requestId).
+export const pullOllamaModel = async (
+  name,
+  { baseUrl = null, onProgress = () => {}, requestId = crypto.randomUUID() } = {}
+) => {
+  const unlisten = await listen('ollama-pull-progress', (event) => {
+    if (event.payload.request_id === requestId) {
+      onProgress(event.payload);
+    }
+  });
+
+  try {
+    return await invoke('pull_ollama_model', { requestId, name, baseUrl });
+  } catch (error) {
+    console.error(`Failed to pull ${name}:`, error);
+    throw error;
+  } finally {
+    unlisten();
+  }
+};
+
+export const deleteOllamaModel = async (name, baseUrl = null) => {
+  try {
+    return await invoke('delete_ollama_model', { name, baseUrl });
+  } catch (error) {
+    console.error(`Failed to delete ${name}:`, error);
+    throw error;
+  }
+};
+
+// Resolves to { family, parameter_size, quantization_level, context_length,
+// parameters, template, system, license }
+export const showOllamaModel = async (name, baseUrl = null) => {
   try {
-    await getOllamaModels(baseUrl);
-    return true;
+    return await invoke('show_ollama_model', { name, baseUrl });
   } catch (error) {
-    return false;
+    console.error(`Failed to show ${name}:`, error);
+    throw error;
+  }
+};
+
+// Resolves to [{ name, size, size_vram, parameter_size, quantization_level, expires_at }]
+export const listRunningOllamaModels = async (baseUrl = null) => {
+  try {
+    return await invoke('list_running_ollama_models', { baseUrl });
+  } catch (error) {
+    console.error('Failed to list running Ollama models:', error);
+    throw error;
   }
 };
@@ -1,4 +1,5 @@
 import { invoke } from '@tauri-apps/api/tauri';
+import { validateProviderKey } from '../tauriCommands';
 
 // Available OpenRouter models
 export const OPENROUTER_MODELS = [
@@ -26,26 +27,15 @@ export const OPENROUTER_MODELS = [
 
 export const callOpenRouter = async (apiKey, model, messages) => {
   try {
-    const response = await invoke('call_openrouter_api', {
-      apiKey,
-      model,
-      messages,
+    const response = await invoke('chat', {
+      request: { provider: 'openrouter', model, messages, api_key: apiKey || undefined },
     });
-    return response;
+    return response.content;
   } catch (error) {
     console.error('OpenRouter API call failed:', error);
     throw error;
   }
 };
 
-export const testOpenRouterConnection = async (apiKey) => {
-  try {
-    const messages = [
-      { role: 'user', content: 'Hello' }
-    ];
-    await callOpenRouter(apiKey, 'openai/gpt-3.5-turbo', messages);
-    return true;
-  } catch (error) {
-    return false;
-  }
-};
+// Lists models with the key instead of sending a chat, so the check is free
+export const testOpenRouterConnection = (apiKey) => validateProviderKey('openrouter', apiKey);
@@ -36,12 +36,136 @@ export const saveAPIKey = async (provider, key) => {
   }
 };
 
-export const getAPIKey = async (provider) => {
+// Returns { provider, configured, masked } - the key itself never leaves the backend
+export const getAPIKeyStatus = async (provider) => {
   try {
-    return await invoke('get_api_key', { provider });
+    return await invoke('get_api_key_status', { provider });
   } catch (error) {
-    console.error('Failed to get API key:', error);
-    return '';
+    console.error('Failed to get API key status:', error);
+    return { provider, configured: false, masked: null };
+  }
+};
+
+// Resolves to { provider, valid, error, models }; uses the saved key when
+// apiKey is omitted. Never throws, so settings forms can show the error.
+export const validateProviderKey = async (provider, apiKey = null, baseUrl = null) => {
+  try {
+    return await invoke('validate_provider_key', { provider, apiKey, baseUrl });
+  } catch (error) {
+    console.error('Failed to validate API key:', error);
+    return { provider, valid: false, error, models: [] };
+  }
+};
+
+// Resolves to { provider, models: [{ id, name, context_window, modality, pricing }],
+// fetched_at, stale }; cached by the backend unless refresh is set
+export const listModels = async (provider, refresh = false) => {
+  try {
+    return await invoke('list_models', { provider, refresh });
+  } catch (error) {
+    console.error(`Failed to list ${provider} models:`, error);
+    throw error;
+  }
+};
+
+export const deleteAPIKey = async (provider) => {
+  try {
+    return await invoke('delete_api_key', { provider });
+  } catch (error) {
+    console.error('Failed to delete API key:', error);
+    throw error;
+  }
+};
+
+export const listAPIKeyProviders = async () => {
+  try {
+    return await invoke('list_api_key_providers');
+  } catch (error) {
+    console.error('Failed to list API key providers:', error);
+    return [];
+  }
+};
+
+export const listEndpointProfiles = async () => {
+  try {
+    return await invoke('list_endpoint_profiles');
+  } catch (error) {
+    console.error('Failed to list custom endpoints:', error);
+    throw error;
+  }
+};
+
+export const saveEndpointProfile = async (profile) => {
+  try {
+    return await invoke('save_endpoint_profile', { profile });
+  } catch (error) {
+    console.error('Failed to save custom endpoint:', error);
+    throw error;
+  }
+};
+
+export const deleteEndpointProfile = async (id) => {
+  try {
+    return await invoke('delete_endpoint_profile', { id });
+  } catch (error) {
+    console.error('Failed to delete custom endpoint:', error);
+    throw error;
+  }
+};
+
+// The Ollama URL is a plain setting, readable while the vault is locked;
+// null means the default local server
+export const getOllamaBaseUrl = async () => {
+  try {
+    return await invoke('get_ollama_base_url');
+  } catch (error) {
+    console.error('Failed to get Ollama base URL:', error);
+    return null;
+  }
+};
+
+export const setOllamaBaseUrl = async (baseUrl) => {
+  try {
+    return await invoke('set_ollama_base_url', { baseUrl });
+  } catch (error) {
+    console.error('Failed to save Ollama base URL:', error);
+    throw error;
+  }
+};
+
+export const getVaultStatus = async () => {
+  try {
+    return await invoke('vault_status');
+  } catch (error) {
+    console.error('Failed to get vault status:', error);
+    throw error;
+  }
+};
+
+export const unlockVault = async (password = null) => {
+  try {
+    return await invoke('unlock_vault', { password });
+  } catch (error) {
+    console.error('Failed to unlock vault:', error);
+    throw error;
+  }
+};
+
+export const lockVault = async () => {
+  try {
+    return await invoke('lock_vault');
+  } catch (error) {
+    console.error('Failed to lock vault:', error);
+    throw error;
+  }
+};
+
+export const setVaultPassword = async (password = null) => {
+  try {
+    return await invoke('set_vault_password', { password });
+  } catch (error) {
+    console.error('Failed to set vault password:', error);
+    throw error;
   }
 };
 
@@ -126,3 +250,271 @@ export const executeCode = async (command, workingDir) => {
   }
 };
 
+
+export const getRetryPolicy = async () => {
+  try {
+    return await invoke('get_retry_policy');
+  } catch (error) {
+    console.error('Failed to get retry policy:', error);
+    throw error;
+  }
+};
+
+export const setRetryPolicy = async (policy) => {
+  try {
+    return await invoke('set_retry_policy', { policy });
+  } catch (error) {
+    console.error('Failed to save retry policy:', error);
+    throw error;
+  }
+};
+
+// Resolves to { [provider]: { requests_per_minute, tokens_per_minute,
+// max_concurrent } }; unset fields are null and mean no limit
+export const getRateLimits = async () => {
+  try {
+    return await invoke('get_rate_limits');
+  } catch (error) {
+    console.error('Failed to get rate limits:', error);
+    throw error;
+  }
+};
+
+export const setRateLimits = async (limits) => {
+  try {
+    return await invoke('set_rate_limits', { limits });
+  } catch (error) {
+    console.error('Failed to save rate limits:', error);
+    throw error;
+  }
+};
+
+// Resolves to [{ provider, queued, in_flight, requests_last_minute, tokens_last_minute }]
+export const getRateLimitStatus = async () => {
+  try {
+    return await invoke('get_rate_limit_status');
+  } catch (error) {
+    console.error('Failed to get rate limit status:', error);
+    throw error;
+  }
+};
+
+// Resolves to { strategy, keep_last, reserve_tokens, summary_provider, summary_model };
+// strategy is 'off', 'drop_oldest', 'keep_last' or 'summarize'
+export const getContextPolicy = async () => {
+  try {
+    return await invoke('get_context_policy');
+  } catch (error) {
+    console.error('Failed to get context policy:', error);
+    throw error;
+  }
+};
+
+export const setContextPolicy = async (policy) => {
+  try {
+    return await invoke('set_context_policy', { policy });
+  } catch (error) {
+    console.error('Failed to save context policy:', error);
+    throw error;
+  }
+};
+
+export const getCacheSettings = async () => {
+  try {
+    return await invoke('get_cache_settings');
+  } catch (error) {
+    console.error('Failed to get cache settings:', error);
+    throw error;
+  }
+};
+
+export const setCacheSettings = async (settings) => {
+  try {
+    return await invoke('set_cache_settings', { settings });
+  } catch (error) {
+    console.error('Failed to save cache settings:', error);
+    throw error;
+  }
+};
+
+// Resolves to { entries, bytes }
+export const getAICacheStats = async () => {
+  try {
+    return await invoke('get_ai_cache_stats');
+  } catch (error) {
+    console.error('Failed to get AI cache stats:', error);
+    throw error;
+  }
+};
+
+// Resolves to the { entries, bytes } that were removed
+export const clearAICache = async () => {
+  try {
+    return await invoke('clear_ai_cache');
+  } catch (error) {
+    console.error('Failed to clear AI cache:', error);
+    throw error;
+  }
+};
+
+// Resolves to { id, title, provider, model, created_at, updated_at, messages, usage }
+export const createConversation = async (provider, model, title = null) => {
+  try {
+    return await invoke('create_conversation', { provider, model, title });
+  } catch (error) {
+    console.error('Failed to create conversation:', error);
+    throw error;
+  }
+};
+
+// Summaries without messages, most recently updated first
+export const listConversations = async () => {
+  try {
+    return await invoke('list_conversations');
+  } catch (error) {
+    console.error('Failed to list conversations:', error);
+    throw error;
+  }
+};
+
+export const loadConversation = async (id) => {
+  try {
+    return await invoke('load_conversation', { id });
+  } catch (error) {
+    console.error('Failed to load conversation:', error);
+    throw error;
+  }
+};
+
+// `usage` is that of the reply, which should be the last message
+export const appendConversationMessages = async (id, messages, { provider = null, model = null, usage = null } = {}) => {
+  try {
+    return await invoke('append_conversation_messages', { id, messages, provider, model, usage });
+  } catch (error) {
+    console.error('Failed to save conversation messages:', error);
+    throw error;
+  }
+};
+
+export const renameConversation = async (id, title) => {
+  try {
+    return await invoke('rename_conversation', { id, title });
+  } catch (error) {
+    console.error('Failed to rename conversation:', error);
+    throw error;
+  }
+};
+
+export const deleteConversation = async (id) => {
+  try {
+    return await invoke('delete_conversation', { id });
+  } catch (error) {
+    console.error('Failed to delete conversation:', error);
+    throw error;
+  }
+};
+
+// Resolves to [{ conversation, message_index, snippet }]
+export const searchConversations = async (query) => {
+  try {
+    return await invoke('search_conversations', { query });
+  } catch (error) {
+    console.error('Failed to search conversations:', error);
+    throw error;
+  }
+};
+
+// format is 'markdown' or 'json'; also writes the file when path is given
+export const exportConversation = async (id, format = 'markdown', path = null) => {
+  try {
+    return await invoke('export_conversation', { id, format, path });
+  } catch (error) {
+    console.error('Failed to export conversation:', error);
+    throw error;
+  }
+};
+
+// One vector per text; model defaults to the provider's embedding model,
+// purpose is 'document' or 'query'
+export const embedTexts = async (provider, texts, { model = null, purpose = 'document' } = {}) => {
+  try {
+    return await invoke('embed_texts', { provider, model, texts, purpose });
+  } catch (error) {
+    console.error('Failed to embed texts:', error);
+    throw error;
+  }
+};
+
+// Resolves to the saved index status of a project, or null if it was never indexed
+export const openProjectIndex = async (root) => {
+  try {
+    return await invoke('open_project_index', { root });
+  } catch (error) {
+    console.error('Failed to open project index:', error);
+    throw error;
+  }
+};
+
+export const clearProjectIndex = async (root) => {
+  try {
+    return await invoke('clear_project_index', { root });
+  } catch (error) {
+    console.error('Failed to clear project index:', error);
+    throw error;
+  }
+};
+
+// Resolves to [{ path, start_line, end_line, text, score }], best match first
+export const semanticSearch = async (query, k = 5) => {
+  try {
+    return await invoke('semantic_search', { query, k });
+  } catch (error) {
+    console.error('Failed to search project index:', error);
+    throw error;
+  }
+};
+
+export const getUsageReport = async (days = null) => {
+  try {
+    return await invoke('get_usage_report', { days });
+  } catch (error) {
+    console.error('Failed to get usage report:', error);
+    throw error;
+  }
+};
+
+export const getPriceTable = async () => {
+  try {
+    return await invoke('get_price_table');
+  } catch (error) {
+    console.error('Failed to get price table:', error);
+    throw error;
+  }
+};
+
+export const setPriceTable = async (prices) => {
+  try {
+    return await invoke('set_price_table', { prices });
+  } catch (error) {
+    console.error('Failed to save price table:', error);
+    throw error;
+  }
+};
+
+export const getNetworkConfig = async () => {
+  try {
+    return await invoke('get_network_config');
+  } catch (error) {
+    console.error('Failed to get network config:', error);
+    throw error;
+  }
+};
+
+export const setNetworkConfig = async (config) => {
+  try {
+    return await invoke('set_network_config', { config });
+  } catch (error) {
+    console.error('Failed to save network config:', error);
+    throw error;
+  }
+};